@@ -0,0 +1,79 @@
+//! Compare the throughput of merging inputs that are memory mapped and inputs read into memory
+//! (`--no-mmap`), on inputs generated in a temporary directory.
+//!
+//! Run with `cargo bench --bench mmap_input`.
+
+use std::{io::Write, path::Path, time::Instant};
+use vcd_merger::{
+    find_sections, parse_headers, reset_identifiers, write_output, Header, ParseOptions,
+    WriteOptions,
+};
+
+const SIGNALS: usize = 64;
+const TIMESTAMPS: u64 = 100_000;
+const ITERATIONS: usize = 5;
+
+/// Write a input with `SIGNALS` signals that all change at each of `TIMESTAMPS` timestamps.
+fn generate_input(path: &Path, name: &str) -> std::io::Result<()> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "$timescale 1ns $end")?;
+    writeln!(out, "$scope module {} $end", name)?;
+    for i in 0..SIGNALS {
+        writeln!(out, "$var wire 8 {} s{} $end", (b'!' + i as u8) as char, i)?;
+    }
+    writeln!(out, "$upscope $end")?;
+    writeln!(out, "$enddefinitions $end")?;
+    for time in 0..TIMESTAMPS {
+        writeln!(out, "#{}", time * 2)?;
+        for i in 0..SIGNALS {
+            let value = (time as usize + i) as u8;
+            writeln!(out, "b{:b} {}", value, (b'!' + i as u8) as char)?;
+        }
+    }
+    out.flush()
+}
+
+/// Read and merge the inputs into `output`, with `--reorder`, returning the time taken.
+fn merge(inputs: &[&Path], output: &Path, no_mmap: bool) -> f64 {
+    let start = Instant::now();
+
+    reset_identifiers();
+    let mut header = Header::default();
+    let options = ParseOptions {
+        no_mmap,
+        ..Default::default()
+    };
+    let vcds = parse_headers(inputs.iter(), &mut header, &options).unwrap();
+    let sections = find_sections(&vcds, 1, |_| {}).unwrap();
+    let options = WriteOptions::default();
+    write_output(output, header, &vcds, sections, &options, |_| {}).unwrap();
+
+    start.elapsed().as_secs_f64()
+}
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::temp_dir().join(format!("vcd-merger-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+
+    let a = dir.join("a.vcd");
+    let b = dir.join("b.vcd");
+    generate_input(&a, "a")?;
+    generate_input(&b, "b")?;
+    let size = std::fs::metadata(&a)?.len() + std::fs::metadata(&b)?.len();
+    let output = dir.join("out.vcd");
+
+    for (name, no_mmap) in [("mmap", false), ("no-mmap", true)] {
+        let mut best = f64::INFINITY;
+        for _ in 0..ITERATIONS {
+            best = best.min(merge(&[&a, &b], &output, no_mmap));
+        }
+        eprintln!(
+            "{:>8}: {:.3}s, {:.1} MiB/s of input",
+            name,
+            best,
+            size as f64 / best / (1 << 20) as f64
+        );
+    }
+
+    std::fs::remove_dir_all(&dir)
+}
@@ -0,0 +1,77 @@
+//! Compare the throughput of writing the output with buffered writes and through a memory map
+//! (`--mmap-output`), on inputs generated in a temporary directory.
+//!
+//! Run with `cargo bench --bench mmap_output`.
+
+use std::{io::Write, path::Path, time::Instant};
+use vcd_merger::{file_sections, parse_headers, write_output, Header, ParseOptions, WriteOptions};
+
+const SIGNALS: usize = 64;
+const TIMESTAMPS: u64 = 100_000;
+const ITERATIONS: usize = 5;
+
+/// Write a input with `SIGNALS` signals that all change at each of `TIMESTAMPS` timestamps.
+fn generate_input(path: &Path, name: &str) -> std::io::Result<()> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "$timescale 1ns $end")?;
+    writeln!(out, "$scope module {} $end", name)?;
+    for i in 0..SIGNALS {
+        writeln!(out, "$var wire 8 {} s{} $end", (b'!' + i as u8) as char, i)?;
+    }
+    writeln!(out, "$upscope $end")?;
+    writeln!(out, "$enddefinitions $end")?;
+    for time in 0..TIMESTAMPS {
+        writeln!(out, "#{}", time * 2)?;
+        for i in 0..SIGNALS {
+            let value = (time as usize + i) as u8;
+            writeln!(out, "b{:b} {}", value, (b'!' + i as u8) as char)?;
+        }
+    }
+    out.flush()
+}
+
+/// Merge the inputs into `output`, returning the size of the output and the time taken.
+fn merge(inputs: &[&Path], output: &Path, mmap_output: bool) -> (u64, f64) {
+    let start = Instant::now();
+
+    let mut header = Header::default();
+    let vcds = parse_headers(inputs.iter(), &mut header, &ParseOptions::default()).unwrap();
+    let sections = file_sections(&vcds).unwrap();
+    let options = WriteOptions {
+        mmap_output,
+        ..Default::default()
+    };
+    write_output(output, header, &vcds, sections, &options, |_| {}).unwrap();
+
+    let elapsed = start.elapsed().as_secs_f64();
+    (std::fs::metadata(output).unwrap().len(), elapsed)
+}
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::temp_dir().join(format!("vcd-merger-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+
+    let a = dir.join("a.vcd");
+    let b = dir.join("b.vcd");
+    generate_input(&a, "a")?;
+    generate_input(&b, "b")?;
+    let output = dir.join("out.vcd");
+
+    for (name, mmap_output) in [("buffered", false), ("mmap", true)] {
+        let mut best = f64::INFINITY;
+        let mut size = 0;
+        for _ in 0..ITERATIONS {
+            let (len, elapsed) = merge(&[&a, &b], &output, mmap_output);
+            size = len;
+            best = best.min(elapsed);
+        }
+        eprintln!(
+            "{:>8}: {:.3}s, {:.1} MiB/s of output",
+            name,
+            best,
+            size as f64 / best / (1 << 20) as f64
+        );
+    }
+
+    std::fs::remove_dir_all(&dir)
+}
@@ -0,0 +1,87 @@
+//! Measure the throughput of merging the changes of wide, frequently changing buses, where the
+//! cost per line of writing vector changes dominates.
+//!
+//! Run with `cargo bench --bench wide_bus`.
+
+use std::{io::Write, path::Path, time::Instant};
+use vcd_merger::{file_sections, parse_headers, write_output, Header, ParseOptions, WriteOptions};
+
+const WIDTH: usize = 1024;
+const BUSES: usize = 4;
+const TIMESTAMPS: u64 = 20_000;
+const ITERATIONS: usize = 10;
+
+/// Write a input with `BUSES` buses of `WIDTH` bits that all change at each of `TIMESTAMPS`
+/// timestamps.
+fn generate_input(path: &Path, name: &str) -> std::io::Result<()> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "$timescale 1ns $end")?;
+    writeln!(out, "$scope module {} $end", name)?;
+    for i in 0..BUSES {
+        writeln!(
+            out,
+            "$var wire {} {} bus{} $end",
+            WIDTH,
+            (b'!' + i as u8) as char,
+            i
+        )?;
+    }
+    writeln!(out, "$upscope $end")?;
+    writeln!(out, "$enddefinitions $end")?;
+    let patterns = ["01", "10", "0011", "xz01"].map(|x| x.repeat(WIDTH / x.len()));
+    for time in 0..TIMESTAMPS {
+        writeln!(out, "#{}", time * 2)?;
+        for i in 0..BUSES {
+            let value = &patterns[(time as usize + i) % patterns.len()];
+            writeln!(out, "b{} {}", value, (b'!' + i as u8) as char)?;
+        }
+    }
+    out.flush()
+}
+
+/// Merge the inputs into `output`, returning the size of the output and the time taken.
+fn merge(inputs: &[&Path], output: &Path) -> (u64, f64) {
+    let start = Instant::now();
+
+    let mut header = Header::default();
+    let vcds = parse_headers(inputs.iter(), &mut header, &ParseOptions::default()).unwrap();
+    let sections = file_sections(&vcds).unwrap();
+    write_output(
+        output,
+        header,
+        &vcds,
+        sections,
+        &WriteOptions::default(),
+        |_| {},
+    )
+    .unwrap();
+
+    let elapsed = start.elapsed().as_secs_f64();
+    (std::fs::metadata(output).unwrap().len(), elapsed)
+}
+
+fn main() -> std::io::Result<()> {
+    let dir = std::env::temp_dir().join(format!("vcd-merger-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+
+    let a = dir.join("a.vcd");
+    let b = dir.join("b.vcd");
+    generate_input(&a, "a")?;
+    generate_input(&b, "b")?;
+    let output = dir.join("out.vcd");
+
+    let mut best = f64::INFINITY;
+    let mut size = 0;
+    for _ in 0..ITERATIONS {
+        let (len, elapsed) = merge(&[&a, &b], &output);
+        size = len;
+        best = best.min(elapsed);
+    }
+    eprintln!(
+        "wide bus: {:.3}s, {:.1} MiB/s of output",
+        best,
+        size as f64 / best / (1 << 20) as f64
+    );
+
+    std::fs::remove_dir_all(&dir)
+}
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The header parser must never panic, only return errors.
+fuzz_target!(|data: &[u8]| {
+    let _ = vcd_merger::fuzz_parse_header(data);
+});
@@ -0,0 +1,155 @@
+//! Checkpoints of the merge, to resume it after an interruption.
+
+use crate::{Section, Vcd};
+use std::{
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// The state of a merge after some of its output was written. Sections borrow from the memory
+/// mapped inputs, so they are saved as offsets into the inputs, and are only valid for the same
+/// inputs, parsed with the same options.
+pub struct Checkpoint {
+    /// The length of each input, to detect inputs that changed since the checkpoint.
+    input_lens: Vec<u64>,
+    /// The length of the output written before the checkpoint.
+    pub(crate) output_len: u64,
+    /// The last timestamp written to the output, if any.
+    pub(crate) last_timestamp: Option<u64>,
+    sections: Vec<SavedSection>,
+}
+
+/// A [`Section`] that was not merged yet.
+struct SavedSection {
+    /// The index of the input of the section.
+    vcd: usize,
+    value: u64,
+    /// The start and end of the section in the input.
+    start: usize,
+    end: usize,
+}
+
+impl Checkpoint {
+    pub(crate) fn new<'a>(
+        vcds: &[Vcd],
+        output_len: u64,
+        last_timestamp: Option<u64>,
+        sections: impl Iterator<Item = &'a Section<'a>>,
+    ) -> Self {
+        let sections = sections
+            .map(|section| {
+                let vcd = vcds
+                    .iter()
+                    .position(|vcd| std::ptr::eq(vcd, section.vcd))
+                    .unwrap();
+                let start = section.section.as_ptr() as usize - section.vcd.file.as_ptr() as usize;
+                SavedSection {
+                    vcd,
+                    value: section.value,
+                    start,
+                    end: start + section.section.len(),
+                }
+            })
+            .collect();
+        Self {
+            input_lens: vcds.iter().map(|vcd| vcd.file.len() as u64).collect(),
+            output_len,
+            last_timestamp,
+            sections,
+        }
+    }
+
+    /// Write the checkpoint to `path`, replacing the previous one only after it is complete.
+    pub(crate) fn write(&self, path: &Path) -> std::io::Result<()> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut out = BufWriter::new(std::fs::File::create(&tmp_path)?);
+        writeln!(out, "vcd-merger checkpoint")?;
+        for len in &self.input_lens {
+            writeln!(out, "input {}", len)?;
+        }
+        writeln!(out, "output {}", self.output_len)?;
+        if let Some(timestamp) = self.last_timestamp {
+            writeln!(out, "timestamp {}", timestamp)?;
+        }
+        for s in &self.sections {
+            writeln!(out, "section {} {} {} {}", s.vcd, s.value, s.start, s.end)?;
+        }
+        out.into_inner()?.sync_all()?;
+
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Read a checkpoint written by a merge with [`WriteOptions::checkpoint_path`](crate::WriteOptions::checkpoint_path).
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let invalid = |line: &str| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid checkpoint line {:?}", line),
+            )
+        };
+
+        let mut lines = BufReader::new(std::fs::File::open(path)?).lines();
+        match lines.next().transpose()? {
+            Some(line) if line == "vcd-merger checkpoint" => {}
+            line => return Err(invalid(line.as_deref().unwrap_or_default())),
+        }
+
+        let mut checkpoint = Checkpoint {
+            input_lens: Vec::new(),
+            output_len: 0,
+            last_timestamp: None,
+            sections: Vec::new(),
+        };
+        for line in lines {
+            let line = line?;
+            let (key, numbers) = line.split_once(' ').ok_or_else(|| invalid(&line))?;
+            let numbers = numbers
+                .split(' ')
+                .map(|x| x.parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid(&line))?;
+            match (key, numbers.as_slice()) {
+                ("input", &[len]) => checkpoint.input_lens.push(len),
+                ("output", &[len]) => checkpoint.output_len = len,
+                ("timestamp", &[timestamp]) => checkpoint.last_timestamp = Some(timestamp),
+                ("section", &[vcd, value, start, end]) => checkpoint.sections.push(SavedSection {
+                    vcd: vcd as usize,
+                    value,
+                    start: start as usize,
+                    end: end as usize,
+                }),
+                _ => return Err(invalid(&line)),
+            }
+        }
+        Ok(checkpoint)
+    }
+
+    /// The sections that were not merged at the checkpoint, in the same order. Fails if the
+    /// inputs are not the same as the ones of the checkpoint.
+    pub fn sections<'a>(&self, vcds: &'a [Vcd]) -> Result<Vec<Section<'a>>, String> {
+        let input_lens = vcds.iter().map(|vcd| vcd.file.len() as u64);
+        if !input_lens.eq(self.input_lens.iter().copied()) {
+            return Err("the inputs changed since the checkpoint".to_string());
+        }
+
+        self.sections
+            .iter()
+            .map(|s| {
+                let vcd = vcds
+                    .get(s.vcd)
+                    .ok_or("invalid input index in the checkpoint")?;
+                let section = vcd
+                    .file
+                    .get(s.start..s.end)
+                    .ok_or("invalid section in the checkpoint")?;
+                Ok(Section {
+                    value: s.value,
+                    section,
+                    vcd,
+                })
+            })
+            .collect()
+    }
+}
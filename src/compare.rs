@@ -0,0 +1,261 @@
+//! Semantic comparison of two VCD files.
+
+use crate::{
+    parse_header_data, parse_time, split_value_change, Declaration, Declarations, Header, IdCode,
+    InputData, ParseOptions,
+};
+use fxhash::FxHashMap as HashMap;
+use std::{collections::BTreeMap, path::Path};
+
+/// The first semantic difference found by [`compare`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Difference {
+    /// A signal declared in only one of the files.
+    MissingSignal {
+        path: String,
+        /// If the signal is declared only in the reference, instead of only in the output.
+        in_reference: bool,
+    },
+    /// A timestamp smaller than the one before it.
+    Unsorted {
+        /// The time of the timestamp, in femtoseconds.
+        time: u64,
+        in_reference: bool,
+    },
+    /// A signal that has different value changes at the same time.
+    Value {
+        /// The time of the changes, in femtoseconds.
+        time: u64,
+        path: String,
+        output: Vec<String>,
+        reference: Vec<String>,
+    },
+}
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difference::MissingSignal { path, in_reference } => {
+                let file = if *in_reference { "reference" } else { "output" };
+                write!(f, "signal {} is only declared in the {}", path, file)
+            }
+            Difference::Unsorted { time, in_reference } => {
+                let file = if *in_reference { "reference" } else { "output" };
+                write!(f, "the {} goes back in time to {}fs", file, time)
+            }
+            Difference::Value {
+                time,
+                path,
+                output,
+                reference,
+            } => {
+                let changes = |x: &[String]| match x {
+                    [] => "no change".to_string(),
+                    _ => x.join(", "),
+                };
+                write!(
+                    f,
+                    "at {}fs, {} has {} in the output, but {} in the reference",
+                    time,
+                    path,
+                    changes(output),
+                    changes(reference)
+                )
+            }
+        }
+    }
+}
+
+/// The signals of a VCD file, and its data to read the value changes from.
+struct Dump {
+    data: InputData,
+    /// The identifier of each signal, by hierarchical name.
+    signals: BTreeMap<String, IdCode>,
+    end_of_definitions: usize,
+    /// The timescale of the file, in femtoseconds.
+    timescale: u64,
+}
+impl Dump {
+    fn read(path: &Path) -> Result<Dump, Box<dyn std::error::Error>> {
+        let file = std::fs::File::open(path)?;
+        let data = InputData::Mmap(unsafe { memmap2::Mmap::map(&file)? }).decompressed()?;
+
+        // the identifiers are only read, so the ones generated by a merge don't depend on them.
+        let declarations = Declarations {
+            keep_ids: true,
+            read_only: true,
+            ..Default::default()
+        };
+        let parsed = parse_header_data(
+            &data,
+            &mut Header::default(),
+            &ParseOptions::default(),
+            declarations,
+        )?;
+
+        let mut signals = BTreeMap::new();
+        let mut scopes = Vec::new();
+        for declaration in parsed.declarations.list.iter() {
+            match declaration {
+                Declaration::Scope { name, .. } => scopes.push(name.as_str()),
+                Declaration::Var { old_id, name, .. } => {
+                    let path = scopes
+                        .iter()
+                        .copied()
+                        .chain(std::iter::once(name.as_str()))
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    signals.insert(path, *old_id);
+                }
+                Declaration::Upscope => {
+                    scopes.pop();
+                }
+            }
+        }
+
+        Ok(Dump {
+            end_of_definitions: parsed.end_of_definitions,
+            timescale: parsed.timescale,
+            data,
+            signals,
+        })
+    }
+
+    fn changes(&self) -> Changes<'_> {
+        Changes {
+            lines: self.data[self.end_of_definitions..].split(|&b| b == b'\n'),
+            timescale: self.timescale,
+            time: 0,
+            unsorted: None,
+        }
+    }
+}
+
+type Lines<'a> = std::slice::Split<'a, u8, fn(&u8) -> bool>;
+
+/// The value changes of a [`Dump`], read one timestamp at a time.
+struct Changes<'a> {
+    lines: Lines<'a>,
+    timescale: u64,
+    /// The time of the changes being read, in femtoseconds.
+    time: u64,
+    /// The first timestamp that is smaller than the previous one, if any. No changes are read
+    /// after it.
+    unsorted: Option<u64>,
+}
+impl Changes<'_> {
+    /// The values of each identifier that changed at the next time with changes, and that time.
+    fn next_time(&mut self) -> Result<Option<(u64, TimeChanges)>, Box<dyn std::error::Error>> {
+        if self.unsorted.is_some() {
+            return Ok(None);
+        }
+        let mut changes = TimeChanges::default();
+        for line in self.lines.by_ref() {
+            let line = line.trim_ascii();
+            if let [b'#', timestamp @ ..] = line {
+                let text = String::from_utf8_lossy(timestamp);
+                let timestamp = parse_time(timestamp)
+                    .map_err(|()| format!("invalid timestamp {}", text))?
+                    .checked_mul(self.timescale)
+                    .ok_or_else(|| format!("timestamp {} overflows in femtoseconds", text))?;
+                if timestamp < self.time {
+                    self.unsorted = Some(timestamp);
+                    return Ok(None);
+                }
+                if timestamp != self.time && !changes.is_empty() {
+                    let time = std::mem::replace(&mut self.time, timestamp);
+                    return Ok(Some((time, changes)));
+                }
+                self.time = timestamp;
+            } else if let Some((value, id)) = split_value_change(line) {
+                let code = IdCode::parse(id)
+                    .ok_or_else(|| format!("invalid identifier {}", String::from_utf8_lossy(id)))?;
+                changes
+                    .entry(code)
+                    .or_default()
+                    .push(String::from_utf8_lossy(value).into_owned());
+            }
+        }
+        Ok((!changes.is_empty()).then_some((self.time, changes)))
+    }
+}
+
+/// The values of each identifier that changed at a time.
+type TimeChanges = HashMap<IdCode, Vec<String>>;
+
+/// The changes of `next`, if they are at `time`.
+fn changes_at(next: &Option<(u64, TimeChanges)>, time: u64) -> Option<&TimeChanges> {
+    match next {
+        Some((t, changes)) if *t == time => Some(changes),
+        _ => None,
+    }
+}
+
+/// Compare the signals and value changes of `output` and `reference`, matching signals by their
+/// hierarchical name, so the files may use different identifiers. Returns the first difference
+/// found, if any. The value changes are compared one timestamp at a time, as they are read.
+pub fn compare(
+    output: impl AsRef<Path>,
+    reference: impl AsRef<Path>,
+) -> Result<Option<Difference>, Box<dyn std::error::Error>> {
+    let output = Dump::read(output.as_ref())?;
+    let reference = Dump::read(reference.as_ref())?;
+
+    for (dump, other, in_reference) in [(&output, &reference, false), (&reference, &output, true)] {
+        if let Some(path) = dump
+            .signals
+            .keys()
+            .find(|x| !other.signals.contains_key(*x))
+        {
+            return Ok(Some(Difference::MissingSignal {
+                path: path.clone(),
+                in_reference,
+            }));
+        }
+    }
+
+    let mut output_changes = output.changes();
+    let mut reference_changes = reference.changes();
+    let mut next_output = output_changes.next_time()?;
+    let mut next_reference = reference_changes.next_time()?;
+
+    let no_changes = HashMap::default();
+    loop {
+        for (changes, in_reference) in [(&output_changes, false), (&reference_changes, true)] {
+            if let Some(time) = changes.unsorted {
+                return Ok(Some(Difference::Unsorted { time, in_reference }));
+            }
+        }
+
+        let time = match (&next_output, &next_reference) {
+            (Some((a, _)), Some((b, _))) => *a.min(b),
+            (Some((time, _)), None) | (None, Some((time, _))) => *time,
+            (None, None) => return Ok(None),
+        };
+        let output_at = changes_at(&next_output, time);
+        let reference_at = changes_at(&next_reference, time);
+        let (advance_output, advance_reference) = (output_at.is_some(), reference_at.is_some());
+
+        let output_at = output_at.unwrap_or(&no_changes);
+        let reference_at = reference_at.unwrap_or(&no_changes);
+        for (path, output_id) in output.signals.iter() {
+            let reference_id = reference.signals[path];
+            let output_values = output_at.get(output_id).map_or(&[][..], |x| x);
+            let reference_values = reference_at.get(&reference_id).map_or(&[][..], |x| x);
+            if output_values != reference_values {
+                return Ok(Some(Difference::Value {
+                    time,
+                    path: path.clone(),
+                    output: output_values.to_vec(),
+                    reference: reference_values.to_vec(),
+                }));
+            }
+        }
+
+        if advance_output {
+            next_output = output_changes.next_time()?;
+        }
+        if advance_reference {
+            next_reference = reference_changes.next_time()?;
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! The `--from-config` file: a TOML file with the inputs of the merge and its options.
+//!
+//! Only the subset of TOML needed for the options is supported: strings, integers, booleans and
+//! single-line arrays of them, as top-level keys named after the long options, followed by an
+//! `[[input]]` table for each input:
+//!
+//! ```toml
+//! reorder = true
+//! exclude_types = ["parameter"]
+//!
+//! [[input]]
+//! path = "cpu.vcd"
+//! time_offset = -10
+//! include = ["top.cpu.*"]
+//! ```
+//!
+//! An `[[input]]` takes a `path`, and optionally a `time_offset`, a `bit_order`, and `include` and
+//! `exclude` globs that only apply to it. The config is turned into the equivalent command line arguments.
+//!
+//! Relative paths, of the inputs and of options like `output`, are relative to the directory of
+//! the config.
+
+use std::{ffi::OsString, path::Path};
+
+#[derive(Debug)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(x) => write!(f, "{}", x),
+            Value::Integer(x) => write!(f, "{}", x),
+            Value::Boolean(x) => write!(f, "{}", x),
+            Value::Array(_) => write!(f, "array"),
+        }
+    }
+}
+
+type Table = Vec<(String, Value)>;
+
+/// The options whose values are paths, which are resolved relative to the config.
+const PATH_OPTIONS: [&str; 11] = [
+    "output",
+    "dump_sections",
+    "append",
+    "json_signals",
+    "symbol_map",
+    "symbol_map_in",
+    "compare",
+    "priority",
+    "signal_order",
+    "resume",
+    "activity",
+];
+
+/// The options whose values may start with the path of the input they apply to, like
+/// `cpu.vcd:top.*`.
+const INPUT_OPTIONS: [&str; 3] = ["bit_order", "include", "exclude"];
+
+/// `path` relative to the directory `base` of the config, unless it is absolute or the standard
+/// input.
+fn resolve(base: &Path, path: &str) -> String {
+    if path == "-" || Path::new(path).is_absolute() {
+        return path.to_string();
+    }
+    base.join(path).to_string_lossy().into_owned()
+}
+
+/// Read the config file at `path`, and return the command line arguments equivalent to it: the
+/// options of the top-level keys, followed by the options and paths of the inputs.
+pub fn config_args(path: &Path) -> Result<Vec<OsString>, String> {
+    let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let (options, inputs) = parse(&content)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    let mut args = Vec::new();
+    for (key, value) in options {
+        if key == "input" || key == "from_config" {
+            return Err(format!("{} can't be set in a config", key));
+        }
+        let flag = format!("--{}", key.replace('_', "-"));
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            match value {
+                Value::Boolean(true) => args.push(flag.clone().into()),
+                Value::Boolean(false) => {}
+                Value::Array(_) => return Err(format!("nested array in {}", key)),
+                Value::String(value) if PATH_OPTIONS.contains(&key.as_str()) => {
+                    let value = match key.as_str() {
+                        // a list of paths separated by commas.
+                        "priority" => {
+                            let paths = value.split(',').map(|path| resolve(base, path));
+                            paths.collect::<Vec<_>>().join(",")
+                        }
+                        _ => resolve(base, &value),
+                    };
+                    args.push(format!("{}={}", flag, value).into());
+                }
+                Value::String(value) if INPUT_OPTIONS.contains(&key.as_str()) => {
+                    let value = match value.rsplit_once(':') {
+                        Some((input, rest)) => format!("{}:{}", resolve(base, input), rest),
+                        None => value,
+                    };
+                    args.push(format!("{}={}", flag, value).into());
+                }
+                value => args.push(format!("{}={}", flag, value).into()),
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    for (index, input) in inputs.into_iter().enumerate() {
+        let Some((_, Value::String(path))) = input.iter().find(|(key, _)| key == "path") else {
+            return Err(format!("[[input]] {} has no path", index + 1));
+        };
+        let path = &resolve(base, path);
+        for (key, value) in &input {
+            match (key.as_str(), value) {
+                ("path", _) => {}
+                ("time_offset", Value::Integer(offset)) => {
+                    args.push(format!("--time-offset={}={}", index, offset).into());
+                }
+                ("bit_order", Value::String(order)) => {
+                    args.push(format!("--bit-order={}:{}", path, order).into());
+                }
+                ("include" | "exclude", Value::String(glob)) => {
+                    args.push(format!("--{}={}:{}", key, path, glob).into());
+                }
+                ("include" | "exclude", Value::Array(globs)) => {
+                    for glob in globs {
+                        args.push(format!("--{}={}:{}", key, path, glob).into());
+                    }
+                }
+                _ => return Err(format!("invalid {} of [[input]] {}", key, path)),
+            }
+        }
+        paths.push(path.into());
+    }
+    args.extend(paths);
+
+    Ok(args)
+}
+
+/// Parse the top-level table and the `[[input]]` tables of a config.
+fn parse(content: &str) -> Result<(Table, Vec<Table>), String> {
+    let mut options = Table::new();
+    let mut inputs: Vec<Table> = Vec::new();
+
+    for (number, line) in (1..).zip(content.lines()) {
+        let error = |message: &str| format!("line {}: {}", number, message);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if line.split('#').next().unwrap().trim() != "[[input]]" {
+                return Err(error("only [[input]] tables are supported"));
+            }
+            inputs.push(Table::new());
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected key = value"))?;
+        let key = key.trim();
+        let valid_key = key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+        if key.is_empty() || !valid_key {
+            return Err(error(&format!("invalid key {:?}", key)));
+        }
+
+        let mut rest = value.trim_start();
+        let value = parse_value(&mut rest).map_err(|err| error(&err))?;
+        if !strip_comment(rest).is_empty() {
+            return Err(error(&format!(
+                "unexpected {:?} after the value",
+                rest.trim()
+            )));
+        }
+
+        let table = inputs.last_mut().unwrap_or(&mut options);
+        if table.iter().any(|(k, _)| k == key) {
+            return Err(error(&format!("duplicate key {}", key)));
+        }
+        table.push((key.to_string(), value));
+    }
+
+    Ok((options, inputs))
+}
+
+/// `rest` without the whitespace and comment at its start.
+fn strip_comment(rest: &str) -> &str {
+    let rest = rest.trim();
+    match rest.starts_with('#') {
+        true => "",
+        false => rest,
+    }
+}
+
+/// Parse the value at the start of `rest`, advancing it past the value.
+fn parse_value(rest: &mut &str) -> Result<Value, String> {
+    let s = *rest;
+    if let Some(s) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = s.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    *rest = &s[i + 1..];
+                    return Ok(Value::String(value));
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    c => return Err(format!("invalid escape \\{}", c.unwrap_or(' '))),
+                },
+                c => value.push(c),
+            }
+        }
+        return Err("unterminated string".to_string());
+    }
+    if let Some(s) = s.strip_prefix('\'') {
+        let end = s.find('\'').ok_or("unterminated string")?;
+        *rest = &s[end + 1..];
+        return Ok(Value::String(s[..end].to_string()));
+    }
+    if let Some(s) = s.strip_prefix('[') {
+        let mut values = Vec::new();
+        *rest = s.trim_start();
+        loop {
+            if let Some(s) = rest.strip_prefix(']') {
+                *rest = s;
+                return Ok(Value::Array(values));
+            }
+            values.push(parse_value(rest)?);
+            *rest = rest.trim_start();
+            match rest.strip_prefix(',') {
+                Some(s) => *rest = s.trim_start(),
+                None if rest.starts_with(']') => {}
+                None => return Err("expected , or ] in array".to_string()),
+            }
+        }
+    }
+
+    let end = s
+        .find(|c: char| c.is_whitespace() || c == ',' || c == ']' || c == '#')
+        .unwrap_or(s.len());
+    let (token, after) = s.split_at(end);
+    *rest = after;
+    match token {
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        token => match token.replace('_', "").parse() {
+            Ok(x) => Ok(Value::Integer(x)),
+            Err(_) => Err(format!("invalid value {:?}", token)),
+        },
+    }
+}
@@ -0,0 +1,123 @@
+//! Counting the value changes of each signal, without writing the output.
+
+use crate::{split_value_change, IdCode, Section, TimeError, Vcd};
+use fxhash::FxHashMap as HashMap;
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// The value changes of each signal, counted by [`count_changes`].
+pub struct ChangeCounts {
+    /// The hierarchical name and number of changes of each signal that changes at least once,
+    /// from the most to the least active.
+    pub signals: Vec<(String, u64)>,
+    /// The first and last timestamps of the counted changes, in the output timescale.
+    pub time_range: Option<(u64, u64)>,
+}
+
+/// Count the value changes of each output signal in `sections`, stopping at the first timestamp
+/// after `max_time`, in the output timescale.
+///
+/// Inputs that share a signal, like with [`ParseOptions::append`](crate::ParseOptions::append),
+/// count towards the same signal.
+pub fn count_changes(
+    vcds: &[Vcd],
+    sections: &[Section],
+    max_time: Option<u64>,
+) -> Result<ChangeCounts, TimeError> {
+    let mut counts: HashMap<IdCode, u64> = HashMap::default();
+    let mut time_range: Option<(u64, u64)> = None;
+    let mut add_time = |time: u64| {
+        let (first, last) = time_range.get_or_insert((time, time));
+        *first = time.min(*first);
+        *last = time.max(*last);
+    };
+
+    for section in sections {
+        add_time(section.value);
+        let mut lines = section.section.split(|&b| b == b'\n');
+        // the first line is the timestamp of the section, if it starts with one.
+        if let [b'#', ..] = section.section {
+            lines.next();
+        }
+        for line in lines {
+            if let [b'#', ..] = line {
+                let Some(value) = section.vcd.timestamp(&line[1..])? else {
+                    // dropped timestamps always end a section
+                    break;
+                };
+                if max_time.is_some_and(|max_time| value > max_time) {
+                    break;
+                }
+                add_time(value);
+                continue;
+            }
+            let Some((_, symbol)) = split_value_change(line) else {
+                continue;
+            };
+            let id = IdCode::parse(symbol);
+            if let Some(new_id) = id.and_then(|id| section.vcd.symbol_map.get(&id)) {
+                *counts.entry(*new_id).or_default() += 1;
+            }
+        }
+    }
+
+    let mut names: HashMap<IdCode, &str> = HashMap::default();
+    for vcd in vcds {
+        for (old_id, new_id) in vcd.symbol_map.iter() {
+            if let Some(signal) = vcd.signals.get(old_id) {
+                names.entry(*new_id).or_insert(&signal.name);
+            }
+        }
+    }
+
+    let mut counts = counts
+        .into_iter()
+        .map(|(id, count)| {
+            (
+                names.get(&id).copied().unwrap_or_default().to_string(),
+                count,
+            )
+        })
+        .collect::<Vec<_>>();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    Ok(ChangeCounts {
+        signals: counts,
+        time_range,
+    })
+}
+
+/// Write `counts` to `output` as CSV, with the columns `name`, `changes` and `changes_per_us`.
+///
+/// The rate is the number of changes over the time range of all changes, given the `timescale` of
+/// the output in femtoseconds. It is empty if all changes are at the same time.
+pub fn write_activity_csv(
+    output: impl AsRef<Path>,
+    counts: &ChangeCounts,
+    timescale: u64,
+) -> std::io::Result<()> {
+    let mut out = BufWriter::new(std::fs::File::create(output)?);
+
+    // the time range in microseconds.
+    let coverage = counts.time_range.map_or(0.0, |(first, last)| {
+        (last - first) as f64 * timescale as f64 / 1e9
+    });
+
+    writeln!(out, "name,changes,changes_per_us")?;
+    for (name, count) in &counts.signals {
+        if name.contains([',', '"', '\n']) {
+            write!(out, "\"{}\"", name.replace('"', "\"\""))?;
+        } else {
+            write!(out, "{}", name)?;
+        }
+        write!(out, ",{},", count)?;
+        if coverage > 0.0 {
+            write!(out, "{}", *count as f64 / coverage)?;
+        }
+        writeln!(out)?;
+    }
+
+    out.flush()
+}
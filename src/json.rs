@@ -0,0 +1,121 @@
+//! Export of the merged signal hierarchy as JSON.
+
+use crate::{Declaration, IdCode, Vcd};
+use std::{
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// A scope in the merged hierarchy.
+#[derive(Default)]
+struct Scope<'a> {
+    kind: &'a str,
+    name: &'a str,
+    children: Vec<Scope<'a>>,
+    variables: Vec<&'a Declaration>,
+}
+
+/// Write the scope hierarchy of all inputs, as they are declared in the output, to `output` as
+/// JSON.
+///
+/// Each scope is a object with `type`, `name`, `children` and `variables`, and each variable has
+/// `name`, `type`, `width`, `original_id` and `new_id`. The root of the tree is a scope of type
+/// `root`.
+pub fn write_json_signals(output: impl AsRef<Path>, vcds: &[Vcd]) -> std::io::Result<()> {
+    let mut root = Scope {
+        kind: "root",
+        ..Default::default()
+    };
+
+    for vcd in vcds {
+        // the path from the root to the current scope, as indices into `children`.
+        let mut path: Vec<usize> = Vec::new();
+        for declaration in vcd.declarations.iter() {
+            let mut scope = &mut root;
+            for &i in &path {
+                scope = &mut scope.children[i];
+            }
+            match declaration {
+                Declaration::Scope { kind, name } => {
+                    path.push(scope.children.len());
+                    scope.children.push(Scope {
+                        kind,
+                        name,
+                        ..Default::default()
+                    });
+                }
+                Declaration::Var { .. } => scope.variables.push(declaration),
+                Declaration::Upscope => {
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    let mut out = BufWriter::new(std::fs::File::create(output)?);
+    write_scope(&mut out, &root)?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
+
+fn write_scope(out: &mut impl Write, scope: &Scope) -> std::io::Result<()> {
+    out.write_all(b"{\"type\":")?;
+    write_str(out, scope.kind.as_bytes())?;
+    out.write_all(b",\"name\":")?;
+    write_str(out, scope.name.as_bytes())?;
+
+    out.write_all(b",\"children\":[")?;
+    for (i, child) in scope.children.iter().enumerate() {
+        if i != 0 {
+            out.write_all(b",")?;
+        }
+        write_scope(out, child)?;
+    }
+
+    out.write_all(b"],\"variables\":[")?;
+    for (i, var) in scope.variables.iter().enumerate() {
+        let Declaration::Var {
+            ty,
+            width,
+            old_id,
+            new_id,
+            name,
+            ..
+        } = var
+        else {
+            unreachable!()
+        };
+        if i != 0 {
+            out.write_all(b",")?;
+        }
+        out.write_all(b"{\"name\":")?;
+        write_str(out, name.as_bytes())?;
+        out.write_all(b",\"type\":")?;
+        write_str(out, ty.as_bytes())?;
+        write!(out, ",\"width\":{}", width)?;
+        out.write_all(b",\"original_id\":")?;
+        write_id(out, old_id)?;
+        out.write_all(b",\"new_id\":")?;
+        write_id(out, new_id)?;
+        out.write_all(b"}")?;
+    }
+    out.write_all(b"]}")
+}
+
+fn write_id(out: &mut impl Write, id: &IdCode) -> std::io::Result<()> {
+    write_str(out, id.as_bytes())
+}
+
+/// Write `s` as a JSON string, escaping it as needed.
+pub(crate) fn write_str(out: &mut impl Write, s: &[u8]) -> std::io::Result<()> {
+    out.write_all(b"\"")?;
+    for &b in s {
+        match b {
+            b'"' => out.write_all(b"\\\"")?,
+            b'\\' => out.write_all(b"\\\\")?,
+            0x00..=0x1F => write!(out, "\\u{:04x}", b)?,
+            _ => out.write_all(&[b])?,
+        }
+    }
+    out.write_all(b"\"")
+}
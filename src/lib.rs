@@ -0,0 +1,3490 @@
+//! Merge multiple VCD (Value Change Dump) files together.
+//!
+//! The merge is done in three steps: [`parse_headers`] gathers the declarations of each input,
+//! [`find_sections`] (or [`file_sections`]) splits the data of each input in sections of sorted
+//! timestamps, and [`write_output`] merge-sorts these sections into the output file. [`merge`]
+//! does all three, reporting the [`Progress`] of each to a single callback.
+//!
+//! For profiling, each step is a [`tracing`] span, `gathering`, `finding` and `merging`, with a
+//! `parse_file` and a `find_file` span for each input, and a `write_section` span each time the
+//! merge writes from a section. Nothing is recorded unless a subscriber is installed.
+
+mod checkpoint;
+mod compare;
+mod count;
+mod json;
+mod mmap_output;
+mod plan;
+mod preview;
+mod progress;
+mod sample;
+mod split;
+mod symbol_map;
+mod validate;
+
+pub use checkpoint::Checkpoint;
+pub use compare::{compare, Difference};
+pub use count::{count_changes, write_activity_csv, ChangeCounts};
+pub use json::write_json_signals;
+pub use plan::{plan_sections, write_sections_tsv, SectionInfo};
+pub use preview::write_preview;
+pub use progress::{merge, Phase, Progress};
+pub use sample::Samples;
+pub use split::{chunk_path, manifest_path, split_output, write_split_manifest, Chunk};
+pub use symbol_map::{read_symbol_map, write_symbol_map};
+pub use validate::{validate, Problem};
+
+use fxhash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use memmap2::Mmap;
+use mmap_output::MmapWriter;
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{binary_heap::PeekMut, BTreeMap},
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+// this can only represent 94^4 = 78_074_896 symbols.
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+struct IdCode([u8; 4]);
+impl From<&[u8]> for IdCode {
+    fn from(s: &[u8]) -> Self {
+        let mut code = [0; 4];
+        for (i, b) in s.iter().enumerate() {
+            code[i] = *b;
+        }
+        IdCode(code)
+    }
+}
+impl IdCode {
+    /// The code of a identifier, or `None` if it is longer than the 4 bytes a code can hold.
+    fn parse(s: &[u8]) -> Option<Self> {
+        (s.len() <= 4).then(|| IdCode::from(s))
+    }
+
+    /// The order of this code in the sequence generated by [`next_code`].
+    fn index(&self) -> u32 {
+        u32::from_le_bytes(self.0)
+    }
+
+    /// The number of codes in the sequence generated by [`next_code`] up to this one, counting
+    /// the ones that are skipped.
+    fn ordinal(&self) -> u64 {
+        let bytes = self.as_bytes();
+        let shorter = (1..bytes.len() as u32)
+            .map(|len| 94u64.pow(len))
+            .sum::<u64>();
+        let value = bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &b| value * 94 + (b - 0x21) as u64);
+        if bytes.is_empty() {
+            0
+        } else {
+            shorter + value + 1
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        for i in 0..4 {
+            if self.0[i] == 0 {
+                return &self.0[..i];
+            }
+        }
+        &self.0
+    }
+}
+impl std::fmt::Debug for IdCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IdCode(")?;
+        for b in self.as_bytes() {
+            write!(f, "{}", *b as char)?;
+        }
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// A parsed input VCD file.
+pub struct Vcd {
+    /// Map from old symbol to new symbol.
+    symbol_map: HashMap<IdCode, IdCode>,
+    /// Information about each signal, indexed by its old symbol.
+    signals: HashMap<IdCode, SignalInfo>,
+    /// The old symbols of the signals excluded by [`ParseOptions::exclude_types`].
+    excluded: HashSet<IdCode>,
+    /// All scope and var declarations.
+    declarations: Vec<Declaration>,
+    /// The whole input file. All offsets into it are `usize`, so the file must fit in the address
+    /// space, which limits the size of inputs on 32-bit targets.
+    file: InputData,
+    /// The path of the input file.
+    path: PathBuf,
+    end_of_definitions: usize,
+    /// The `$dumpvars` block found before the end of the declarations, if any, whose values are
+    /// merged at time 0.
+    early_dumpvars: Option<Range<usize>>,
+    /// The timescale ratio between this input timescale and the output timescale.
+    timescale: u64,
+    /// The divisor of the timescale ratio, above 1 for inputs finer than the output timescale
+    /// given in [`ParseOptions::output_timescale`], whose timestamps are rounded down.
+    timescale_divisor: u64,
+    /// The timescale of this input, in femtoseconds.
+    input_timescale: u64,
+    /// The content of the `$timescale` command, as written in the input.
+    raw_timescale: Option<String>,
+    /// Offset added to each timestamp, in the input timescale.
+    offset: i64,
+    negative_time: NegativeTime,
+    /// The order of the bits of the vector values of this input.
+    bit_order: BitOrder,
+    /// The problems found while parsing this input, which were worked around.
+    warnings: Vec<String>,
+}
+impl Vcd {
+    /// The content of the input file.
+    pub fn file(&self) -> &[u8] {
+        &self.file
+    }
+
+    /// The path of the input file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The content of the `$timescale` command, without the surrounding whitespace.
+    pub fn raw_timescale(&self) -> Option<&str> {
+        self.raw_timescale.as_deref().map(str::trim)
+    }
+
+    /// The timescale of this input, in femtoseconds. This is the fallback timescale if its
+    /// `$timescale` could not be parsed.
+    pub fn input_timescale(&self) -> u64 {
+        self.input_timescale
+    }
+
+    /// The number of signals of this input that are merged.
+    pub fn signal_count(&self) -> usize {
+        self.symbol_map.len()
+    }
+
+    /// The problems found while parsing this input that were worked around, like an invalid
+    /// timescale or value changes among the declarations, to be reported to the user.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The names of the top-level scopes declared by this input, in the order they are declared,
+    /// without repeating scopes that are declared more than once.
+    pub fn top_scopes(&self) -> Vec<&str> {
+        let mut scopes = Vec::new();
+        let mut depth = 0usize;
+        for declaration in self.declarations.iter() {
+            match declaration {
+                Declaration::Scope { name, .. } => {
+                    if depth == 0 && !scopes.contains(&name.as_str()) {
+                        scopes.push(name.as_str());
+                    }
+                    depth += 1;
+                }
+                Declaration::Var { .. } => {}
+                Declaration::Upscope => depth = depth.saturating_sub(1),
+            }
+        }
+        scopes
+    }
+
+    /// The hierarchical names of the vars declared by this input outside of any scope.
+    pub fn top_vars(&self) -> Vec<&str> {
+        let mut vars = Vec::new();
+        let mut depth = 0usize;
+        for declaration in self.declarations.iter() {
+            match declaration {
+                Declaration::Scope { .. } => depth += 1,
+                Declaration::Var { path, .. } if depth == 0 => vars.push(path.as_str()),
+                Declaration::Var { .. } => {}
+                Declaration::Upscope => depth = depth.saturating_sub(1),
+            }
+        }
+        vars
+    }
+
+    /// The new symbol of the old `symbol` of a value change `line`, or `None` if its signal is
+    /// excluded. Fails if `symbol` is not declared in the header.
+    fn new_symbol(&self, line: &[u8], symbol: &[u8]) -> std::io::Result<Option<IdCode>> {
+        let undeclared = || undeclared_identifier(self, line, symbol);
+        let id = IdCode::parse(symbol).ok_or_else(undeclared)?;
+        match self.symbol_map.get(&id) {
+            Some(new_id) => Ok(Some(*new_id)),
+            None if self.excluded.contains(&id) => Ok(None),
+            None => Err(undeclared()),
+        }
+    }
+
+    /// Convert a timestamp of this input to the output timescale, after applying its offset.
+    /// Returns `None` if the timestamp is before zero and should be dropped.
+    fn time(&self, timestamp: u64) -> Result<Option<u64>, TimeError> {
+        let overflow = || TimeError::Overflow {
+            path: self.path.clone(),
+            timestamp: timestamp.to_string(),
+        };
+        let time = timestamp as i128 + self.offset as i128;
+        if time < 0 {
+            return match self.negative_time {
+                NegativeTime::Clamp => Ok(Some(0)),
+                NegativeTime::Drop => Ok(None),
+            };
+        }
+        let time = u64::try_from(time).map_err(|_| overflow())?;
+        if self.timescale_divisor != 1 {
+            let time = time as u128 * self.timescale as u128 / self.timescale_divisor as u128;
+            return u64::try_from(time).map(Some).map_err(|_| overflow());
+        }
+        match time.checked_mul(self.timescale) {
+            Some(time) => Ok(Some(time)),
+            None => Err(overflow()),
+        }
+    }
+
+    /// Parse a timestamp of this input, written as `text` after its `#`, and convert it to the
+    /// output timescale like [`Vcd::time`].
+    fn timestamp(&self, text: &[u8]) -> Result<Option<u64>, TimeError> {
+        let text = text.trim_ascii_end();
+        match parse_time(text) {
+            Ok(time) => self.time(time),
+            Err(()) => {
+                let timestamp = String::from_utf8_lossy(text).into_owned();
+                let path = self.path.clone();
+                // `parse_time` fails on text that is not a number, or on numbers that don't fit
+                // in 64 bits, which are valid but can't be merged.
+                let (digits, radix) = match text {
+                    [b'0', b'x' | b'X', hex @ ..] => (hex, 16),
+                    _ => (text, 10),
+                };
+                let is_number =
+                    !digits.is_empty() && digits.iter().all(|&b| (b as char).is_digit(radix));
+                match is_number {
+                    true => Err(TimeError::Overflow { path, timestamp }),
+                    false => Err(TimeError::Invalid { path, timestamp }),
+                }
+            }
+        }
+    }
+}
+
+/// A timestamp of a input that can't be merged.
+#[derive(Debug, Clone)]
+pub enum TimeError {
+    /// A timestamp that is not a number.
+    Invalid { path: PathBuf, timestamp: String },
+    /// A timestamp that doesn't fit in 64 bits after applying the offset of its input and
+    /// converting it to the output timescale.
+    Overflow { path: PathBuf, timestamp: String },
+}
+impl std::fmt::Display for TimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeError::Invalid { path, timestamp } => {
+                write!(f, "invalid timestamp #{} in {}", timestamp, path.display())
+            }
+            TimeError::Overflow { path, timestamp } => write!(
+                f,
+                "timestamp #{} of {} overflows in the output timescale",
+                timestamp,
+                path.display()
+            ),
+        }
+    }
+}
+impl std::error::Error for TimeError {}
+impl From<TimeError> for Error {
+    fn from(err: TimeError) -> Self {
+        Error::Time(err)
+    }
+}
+impl From<TimeError> for std::io::Error {
+    fn from(err: TimeError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// What to do with value changes that end up before time zero, after applying a negative time
+/// offset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum NegativeTime {
+    /// Move them to time zero.
+    #[default]
+    Clamp,
+    /// Drop them.
+    Drop,
+}
+
+/// What to do when different inputs change the same output signal, like signals merged by name
+/// with [`ParseOptions::unify_by_name`] or [`ParseOptions::append`], to different values at the
+/// same timestamp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Conflict {
+    /// Keep only the change of the first input.
+    First,
+    /// Write all changes in the order of the inputs, so the change of the last input takes
+    /// effect.
+    #[default]
+    Last,
+    /// Fail the merge.
+    Error,
+}
+
+/// The order of the bits in the vector values of a input, for [`ParseOptions::bit_orders`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BitOrder {
+    /// The most significant bit first, as most tools write them.
+    #[default]
+    Msb,
+    /// The least significant bit first. These values are reversed in the output.
+    Lsb,
+}
+
+/// The case that [`WriteOptions::normalize_values`] writes the `x` and `z` states in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ValueCase {
+    /// Write `x` and `z`.
+    Lower,
+    /// Write `X` and `Z`.
+    Upper,
+}
+
+/// A unit of time, for [`TimescaleLabel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TimeUnit {
+    Fs,
+    Ps,
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+impl TimeUnit {
+    /// All units, from the largest to the smallest.
+    const ALL: [TimeUnit; 6] = [
+        TimeUnit::S,
+        TimeUnit::Ms,
+        TimeUnit::Us,
+        TimeUnit::Ns,
+        TimeUnit::Ps,
+        TimeUnit::Fs,
+    ];
+
+    /// The length of the unit, in femtoseconds.
+    pub fn femtoseconds(self) -> u64 {
+        match self {
+            TimeUnit::Fs => 1,
+            TimeUnit::Ps => 1_000,
+            TimeUnit::Ns => 1_000_000,
+            TimeUnit::Us => 1_000_000_000,
+            TimeUnit::Ms => 1_000_000_000_000,
+            TimeUnit::S => 1_000_000_000_000_000,
+        }
+    }
+
+    /// The unit as written in a `$timescale`, like `ns`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeUnit::Fs => "fs",
+            TimeUnit::Ps => "ps",
+            TimeUnit::Ns => "ns",
+            TimeUnit::Us => "us",
+            TimeUnit::Ms => "ms",
+            TimeUnit::S => "s",
+        }
+    }
+}
+
+/// How the `$timescale` of the output is spelled, without changing its resolution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimescaleLabel {
+    /// The unit to write the timescale in, like `1000ps` instead of `1ns`. If `None`, or if the
+    /// timescale is not a whole number of this unit, the largest unit that divides it is used.
+    pub unit: Option<TimeUnit>,
+    /// Write a space between the number and the unit, like `1 ns`.
+    pub spaced: bool,
+}
+impl TimescaleLabel {
+    /// The label of a timescale in femtoseconds.
+    pub fn format(&self, timescale: u64) -> String {
+        let unit = match self.unit {
+            Some(unit) if timescale.is_multiple_of(unit.femtoseconds()) => unit,
+            _ => largest_unit(timescale),
+        };
+        let space = if self.spaced { " " } else { "" };
+        format!(
+            "{}{}{}",
+            timescale / unit.femtoseconds(),
+            space,
+            unit.name()
+        )
+    }
+}
+
+/// Information about a declared signal, as given to a [`TransformFn`].
+#[derive(Debug, Clone)]
+pub struct SignalInfo {
+    /// The hierarchical name of the signal, with scopes separated by a `.`.
+    pub name: String,
+    /// The type of the variable, like `wire` or `reg`.
+    pub ty: String,
+    /// The width of the variable, in bits.
+    pub width: u64,
+}
+
+/// A callback that can rewrite the value of each value change written to the output.
+///
+/// For vectors and reals, the value includes its `b` or `r` prefix.
+pub type TransformFn = dyn for<'a> Fn(&SignalInfo, &'a [u8]) -> Cow<'a, [u8]>;
+
+/// A filter of vars by their hierarchical name in a input, for [`ParseOptions::filters`].
+///
+/// If any include filter applies to a input, only its vars that match one of them are kept. Vars
+/// that match a exclude filter are always left out.
+#[derive(Debug, Clone)]
+pub struct SignalFilter {
+    /// The input the filter applies to, as given to [`parse_headers`], or `None` for all inputs.
+    pub input: Option<PathBuf>,
+    /// A pattern of hierarchical names, like `top.cpu.*`, where `*` matches any sequence of
+    /// characters, and `?` matches a single character.
+    pub pattern: String,
+    /// Keep the matching vars, instead of leaving them out.
+    pub include: bool,
+}
+
+/// Whether `name` matches the glob `pattern`, where `*` matches any sequence of bytes and `?`
+/// matches a single byte.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // the position of the last `*` in the pattern, and of the name when it was reached.
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // let the last `*` match one more byte.
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Options that control how [`parse_headers`] parses the inputs.
+#[derive(Default)]
+pub struct ParseOptions {
+    /// Also look for `$scope`, `$var` and `$upscope` declarations after the first value change.
+    pub rescan_declarations: bool,
+    /// Offset added to the timestamps of each input, in the units of the input timescale. Inputs
+    /// without an entry are not offset.
+    pub time_offsets: Vec<i64>,
+    /// What to do with changes that a negative offset moves before time zero.
+    pub negative_time: NegativeTime,
+    /// The order of the bits in the vector values of each input, whose values are written most
+    /// significant bit first in the output. Inputs without an entry are most significant bit
+    /// first.
+    pub bit_orders: Vec<BitOrder>,
+    /// A previously merged file to append the inputs to. It is parsed before all inputs, keeping
+    /// its identifiers, and signals of the inputs with the same hierarchical name are mapped to
+    /// them.
+    pub append: Option<PathBuf>,
+    /// Map the vars of all inputs with the same hierarchical name, after
+    /// [`ParseOptions::renames`], to a single signal of the output, declared once with the
+    /// identifier of the first of them, even when the inputs use different identifiers for it.
+    pub unify_by_name: bool,
+    /// The timescale, in femtoseconds, used for inputs whose `$timescale` can't be parsed.
+    /// Defaults to [`DEFAULT_TIMESCALE`].
+    pub fallback_timescale: Option<u64>,
+    /// The timescale of the output, in femtoseconds, instead of the finest timescale of the
+    /// inputs. The timestamps of finer inputs are rounded down to it.
+    pub output_timescale: Option<u64>,
+    /// Wrap the declarations of each input in a scope named after its file.
+    pub scope_per_file: bool,
+    /// The types of vars, like `parameter` or `real`, that are left out of the output, together
+    /// with their value changes.
+    pub exclude_types: Vec<String>,
+    /// Filters of the vars of all inputs, or of a single input, by their hierarchical name before
+    /// [`ParseOptions::renames`]. The vars that are left out are not declared and their value
+    /// changes are not written. Scopes left empty are not declared either.
+    pub filters: Vec<SignalFilter>,
+    /// Pairs of old and new hierarchical names of vars to rename. Only the last component of the
+    /// name can change, the var stays in the same scope. Inputs merged by name, like with
+    /// [`ParseOptions::append`], are merged by the new name.
+    pub renames: Vec<(String, String)>,
+    /// Characters that are never used in the generated identifiers, like `~`, for tools that
+    /// don't accept them. Identifiers kept from [`ParseOptions::append`] may still contain them.
+    pub avoid_chars: Vec<u8>,
+    /// Only declare the first N signals, in the order of the inputs, leaving out the vars after
+    /// them, together with their value changes, and the scopes left empty. Vars that share the
+    /// identifier or name of a declared signal are still declared.
+    pub limit_signals: Option<usize>,
+    /// Pairs of hierarchical names and identifiers, like the ones written by
+    /// [`write_symbol_map`], to use for the vars with these names instead of generating new
+    /// identifiers. A name listed more than once gives its identifiers to the vars with that name
+    /// in order. New identifiers are only generated after all of these.
+    pub symbol_map: Vec<(String, String)>,
+    /// Read each input into memory with buffered reads, instead of memory mapping it, for file
+    /// systems where mapping is slow or not supported.
+    pub no_mmap: bool,
+}
+
+/// The timescale used for inputs with a invalid `$timescale`, if no other is given: 1 ns.
+pub const DEFAULT_TIMESCALE: u64 = 1_000_000;
+
+/// Options that control how [`write_output`] writes the merged file.
+#[derive(Default)]
+pub struct WriteOptions<'a> {
+    /// Called for each value change, allowing to rewrite its value.
+    pub transform: Option<&'a TransformFn>,
+    /// Write the map from old to new identifiers of each input in a `$comment` block.
+    pub embed_map: bool,
+    /// Write the output through a memory map of the file, pre-allocated to the estimated size of
+    /// the output. Experimental.
+    pub mmap_output: bool,
+    /// Stop writing at the first timestamp after this time, in the output timescale.
+    pub max_time: Option<u64>,
+    /// What to do with conflicting changes of the same signal at the same timestamp. Changes at
+    /// the same timestamp are always written in the order of the inputs.
+    pub conflict: Conflict,
+    /// The paths of inputs, from the most to the least trusted, as given to [`parse_headers`].
+    /// When inputs change the same signal to different values at the same timestamp, the change of
+    /// the most trusted one takes effect, and [`WriteOptions::conflict`] only applies between inputs
+    /// that are not listed. Inputs that are not listed are the least trusted.
+    pub priority: Vec<PathBuf>,
+    /// Don't write scopes, and name each var by its hierarchical name instead.
+    pub flatten_scopes: bool,
+    /// Write the declarations of all inputs sorted by their hierarchical name, so the header
+    /// doesn't depend on the order of the inputs or their declarations. Scopes with the same name
+    /// and kind are merged into one.
+    pub sort_signals: bool,
+    /// Hierarchical names of vars, like `top.clk`, in the order their declarations are written.
+    /// The vars that are not listed are written after them, in the default order. Scopes are
+    /// written before the first of their vars, and scopes with the same name and kind are merged
+    /// into one.
+    pub signal_order: Vec<String>,
+    /// Write the names of vars with a single space around them, instead of exactly as written in
+    /// the input.
+    pub canonical_names: bool,
+    /// Where to write a [`Checkpoint`] of the merge, to resume it with [`resume_output`] if it is
+    /// interrupted. The checkpoint is overwritten every `checkpoint_interval` steps of the merge,
+    /// where each step writes the changes of a input at a single timestamp, and removed when the
+    /// merge completes. No checkpoint is written while there are streamed inputs of
+    /// [`LazySections`].
+    pub checkpoint_path: Option<PathBuf>,
+    pub checkpoint_interval: usize,
+    /// Lossy: leave out the changes of real signals that are within this distance of the last
+    /// value written for the signal. The last values are not saved in a [`Checkpoint`], so the
+    /// first change of each signal after resuming is always written.
+    pub delta_epsilon: Option<f64>,
+    /// Lossy: only write every Nth distinct timestamp, starting from the first. The changes at the
+    /// timestamps in between are not written, except for the last change of each signal, which is
+    /// written at the next timestamp that is kept, so each kept timestamp has the same values as
+    /// the full merge. Changes after the last kept timestamp are written at the last timestamp of
+    /// the merge. Values below 2 write every timestamp. Not supported with checkpoints.
+    pub downsample: usize,
+    /// Write a blank line before each timestamp, for readability. Viewers ignore blank lines.
+    pub spaced: bool,
+    /// Write the `x` and `z` states of scalar and vector values in the same case, after
+    /// [`WriteOptions::transform`].
+    pub normalize_values: Option<ValueCase>,
+    /// How to spell the `$timescale` of the output. If `None`, it is written in the largest unit
+    /// that divides it, without a space, like `10ns`.
+    pub timescale_label: Option<TimescaleLabel>,
+    /// Don't warn about inputs with unsorted timestamps, for example when a previous merge of the
+    /// same inputs already did.
+    pub quiet_unsorted: bool,
+}
+
+/// The header commands shared by all inputs, written at the start of the output.
+#[derive(Default)]
+pub struct Header {
+    date: Option<String>,
+    version: Option<String>,
+    timescale: Option<String>,
+}
+impl Header {
+    /// The timescale of the output, in femtoseconds, after the headers are parsed.
+    pub fn timescale(&self) -> Option<u64> {
+        self.timescale.as_deref().and_then(parse_timescale)
+    }
+
+    /// The timescale of the output with the largest unit that divides it, like `10ns`, after the
+    /// headers are parsed.
+    pub fn timescale_name(&self) -> Option<&str> {
+        self.timescale.as_deref()
+    }
+}
+
+/// A error of [`parse_headers`] or [`write_output`].
+#[derive(Debug)]
+pub enum Error {
+    /// A input could not be opened or read.
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A input could not be memory mapped.
+    Map {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The header of a input could not be parsed.
+    Header { path: PathBuf, source: HeaderError },
+    /// The output file could not be created.
+    Create {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The output could not be written.
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A timestamp of a input could not be merged.
+    Time(TimeError),
+    /// There were no inputs to merge.
+    NoInputs,
+}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Read { path, source } => {
+                write!(f, "could not read {}: {}", path.display(), source)
+            }
+            Error::Map { path, source } => {
+                write!(f, "could not memmap {}: {}", path.display(), source)
+            }
+            Error::Header { path, source } => {
+                write!(
+                    f,
+                    "could not parse the header of {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            Error::Create { path, source } => {
+                write!(f, "could not create file {}: {}", path.display(), source)
+            }
+            Error::Write { path, source } => {
+                write!(f, "could not write {}: {}", path.display(), source)
+            }
+            Error::Time(err) => write!(f, "{}", err),
+            Error::NoInputs => write!(f, "no inputs to merge"),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Read { source, .. } | Error::Map { source, .. } => Some(source),
+            Error::Create { source, .. } | Error::Write { source, .. } => Some(source),
+            Error::Header { source, .. } => Some(source),
+            Error::Time(err) => Some(err),
+            Error::NoInputs => None,
+        }
+    }
+}
+
+/// A error found while parsing the header of a input.
+#[derive(Debug)]
+pub enum HeaderError {
+    /// The file ended in the middle of a declaration.
+    UnexpectedEof {
+        /// The directive being parsed.
+        directive: &'static str,
+    },
+    /// A directive has more tokens than expected before its `$end`.
+    ExpectedEnd {
+        directive: &'static str,
+        found: String,
+    },
+    InvalidWidth(String),
+    /// A identifier code longer than the 4 bytes supported.
+    InvalidIdentifier(String),
+    MissingTimescale,
+    /// A timescale of zero, which can't be merged with any other.
+    ZeroTimescale,
+    /// A timescale with a fractional number, like `1.5 ns`, which can't be represented.
+    FractionalTimescale(String),
+    /// A line of the header longer than [`MAX_HEADER_LINE`], most likely a file that is not a VCD.
+    NoLineBreaks,
+    /// A input compressed with the given format, detected by its magic bytes, after decompressing
+    /// it once, like a file compressed twice.
+    Compressed(&'static str),
+}
+impl std::fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderError::UnexpectedEof { directive } => {
+                write!(f, "unexpected end of file in {}", directive)
+            }
+            HeaderError::ExpectedEnd { directive, found } => {
+                write!(f, "expected $end in {}, found {:?}", directive, found)
+            }
+            HeaderError::InvalidWidth(width) => write!(f, "invalid var width {:?}", width),
+            HeaderError::InvalidIdentifier(id) => {
+                write!(f, "identifier {:?} is longer than 4 characters", id)
+            }
+            HeaderError::MissingTimescale => write!(f, "missing timescale"),
+            HeaderError::ZeroTimescale => write!(f, "timescale must be positive"),
+            HeaderError::FractionalTimescale(scale) => {
+                write!(f, "timescale {:?} is not an integer", scale)
+            }
+            HeaderError::NoLineBreaks => write!(
+                f,
+                "malformed header: no line breaks in {} bytes",
+                MAX_HEADER_LINE
+            ),
+            HeaderError::Compressed(format) => {
+                write!(
+                    f,
+                    "input is compressed with {}, decompress it first",
+                    format
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for HeaderError {}
+
+/// The compressed formats of inputs, detected by the magic bytes at their start, which are
+/// decompressed into memory before parsing them.
+#[derive(Clone, Copy)]
+enum Compression {
+    Gzip,
+    Zstd,
+    Lz4,
+}
+impl Compression {
+    const ALL: [Compression; 3] = [Compression::Gzip, Compression::Zstd, Compression::Lz4];
+
+    /// The format `data` is compressed with, if any.
+    fn detect(data: &[u8]) -> Option<Compression> {
+        Self::ALL
+            .into_iter()
+            .find(|format| data.starts_with(format.magic()))
+    }
+
+    fn magic(self) -> &'static [u8] {
+        match self {
+            Compression::Gzip => b"\x1F\x8B",
+            Compression::Zstd => b"\x28\xB5\x2F\xFD",
+            Compression::Lz4 => b"\x04\x22\x4D\x18",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    fn decompress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        match self {
+            Compression::Gzip => {
+                flate2::read::MultiGzDecoder::new(data).read_to_end(&mut buffer)?
+            }
+            Compression::Zstd => ruzstd::decoding::StreamingDecoder::new(data)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?
+                .read_to_end(&mut buffer)?,
+            Compression::Lz4 => {
+                lz4_flex::frame::FrameDecoder::new(data).read_to_end(&mut buffer)?
+            }
+        };
+        Ok(buffer)
+    }
+}
+
+/// The keywords of a VCD file, which can't be part of the text of a directive.
+const KEYWORDS: [&[u8]; 12] = [
+    b"$comment",
+    b"$date",
+    b"$dumpall",
+    b"$dumpoff",
+    b"$dumpon",
+    b"$dumpvars",
+    b"$enddefinitions",
+    b"$scope",
+    b"$timescale",
+    b"$upscope",
+    b"$var",
+    b"$version",
+];
+
+/// Whether `token` is one of the [`KEYWORDS`], which means the directive before it is missing its
+/// `$end`.
+fn is_keyword(token: &[u8]) -> bool {
+    KEYWORDS.contains(&token)
+}
+
+/// The longest line accepted in a header, in bytes.
+pub const MAX_HEADER_LINE: usize = 1 << 20;
+
+/// The whitespace separated tokens of a header, keeping track of their position in the data.
+#[derive(Clone)]
+struct HeaderTokens<'d> {
+    data: &'d [u8],
+    pos: usize,
+    /// The position where the last token starts.
+    start: usize,
+    /// The position where the line of the last token starts.
+    line_start: usize,
+    /// The position of the first token found after a `$end` in the same line, for each line with
+    /// tokens after a `$end`, which are skipped.
+    trailing: Vec<usize>,
+}
+impl<'d> HeaderTokens<'d> {
+    fn new(data: &'d [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            start: 0,
+            line_start: 0,
+            trailing: Vec::new(),
+        }
+    }
+
+    /// The next token, and the position where it starts.
+    fn next_raw(&mut self) -> Option<(usize, &'d [u8])> {
+        let rest = &self.data[self.pos..];
+        let space = rest.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if let Some(x) = rest[..space].iter().rposition(|&b| b == b'\n') {
+            self.line_start = self.pos + x + 1;
+        }
+        self.pos += space;
+        let start = self.pos;
+        let rest = &self.data[start..];
+        self.pos += rest.iter().take_while(|b| !b.is_ascii_whitespace()).count();
+        self.start = start;
+        (self.pos > start).then(|| (start, &self.data[start..self.pos]))
+    }
+
+    /// The next token of `directive`, which must exist. Tokens are borrowed from the data, and
+    /// only copied if they are not valid UTF-8.
+    fn expect_token(&mut self, directive: &'static str) -> Result<Cow<'d, str>, HeaderError> {
+        self.next().ok_or(HeaderError::UnexpectedEof { directive })
+    }
+
+    /// Consume the `$end` that closes `directive`, and skip the tokens after it in the same line.
+    fn expect_end(&mut self, directive: &'static str) -> Result<(), HeaderError> {
+        self.expect_end_token(directive)?;
+        self.skip_trailing();
+        Ok(())
+    }
+
+    /// Consume the `$end` that closes `directive`, keeping the rest of its line.
+    fn expect_end_token(&mut self, directive: &'static str) -> Result<(), HeaderError> {
+        let end = self.expect_token(directive)?;
+        if end != "$end" {
+            return Err(HeaderError::ExpectedEnd {
+                directive,
+                found: end.into_owned(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Skip the tokens in the rest of the line after a `$end`, like in `$upscope $end extra`,
+    /// saving the position of the first in `trailing`. A keyword or timestamp is not skipped, as some files
+    /// write several directives in a line.
+    fn skip_trailing(&mut self) {
+        let mut first = true;
+        loop {
+            let rest = &self.data[self.pos..];
+            let space = rest
+                .iter()
+                .take_while(|&&b| b != b'\n' && b.is_ascii_whitespace())
+                .count();
+            let start = self.pos + space;
+            let len = self.data[start..]
+                .iter()
+                .take_while(|b| !b.is_ascii_whitespace())
+                .count();
+            let token = &self.data[start..start + len];
+            if token.is_empty() || is_keyword(token) || is_timestamp(token) {
+                return;
+            }
+            if first {
+                self.trailing.push(start);
+                first = false;
+            }
+            self.pos = start + len;
+        }
+    }
+
+    /// The data up to the `$end` that closes `directive`, exactly as written, consuming the
+    /// `$end`. Fails if there is a keyword before it, or no `$end` at all. Other words starting
+    /// with `$`, like in the text of a `$comment`, are part of the data.
+    fn raw_to_end(&mut self, directive: &'static str) -> Result<&'d [u8], HeaderError> {
+        let start = self.pos;
+        loop {
+            match self.next_raw() {
+                Some((end, b"$end")) => {
+                    self.skip_trailing();
+                    return Ok(&self.data[start..end]);
+                }
+                Some((_, token)) if is_keyword(token) => {
+                    return Err(HeaderError::ExpectedEnd {
+                        directive,
+                        found: String::from_utf8_lossy(token).into_owned(),
+                    })
+                }
+                Some(_) => {}
+                None => return Err(HeaderError::UnexpectedEof { directive }),
+            }
+        }
+    }
+
+    /// The position after the end of the line of the last token, or the start of the next token,
+    /// if it is in the same line.
+    fn end_of_line(&self) -> usize {
+        let rest = &self.data[self.pos..];
+        match rest
+            .iter()
+            .position(|&b| b == b'\n' || !b.is_ascii_whitespace())
+        {
+            Some(x) if rest[x] == b'\n' => self.pos + x + 1,
+            Some(x) => self.pos + x,
+            None => self.data.len(),
+        }
+    }
+}
+impl<'d> Iterator for HeaderTokens<'d> {
+    type Item = Cow<'d, str>;
+
+    fn next(&mut self) -> Option<Cow<'d, str>> {
+        self.next_raw()
+            .map(|(_, token)| String::from_utf8_lossy(token))
+    }
+}
+
+/// The number of identifiers with up to 4 characters, which is the most that can be generated.
+pub const IDENTIFIER_CAPACITY: u64 = 94 + 94 * 94 + 94 * 94 * 94 + 94 * 94 * 94 * 94;
+
+/// The number of identifiers generated or reserved so far, of the [`IDENTIFIER_CAPACITY`].
+pub fn identifiers_used() -> u64 {
+    CURR_CODE.lock().unwrap().ordinal()
+}
+
+/// Start generating identifiers from `!` again, for a output that doesn't share identifiers with
+/// the ones written before.
+pub fn reset_identifiers() {
+    *CURR_CODE.lock().unwrap() = IdCode([0; 4]);
+}
+
+static CURR_CODE: Mutex<IdCode> = Mutex::new(IdCode([0; 4])); // '!'
+
+/// Make sure [`next_code`] will only generate codes after `code`.
+fn reserve_code(code: IdCode) {
+    let mut curr = CURR_CODE.lock().unwrap();
+    if code.index() > curr.index() {
+        *curr = code;
+    }
+}
+
+/// Generate the next identifier, with none of the `avoid` characters.
+fn next_code(avoid: &[u8]) -> IdCode {
+    let allowed = |b: &u8| !avoid.contains(b);
+    // '!' to '~'
+    let first = (0x21..=0x7E)
+        .find(allowed)
+        .expect("all identifier characters are avoided");
+
+    let mut code = CURR_CODE.lock().unwrap();
+
+    // a code reserved by `reserve_code` may contain avoided characters, which are only skipped
+    // when incremented.
+    loop {
+        for b in code.0.iter_mut() {
+            if *b == 0x0 {
+                *b = first;
+                break;
+            }
+            if let Some(next) = (*b + 1..=0x7E).find(allowed) {
+                *b = next;
+                break;
+            } else {
+                *b = first;
+            }
+        }
+        if code.as_bytes().iter().all(allowed) {
+            break;
+        }
+    }
+
+    // a whitespace in a identifier would split it in the value changes.
+    assert!(
+        code.as_bytes().iter().all(|b| !b.is_ascii_whitespace()),
+        "generated identifier with whitespace: {:?}",
+        *code
+    );
+
+    *code
+}
+
+/// The tokens up to the `$end` that closes `directive`, each followed by a space. Fails if there
+/// is a keyword before it, or no `$end` at all, instead of consuming the rest of the file.
+fn take_to_end(tokens: &mut HeaderTokens, directive: &'static str) -> Result<String, HeaderError> {
+    let mut scale = String::with_capacity(8);
+    loop {
+        let token = tokens.expect_token(directive)?;
+        if token == "$end" {
+            return Ok(scale);
+        }
+        if is_keyword(token.as_bytes()) {
+            return Err(HeaderError::ExpectedEnd {
+                directive,
+                found: token.into_owned(),
+            });
+        }
+        scale.push_str(&token);
+        scale.push(' ');
+    }
+}
+
+/// Parse the header of each input, and find the timescale common to all of them. A input with the
+/// path `-` is read from stdin.
+///
+/// The problems that could be worked around are listed in [`Vcd::warnings`]. Without any input,
+/// or a file to append to, this returns [`Error::NoInputs`].
+pub fn parse_headers<'b>(
+    inputs: impl Iterator<Item = impl AsRef<Path>> + 'b,
+    header: &mut Header,
+    options: &ParseOptions,
+) -> Result<Vec<Vcd>, Error> {
+    let _span = tracing::info_span!("gathering").entered();
+    let mut vcds = Vec::new();
+    let mut known = HashMap::default();
+    let mut remaining_signals = options.limit_signals;
+
+    // the identifiers of each name in the symbol map, in reverse order so they can be popped.
+    let mut assigned: HashMap<String, Vec<IdCode>> = HashMap::default();
+    for (name, id) in options.symbol_map.iter().rev() {
+        let id = IdCode::from(id.as_bytes());
+        reserve_code(id);
+        assigned.entry(name.clone()).or_default().push(id);
+    }
+
+    if let Some(existing) = &options.append {
+        let declarations = Declarations {
+            keep_ids: true,
+            known: Some(&mut known),
+            exclude_types: &options.exclude_types,
+            renames: &options.renames,
+            avoid_chars: &options.avoid_chars,
+            remaining_signals: remaining_signals.as_mut(),
+            ..Default::default()
+        };
+        vcds.push(parse_header(existing, header, options, declarations)?);
+    }
+
+    let appended = vcds.len();
+    let mut file_scopes = HashSet::default();
+
+    for input in inputs {
+        let filters = options
+            .filters
+            .iter()
+            .filter(|filter| filter.input.as_deref().is_none_or(|x| x == input.as_ref()))
+            .collect();
+        let mut declarations = Declarations {
+            known: (options.append.is_some() || options.unify_by_name).then_some(&mut known),
+            exclude_types: &options.exclude_types,
+            filters,
+            renames: &options.renames,
+            avoid_chars: &options.avoid_chars,
+            remaining_signals: remaining_signals.as_mut(),
+            assigned: Some(&mut assigned),
+            ..Default::default()
+        };
+        if options.scope_per_file {
+            let name = file_scope_name(input.as_ref(), &mut file_scopes);
+            declarations.push_scope(&name);
+            declarations.list.push(Declaration::Scope {
+                kind: "module".to_string(),
+                name,
+            });
+        }
+        let mut vcd = parse_header(input.as_ref(), header, options, declarations)?;
+        if options.scope_per_file {
+            vcd.declarations.push(Declaration::Upscope);
+        }
+        vcds.push(vcd);
+    }
+
+    let inputs = &mut vcds[appended..];
+    for (vcd, &bit_order) in inputs.iter_mut().zip(options.bit_orders.iter()) {
+        vcd.bit_order = bit_order;
+    }
+    for (vcd, &offset) in inputs.iter_mut().zip(options.time_offsets.iter()) {
+        vcd.offset = offset;
+        vcd.negative_time = options.negative_time;
+        if offset < 0 {
+            let action = match options.negative_time {
+                NegativeTime::Clamp => "clamped to 0",
+                NegativeTime::Drop => "dropped",
+            };
+            vcd.warnings.push(format!(
+                "changes of {} before time 0 after the offset of {} will be {}",
+                vcd.path.display(),
+                offset,
+                action
+            ));
+        }
+    }
+
+    if options.limit_signals.is_some() || !options.filters.is_empty() {
+        for vcd in vcds.iter_mut() {
+            let declarations = std::mem::take(&mut vcd.declarations);
+            vcd.declarations = retain_declarations(declarations, |_| true);
+        }
+    }
+
+    if vcds.is_empty() {
+        return Err(Error::NoInputs);
+    }
+
+    set_common_timescale(&mut vcds, header, options.output_timescale);
+
+    Ok(vcds)
+}
+
+/// The name of the scope of a input, for [`ParseOptions::scope_per_file`]. This is the name of the
+/// file without its extension, followed by a index if it is already in `used`.
+fn file_scope_name(input: &Path, used: &mut HashSet<String>) -> String {
+    if input == Path::new("-") {
+        return unique_name("stdin".to_string(), used);
+    }
+    let stem = input
+        .file_stem()
+        .map(|x| x.to_string_lossy())
+        .unwrap_or_default()
+        .replace(|c: char| c.is_ascii_whitespace(), "_");
+
+    unique_name(stem, used)
+}
+
+/// `name`, followed by a index if it is already in `used`, and add it to `used`.
+fn unique_name(name: String, used: &mut HashSet<String>) -> String {
+    let mut unique = name.clone();
+    let mut index = 0;
+    while !used.insert(unique.clone()) {
+        index += 1;
+        unique = format!("{}_{}", name, index);
+    }
+    unique
+}
+
+/// A scope or var declaration of a input.
+enum Declaration {
+    Scope {
+        kind: String,
+        name: String,
+    },
+    Var {
+        ty: String,
+        width: u64,
+        old_id: IdCode,
+        new_id: IdCode,
+        name: String,
+        /// The name exactly as written in the input, including the whitespace around it.
+        raw_name: String,
+        /// The hierarchical name, with scopes separated by a `.`.
+        path: String,
+    },
+    Upscope,
+}
+impl Declaration {
+    /// Write the declaration to the output, using the new identifier.
+    fn write(&self, out: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            Declaration::Scope { kind, name } => writeln!(out, "$scope {} {} $end", kind, name),
+            Declaration::Var {
+                ty,
+                width,
+                new_id,
+                raw_name,
+                ..
+            } => {
+                write!(out, "$var {} {} ", ty, width)?;
+                out.write_all(new_id.as_bytes())?;
+                writeln!(out, "{}$end", raw_name)
+            }
+            Declaration::Upscope => writeln!(out, "$upscope $end"),
+        }
+    }
+}
+
+/// The data of a input, either mapped from its file, or read into memory if it can't be mapped.
+enum InputData {
+    Mmap(Mmap),
+    Buffer(Vec<u8>),
+}
+impl std::ops::Deref for InputData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            InputData::Mmap(map) => map,
+            InputData::Buffer(buffer) => buffer,
+        }
+    }
+}
+impl InputData {
+    /// This data, decompressed into memory if it is compressed in one of the [`Compression`]
+    /// formats.
+    fn decompressed(self) -> std::io::Result<InputData> {
+        match Compression::detect(&self) {
+            Some(format) => Ok(InputData::Buffer(format.decompress(&self)?)),
+            None => Ok(self),
+        }
+    }
+}
+
+/// The scope and var declarations of a input, as they are being parsed.
+#[derive(Default)]
+struct Declarations<'a> {
+    symbol_map: HashMap<IdCode, IdCode>,
+    signals: HashMap<IdCode, SignalInfo>,
+    /// The names of the scopes enclosing the current declaration, each followed by a `.`. Kept
+    /// between declarations, so the hierarchical name of each var is only a copy of it.
+    scope_path: String,
+    /// The length of `scope_path` before each of its scopes.
+    scope_starts: Vec<usize>,
+    list: Vec<Declaration>,
+    /// The hierarchical name and identifier of all declared vars, to skip redundant
+    /// redeclarations.
+    declared: HashSet<(String, IdCode)>,
+    /// Keep the identifiers of the input, instead of generating new ones.
+    keep_ids: bool,
+    /// Don't reserve the identifiers kept by `keep_ids`, for inputs that are only read and not
+    /// merged, so the identifiers generated later don't depend on them.
+    read_only: bool,
+    /// The types of vars that are not declared, and whose identifiers are added to `excluded`.
+    exclude_types: &'a [String],
+    /// The filters that apply to this input. Vars left out by them are added to `excluded`.
+    filters: Vec<&'a SignalFilter>,
+    excluded: HashSet<IdCode>,
+    /// The names given to vars declared without a name.
+    unnamed: Vec<String>,
+    /// Pairs of old and new hierarchical names of vars to rename.
+    renames: &'a [(String, String)],
+    /// Characters that are not used in generated identifiers.
+    avoid_chars: &'a [u8],
+    /// The number of signals that can still be declared, for [`ParseOptions::limit_signals`].
+    /// Vars that would need a new identifier after it reaches zero are added to `excluded`.
+    remaining_signals: Option<&'a mut usize>,
+    /// The identifiers to use for vars with these hierarchical names, from
+    /// [`ParseOptions::symbol_map`], in reverse order.
+    assigned: Option<&'a mut HashMap<String, Vec<IdCode>>>,
+    /// Signals that are already declared, by hierarchical name. Vars with these names are mapped
+    /// to the existing identifier, and are not declared again. New vars are added to it.
+    known: Option<&'a mut HashMap<String, IdCode>>,
+}
+impl Declarations<'_> {
+    fn push_scope(&mut self, name: &str) {
+        self.scope_starts.push(self.scope_path.len());
+        self.scope_path.push_str(name);
+        self.scope_path.push('.');
+    }
+
+    fn pop_scope(&mut self) {
+        if let Some(start) = self.scope_starts.pop() {
+            self.scope_path.truncate(start);
+        }
+    }
+
+    /// Whether a var with the hierarchical name `path` is kept by the `filters`.
+    fn is_kept(&self, path: &str) -> bool {
+        let matches =
+            |filter: &&SignalFilter| glob_match(filter.pattern.as_bytes(), path.as_bytes());
+        let mut includes = self.filters.iter().filter(|f| f.include).peekable();
+        let included = includes.peek().is_none() || includes.any(matches);
+        included && !self.filters.iter().filter(|f| !f.include).any(matches)
+    }
+
+    /// Parse a `$scope`, `$var` or `$upscope` declaration, starting after its `keyword`.
+    fn parse(&mut self, keyword: &str, tokens: &mut HeaderTokens) -> Result<(), HeaderError> {
+        match keyword {
+            "$scope" => {
+                let module = tokens.expect_token("$scope")?;
+                let name = tokens.expect_token("$scope")?;
+                tokens.expect_end("$scope")?;
+
+                self.push_scope(&name);
+                self.list.push(Declaration::Scope {
+                    kind: module.into_owned(),
+                    name: name.into_owned(),
+                });
+            }
+            "$var" => {
+                let ty = tokens.expect_token("$var")?;
+                let width = tokens.expect_token("$var")?;
+                let old_id = tokens.expect_token("$var")?;
+                let mut raw_name = String::from_utf8_lossy(tokens.raw_to_end("$var")?).into_owned();
+                let mut name = raw_name.trim().to_string();
+                if name.is_empty() {
+                    name = format!("unnamed_{}", old_id);
+                    raw_name = format!(" {} ", name);
+                    self.unnamed.push(name.clone());
+                }
+
+                let width = match width.parse::<u64>() {
+                    Ok(width) => width,
+                    Err(_) => return Err(HeaderError::InvalidWidth(width.into_owned())),
+                };
+                if old_id.len() > 4 {
+                    return Err(HeaderError::InvalidIdentifier(old_id.into_owned()));
+                }
+
+                let old_id = IdCode::from(old_id.as_bytes());
+
+                if self.exclude_types.iter().any(|x| *x == ty) {
+                    self.excluded.insert(old_id);
+                    return Ok(());
+                }
+                let ty = ty.into_owned();
+
+                let mut path = String::with_capacity(self.scope_path.len() + name.len());
+                path.push_str(&self.scope_path);
+                path.push_str(&name);
+
+                if !self.filters.is_empty() && !self.is_kept(&path) {
+                    self.excluded.insert(old_id);
+                    return Ok(());
+                }
+
+                let (name, raw_name, path) = match self.renames.iter().find(|(old, _)| *old == path)
+                {
+                    Some((_, new)) => {
+                        let leaf = new.rsplit_once('.').map_or(new.as_str(), |(_, leaf)| leaf);
+                        (leaf.to_string(), format!(" {} ", leaf), new.clone())
+                    }
+                    None => (name, raw_name, path),
+                };
+
+                let known_id = self.known.as_ref().and_then(|x| x.get(&path).copied());
+                if known_id.is_none() && !self.symbol_map.contains_key(&old_id) {
+                    match self.remaining_signals.as_deref_mut() {
+                        Some(0) => {
+                            self.excluded.insert(old_id);
+                            return Ok(());
+                        }
+                        Some(remaining) => *remaining -= 1,
+                        None => {}
+                    }
+                }
+                let new_id = *self.symbol_map.entry(old_id).or_insert_with(|| {
+                    if self.keep_ids {
+                        if !self.read_only {
+                            reserve_code(old_id);
+                        }
+                        old_id
+                    } else {
+                        known_id
+                            .or_else(|| self.assigned.as_mut()?.get_mut(&path)?.pop())
+                            .unwrap_or_else(|| next_code(self.avoid_chars))
+                    }
+                });
+                if let Some(known) = &mut self.known {
+                    known.entry(path.clone()).or_insert(new_id);
+                }
+
+                if !self.declared.insert((path.clone(), old_id)) {
+                    return Ok(());
+                }
+
+                self.signals.entry(old_id).or_insert_with(|| SignalInfo {
+                    name: path.clone(),
+                    ty: ty.clone(),
+                    width,
+                });
+
+                if known_id.is_some() {
+                    return Ok(());
+                }
+
+                self.list.push(Declaration::Var {
+                    ty,
+                    width,
+                    old_id,
+                    new_id,
+                    name,
+                    raw_name,
+                    path,
+                });
+            }
+            "$upscope" => {
+                tokens.expect_end("$upscope")?;
+                self.list.push(Declaration::Upscope);
+                self.pop_scope();
+            }
+            _ => unreachable!("not a declaration: {}", keyword),
+        }
+        Ok(())
+    }
+}
+
+/// The result of parsing the header of a input.
+struct ParsedHeader<'a> {
+    declarations: Declarations<'a>,
+    /// The timescale of the input, in femtoseconds.
+    timescale: u64,
+    /// The content of the `$timescale` command, if any.
+    raw_timescale: Option<String>,
+    /// The content of the `$timescale` command, if it could not be parsed and the fallback
+    /// timescale was used instead.
+    invalid_timescale: Option<String>,
+    /// A `$dumpvars` block followed by more declarations.
+    early_dumpvars: Option<Range<usize>>,
+    /// The positions of the value changes found among the declarations, which are skipped. Only
+    /// the first of consecutive value changes is listed.
+    stray_changes: Vec<usize>,
+    /// The positions of the first token after a `$end` in the same line, for each line with tokens
+    /// after a `$end`, which are skipped.
+    trailing_tokens: Vec<usize>,
+    end_of_definitions: usize,
+}
+
+/// Parse the header of a VCD file, discarding the result. This is the entry point for fuzzing the
+/// header parser.
+#[doc(hidden)]
+pub fn fuzz_parse_header(data: &[u8]) -> Result<(), HeaderError> {
+    let options = ParseOptions {
+        rescan_declarations: true,
+        ..Default::default()
+    };
+    let declarations = Declarations::default();
+    parse_header_data(data, &mut Header::default(), &options, declarations).map(|_| ())
+}
+
+fn parse_header(
+    input: &Path,
+    header: &mut Header,
+    options: &ParseOptions,
+    declarations: Declarations<'_>,
+) -> Result<Vcd, Error> {
+    let _span = tracing::debug_span!("parse_file", path = %input.display()).entered();
+    let read_error = |source| Error::Read {
+        path: input.to_path_buf(),
+        source,
+    };
+    let data = if input == Path::new("-") {
+        // stdin can't be mapped, so it is read whole into memory.
+        let mut buffer = Vec::new();
+        std::io::stdin()
+            .lock()
+            .read_to_end(&mut buffer)
+            .map_err(read_error)?;
+        InputData::Buffer(buffer)
+    } else if options.no_mmap {
+        InputData::Buffer(std::fs::read(input).map_err(read_error)?)
+    } else {
+        let file = std::fs::File::open(input).map_err(read_error)?;
+
+        let memmap = unsafe { memmap2::MmapOptions::new().map(&file) };
+        let memmap = memmap.map_err(|source| Error::Map {
+            path: input.to_path_buf(),
+            source,
+        })?;
+        InputData::Mmap(memmap)
+    };
+    let data = data.decompressed().map_err(read_error)?;
+
+    let parsed = parse_header_data(&data, header, options, declarations).map_err(|source| {
+        Error::Header {
+            path: input.to_path_buf(),
+            source,
+        }
+    })?;
+
+    let mut warnings = Vec::new();
+
+    if let Some(scale) = &parsed.invalid_timescale {
+        let reason = match parse_timescale(scale) {
+            Some(0) => " (timescale must be positive)",
+            _ => "",
+        };
+        warnings.push(format!(
+            "invalid timescale {:?}{} in {}, using {} instead",
+            scale.trim(),
+            reason,
+            input.display(),
+            format_timescale(parsed.timescale)
+        ));
+    }
+    if !parsed.stray_changes.is_empty() {
+        warnings.push(format!(
+            "skipped stray value changes in {} places among the declarations of {}",
+            parsed.stray_changes.len(),
+            input.display()
+        ));
+    }
+    if !parsed.trailing_tokens.is_empty() {
+        warnings.push(format!(
+            "skipped tokens after $end in {} places in the header of {}",
+            parsed.trailing_tokens.len(),
+            input.display()
+        ));
+    }
+    for name in &parsed.declarations.unnamed {
+        warnings.push(format!(
+            "$var without a name in {}, named {}",
+            input.display(),
+            name
+        ));
+    }
+
+    Ok(Vcd {
+        symbol_map: parsed.declarations.symbol_map,
+        signals: parsed.declarations.signals,
+        excluded: parsed.declarations.excluded,
+        declarations: parsed.declarations.list,
+        end_of_definitions: parsed.end_of_definitions,
+        early_dumpvars: parsed.early_dumpvars,
+        file: data,
+        path: input.to_path_buf(),
+        timescale: parsed.timescale,
+        timescale_divisor: 1,
+        input_timescale: parsed.timescale,
+        raw_timescale: parsed.raw_timescale,
+        offset: 0,
+        negative_time: NegativeTime::default(),
+        bit_order: BitOrder::default(),
+        warnings,
+    })
+}
+
+fn parse_header_data<'a>(
+    data: &[u8],
+    header: &mut Header,
+    options: &ParseOptions,
+    mut declarations: Declarations<'a>,
+) -> Result<ParsedHeader<'a>, HeaderError> {
+    if let Some(format) = Compression::detect(data) {
+        return Err(HeaderError::Compressed(format.name()));
+    }
+
+    let mut tokens = HeaderTokens::new(data);
+
+    // skip the UTF-8 byte order mark, if any.
+    if data.starts_with(b"\xEF\xBB\xBF") {
+        tokens.pos = 3;
+    }
+
+    let mut timescale = 0;
+    let mut raw_timescale = None;
+    let mut invalid_timescale = None;
+    let mut end_of_definitions = None;
+    let mut stray_changes = Vec::new();
+    let mut early_dumpvars = None;
+
+    while let Some(token) = tokens.next() {
+        if tokens.pos - tokens.line_start > MAX_HEADER_LINE {
+            return Err(HeaderError::NoLineBreaks);
+        }
+        match &*token {
+            "$date" => {
+                let date = take_to_end(&mut tokens, "$date")?;
+                if header.date.is_none() {
+                    header.date = Some(date);
+                }
+            }
+            "$version" => {
+                let version = take_to_end(&mut tokens, "$version")?;
+                if header.version.is_none() {
+                    header.version = Some(version);
+                }
+            }
+            "$timescale" => {
+                let scale = String::from_utf8_lossy(tokens.raw_to_end("$timescale")?).into_owned();
+                if is_fractional_timescale(&scale) {
+                    return Err(HeaderError::FractionalTimescale(scale.trim().to_string()));
+                }
+                timescale = match parse_timescale(&scale) {
+                    Some(x) if x != 0 => x,
+                    _ => {
+                        invalid_timescale = Some(scale.clone());
+                        options.fallback_timescale.unwrap_or(DEFAULT_TIMESCALE)
+                    }
+                };
+                raw_timescale = Some(scale);
+            }
+            "$scope" | "$var" | "$upscope" => {
+                declarations.parse(&token, &mut tokens)?;
+            }
+            "$comment" => {
+                tokens.raw_to_end("$comment")?;
+            }
+            "$enddefinitions" => {
+                // the data may start in the same line, so the rest of it is not skipped.
+                tokens.expect_end_token("$enddefinitions")?;
+                end_of_definitions = Some(tokens.end_of_line());
+                break;
+            }
+            "$dumpvars" if early_dumpvars.is_none() => {
+                // the initial values are usually the start of the data, but some files declare
+                // more signals after them, so they are kept apart and the header continues.
+                let start = tokens.start;
+                let mut after = tokens.clone();
+                let is_early =
+                    after.raw_to_end("$dumpvars").is_ok() && declaration_after(&after).is_some();
+                if is_early {
+                    early_dumpvars = Some(start..after.pos);
+                    tokens = after;
+                    continue;
+                }
+                end_of_definitions = Some(start);
+                break;
+            }
+            x => {
+                let stray_end = match x.starts_with('$') || is_timestamp(x.as_bytes()) {
+                    true => None,
+                    false => declaration_after(&tokens),
+                };
+                if let Some(end) = stray_end {
+                    // a stray value change among the declarations, which are parsed up to the
+                    // end.
+                    stray_changes.push(tokens.start);
+                    tokens.pos = end;
+                    continue;
+                }
+                // a `$dumpvars` or a value change without a `$enddefinitions` before it, so the
+                // data starts at this token, which may share its line with the header.
+                end_of_definitions = Some(tokens.start);
+                break;
+            }
+        }
+    }
+
+    if timescale == 0 {
+        // only a fallback timescale of zero can give a zero timescale.
+        if raw_timescale.is_some() {
+            return Err(HeaderError::ZeroTimescale);
+        }
+        return Err(HeaderError::MissingTimescale);
+    }
+
+    let end_of_definitions = end_of_definitions.unwrap_or(tokens.pos);
+
+    if options.rescan_declarations {
+        let data = &data[end_of_definitions..];
+        let lines = data
+            .split(|&b| b == b'\n')
+            .map(|line| line.trim_ascii_start())
+            .filter(|line| {
+                let keyword = line.split(|b| b.is_ascii_whitespace()).next();
+                matches!(keyword, Some(b"$scope" | b"$var" | b"$upscope"))
+            });
+        for line in lines {
+            let mut tokens = HeaderTokens::new(line);
+            while let Some(token) = tokens.next() {
+                if let "$scope" | "$var" | "$upscope" = &*token {
+                    declarations.parse(&token, &mut tokens)?;
+                }
+            }
+        }
+    }
+
+    Ok(ParsedHeader {
+        declarations,
+        timescale,
+        raw_timescale,
+        invalid_timescale,
+        early_dumpvars,
+        stray_changes,
+        trailing_tokens: tokens.trailing,
+        end_of_definitions,
+    })
+}
+
+/// The position of the next declaration after the last token of `tokens`, if it comes before any
+/// timestamp or other command, skipping value changes. This tells a stray value change or
+/// `$dumpvars` among the declarations from the start of the data.
+fn declaration_after(tokens: &HeaderTokens) -> Option<usize> {
+    let mut tokens = tokens.clone();
+    loop {
+        match &*tokens.next()? {
+            "$scope" | "$var" | "$upscope" | "$enddefinitions" => return Some(tokens.start),
+            x if x.starts_with('$') || is_timestamp(x.as_bytes()) => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Whether a token is a timestamp, like `#10` or `#0x1F4`, and not a identifier starting with `#`.
+fn is_timestamp(token: &[u8]) -> bool {
+    matches!(token, [b'#', time @ ..] if !time.is_empty() && parse_time(time).is_ok())
+}
+
+/// Parse a timescale like `1 ns` or `100fs` to femtoseconds.
+pub fn parse_timescale(scale: &str) -> Option<u64> {
+    // parse .*\d*.*(fs|ps|ns|us|ms|s)
+    let n = scale.find(|x: char| x.is_ascii_digit())?;
+    let e = scale[n..].find(|x: char| !x.is_ascii_digit())?;
+    let u = scale[n + e..].find(['f', 'p', 'n', 'u', 'm', 's'])?;
+
+    // a fractional number like `1.5` would be read as `1`, but timescales must be integers.
+    if is_fractional_timescale(scale) {
+        return None;
+    }
+
+    let number = scale[n..n + e].parse::<u64>().ok()?;
+    let unit = &scale.as_bytes()[n + e + u..];
+    let factor = match unit.get(..2)? {
+        b"fs" => 1,
+        b"ps" => 1_000,
+        b"ns" => 1_000_000,
+        b"us" => 1_000_000_000,
+        b"ms" => 1_000_000_000_000,
+        [b's', _] => 1_000_000_000_000_000,
+        _ => return None,
+    };
+    number.checked_mul(factor)
+}
+
+/// Whether the number of a timescale has a fractional part, like in `1.5 ns` or `.5 ns`.
+fn is_fractional_timescale(scale: &str) -> bool {
+    let Some(n) = scale.find(|x: char| x.is_ascii_digit()) else {
+        return false;
+    };
+    let e = scale[n..]
+        .find(|x: char| !x.is_ascii_digit())
+        .unwrap_or(scale.len() - n);
+    scale[..n].ends_with('.') || scale[n + e..].starts_with('.')
+}
+
+/// The largest ratio between the timescale of a input and the output timescale without a warning.
+/// Above it, timestamps over about 18 billion overflow.
+const MAX_TIMESCALE_RATIO: u64 = 1_000_000_000;
+
+fn set_common_timescale(vcds: &mut [Vcd], header: &mut Header, output_timescale: Option<u64>) {
+    let output = output_timescale.unwrap_or_else(|| {
+        vcds.iter()
+            .map(|vcd| vcd.timescale)
+            .fold(vcds[0].timescale, gcd)
+    });
+
+    let timescale = format_timescale(output);
+
+    for vcd in vcds.iter_mut() {
+        let common = gcd(vcd.timescale, output);
+        vcd.timescale /= common;
+        vcd.timescale_divisor = output / common;
+
+        if vcd.timescale_divisor != 1 {
+            let warning = format!(
+                "the timescale of {} is finer than the output timescale of {}, its timestamps \
+                 are rounded down",
+                vcd.path.display(),
+                timescale,
+            );
+            vcd.warnings.push(warning);
+        }
+
+        // the output timescale is usually the finest of the inputs, so the timestamps of much
+        // coarser inputs are multiplied by a large factor, and can overflow.
+        if vcd.timescale / vcd.timescale_divisor > MAX_TIMESCALE_RATIO {
+            // the finest output timescale that keeps the ratio of this input under the limit.
+            let suggested = vcd.input_timescale.div_ceil(MAX_TIMESCALE_RATIO);
+            let warning = format!(
+                "the output timescale of {} is {} times finer than the timescale of {}, \
+                 whose timestamps above {} overflow and fail the merge; pass `--timescale {}` \
+                 for a coarser output timescale",
+                timescale,
+                vcd.timescale,
+                vcd.path.display(),
+                u64::MAX / vcd.timescale,
+                format_timescale(suggested),
+            );
+            vcd.warnings.push(warning);
+        }
+    }
+
+    header.timescale = Some(timescale);
+}
+
+/// Format a timescale in femtoseconds using the largest unit that divides it.
+fn format_timescale(timescale: u64) -> String {
+    TimescaleLabel::default().format(timescale)
+}
+
+/// The largest unit that divides a timescale in femtoseconds.
+fn largest_unit(timescale: u64) -> TimeUnit {
+    TimeUnit::ALL
+        .into_iter()
+        .find(|unit| timescale.is_multiple_of(unit.femtoseconds()))
+        .unwrap_or(TimeUnit::Fs)
+}
+
+fn gcd(mut n: u64, mut m: u64) -> u64 {
+    assert!(n != 0 && m != 0);
+    while m != 0 {
+        if m < n {
+            (m, n) = (n, m);
+        }
+        m %= n;
+    }
+    n
+}
+
+/// The hierarchical names of the vars that are declared by some inputs but not by others, sorted
+/// by name, each with the paths of the inputs that don't declare it.
+pub fn missing_signals(vcds: &[Vcd]) -> Vec<(String, Vec<&Path>)> {
+    let mut declared_by: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, vcd) in vcds.iter().enumerate() {
+        for declaration in &vcd.declarations {
+            if let Declaration::Var { path, .. } = declaration {
+                let inputs = declared_by.entry(path).or_default();
+                if inputs.last() != Some(&i) {
+                    inputs.push(i);
+                }
+            }
+        }
+    }
+
+    declared_by
+        .into_iter()
+        .filter(|(_, inputs)| inputs.len() < vcds.len())
+        .map(|(path, inputs)| {
+            let missing = (0..vcds.len())
+                .filter(|i| !inputs.contains(i))
+                .map(|i| vcds[i].path.as_path())
+                .collect();
+            (path.to_string(), missing)
+        })
+        .collect()
+}
+
+/// Remove the declarations of signals that never have a value change in any input, and the scopes
+/// that become empty. Returns the number of signals removed.
+pub fn prune_unused(vcds: &mut [Vcd]) -> usize {
+    let mut used = HashSet::default();
+    for vcd in vcds.iter() {
+        let lines = vcd.file[vcd.end_of_definitions..].split(|&b| b == b'\n');
+        for line in lines {
+            let Some((_, symbol)) = split_value_change(line.trim_ascii_end()) else {
+                continue;
+            };
+            if symbol.len() > 4 {
+                continue;
+            }
+            if let Some(new_id) = vcd.symbol_map.get(&IdCode::from(symbol)) {
+                used.insert(*new_id);
+            }
+        }
+    }
+
+    let mut pruned = 0;
+    for vcd in vcds.iter_mut() {
+        let declarations = std::mem::take(&mut vcd.declarations);
+        vcd.declarations = retain_declarations(declarations, |new_id| {
+            let keep = used.contains(&new_id);
+            if !keep {
+                pruned += 1;
+            }
+            keep
+        });
+
+        vcd.symbol_map.retain(|_, new_id| used.contains(new_id));
+        let symbol_map = &vcd.symbol_map;
+        vcd.signals
+            .retain(|old_id, _| symbol_map.contains_key(old_id));
+    }
+
+    pruned
+}
+
+/// Remove the vars whose new identifier doesn't pass `keep`, and the scopes that are empty.
+fn retain_declarations(
+    list: Vec<Declaration>,
+    mut keep: impl FnMut(IdCode) -> bool,
+) -> Vec<Declaration> {
+    let mut declarations = Vec::with_capacity(list.len());
+    // the index in `declarations` of each open scope.
+    let mut scopes = Vec::new();
+    for declaration in list {
+        match declaration {
+            Declaration::Scope { .. } => {
+                scopes.push(declarations.len());
+                declarations.push(declaration);
+            }
+            Declaration::Var { new_id, .. } if !keep(new_id) => {}
+            Declaration::Var { .. } => declarations.push(declaration),
+            Declaration::Upscope => match scopes.pop() {
+                // the scope is empty, remove it.
+                Some(start) if start + 1 == declarations.len() => {
+                    declarations.pop();
+                }
+                _ => declarations.push(declaration),
+            },
+        }
+    }
+    declarations
+}
+
+/// A slice of the data of a input, where the timestamps are sorted.
+pub struct Section<'a> {
+    /// The timestamp at the start of the section, in the output timescale.
+    pub value: u64,
+    /// The data of the section, starting at its first timestamp.
+    pub section: &'a [u8],
+    /// The input that contains this section.
+    pub vcd: &'a Vcd,
+}
+impl<'a> PartialEq for Section<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+impl<'a> PartialOrd for Section<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<'a> Ord for Section<'a> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+impl<'a> Eq for Section<'a> {}
+
+fn parse_u64(s: &[u8]) -> Result<u64, ()> {
+    let mut value: u64 = 0;
+    for &b in s {
+        if !b.is_ascii_digit() {
+            return Err(());
+        }
+        value = value
+            .checked_mul(10)
+            .and_then(|x| x.checked_add((b - b'0') as u64))
+            .ok_or(())?;
+    }
+    Ok(value)
+}
+
+/// Parse the value of a timestamp, in decimal, or in hexadecimal with a `0x` prefix, as written by
+/// some tools.
+fn parse_time(s: &[u8]) -> Result<u64, ()> {
+    let [b'0', b'x' | b'X', hex @ ..] = s else {
+        return parse_u64(s);
+    };
+    if hex.is_empty() {
+        return Err(());
+    }
+    let mut value: u64 = 0;
+    for &b in hex {
+        let digit = (b as char).to_digit(16).ok_or(())?;
+        value = value
+            .checked_mul(16)
+            .and_then(|x| x.checked_add(digit as u64))
+            .ok_or(())?;
+    }
+    Ok(value)
+}
+
+fn u64_to_bytes(mut value: u64, buffer: &mut [u8; 20]) -> &[u8] {
+    if value == 0 {
+        return b"0";
+    }
+    let mut i = buffer.len();
+    while value > 0 {
+        i -= 1;
+        buffer[i] = (value % 10) as u8 + b'0';
+        value /= 10;
+    }
+    &buffer[i..]
+}
+
+/// Treat the data of each input as a single section, assuming its timestamps are already sorted.
+pub fn file_sections(vcds: &[Vcd]) -> Result<Vec<Section<'_>>, TimeError> {
+    let mut sections = Vec::new();
+    for vcd in vcds {
+        sections.extend(early_dumpvars_section(vcd)?);
+        sections.extend(data_section(vcd)?);
+    }
+    Ok(sections)
+}
+
+/// The section of a `$dumpvars` block found among the declarations of `vcd`, at time 0.
+fn early_dumpvars_section(vcd: &Vcd) -> Result<Option<Section<'_>>, TimeError> {
+    let (Some(range), Some(value)) = (vcd.early_dumpvars.clone(), vcd.time(0)?) else {
+        return Ok(None);
+    };
+    Ok(Some(Section {
+        value,
+        section: &vcd.file[range],
+        vcd,
+    }))
+}
+
+/// The data of `vcd` as a single section, for [`file_sections`].
+fn data_section(vcd: &Vcd) -> Result<Option<Section<'_>>, TimeError> {
+    let end = vcd.end_of_definitions;
+
+    // If the data starts with a timestamp, start the section on it, otherwise a empty
+    // `#0` would be written before it.
+    let mut start = end
+        + vcd.file[end..]
+            .iter()
+            .take_while(|b| b.is_ascii_whitespace())
+            .count();
+    while let [b'#', ..] = &vcd.file[start..] {
+        let line_end = vcd.file[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(vcd.file.len(), |x| start + x);
+        if let Some(value) = vcd.timestamp(&vcd.file[start + 1..line_end])? {
+            return Ok(Some(Section {
+                value,
+                section: &vcd.file[start..],
+                vcd,
+            }));
+        }
+
+        // this timestamp is dropped, so skip to the next one
+        let Some(next) = vcd.file[line_end..].windows(2).position(|x| x == b"\n#") else {
+            return Ok(None);
+        };
+        start = line_end + next + 1;
+    }
+
+    Ok(Some(Section {
+        value: 0,
+        section: &vcd.file[start..],
+        vcd,
+    }))
+}
+
+/// Find sections of sorted signal changes. These will be merged sorted when written to the output
+/// file.
+///
+/// Each input is scanned by one of up to `threads` threads.
+pub fn find_sections(
+    vcds: &[Vcd],
+    threads: usize,
+    on_progress: impl Fn(u64) + Sync,
+) -> Result<Vec<Section<'_>>, TimeError> {
+    let _span = tracing::info_span!("finding").entered();
+    let progress = AtomicU64::new(0);
+    let report = |bytes| on_progress(progress.fetch_add(bytes, Ordering::Relaxed) + bytes);
+
+    if threads <= 1 || vcds.len() <= 1 {
+        let mut sections = Vec::new();
+        for vcd in vcds {
+            sections.extend(find_file_sections(vcd, &report)?);
+        }
+        return Ok(sections);
+    }
+
+    // the threads append the sections of each input to a single Vec, so there is never more than
+    // one copy of all sections, which are most of the memory used by fragmented inputs. On errors,
+    // keep the one of the first input, independent of the number of threads.
+    let next_vcd = AtomicUsize::new(0);
+    let sections = Mutex::new(Ok(Vec::new()));
+
+    std::thread::scope(|s| {
+        for _ in 0..threads.min(vcds.len()) {
+            s.spawn(|| loop {
+                let i = next_vcd.fetch_add(1, Ordering::Relaxed);
+                let Some(vcd) = vcds.get(i) else {
+                    break;
+                };
+                let file_sections = find_file_sections(vcd, &report);
+                let mut sections = sections.lock().unwrap();
+                match (&mut *sections, file_sections) {
+                    (Ok(sections), Ok(mut file_sections)) => sections.append(&mut file_sections),
+                    (Err((j, _)), Err(err)) if i < *j => *sections = Err((i, err)),
+                    (Ok(_), Err(err)) => *sections = Err((i, err)),
+                    _ => {}
+                }
+            });
+        }
+    });
+
+    let mut sections = sections.into_inner().unwrap().map_err(|(_, err)| err)?;
+
+    // keep the sections in the order of the inputs, independent of the number of threads. The
+    // inputs are in a slice and the sections of each input start at distinct offsets, so this
+    // restores the order of the file, which is then put in time order like in find_file_sections.
+    sections.sort_unstable_by_key(|s| (s.vcd as *const Vcd, s.section.as_ptr()));
+    for file_sections in sections.chunk_by_mut(|a, b| std::ptr::eq(a.vcd, b.vcd)) {
+        reverse_descending(file_sections);
+    }
+    Ok(sections)
+}
+
+/// Find the sections of a single input. `report` is called with the number of bytes scanned since
+/// the last call.
+fn find_file_sections<'a>(
+    vcd: &'a Vcd,
+    report: &impl Fn(u64),
+) -> Result<Vec<Section<'a>>, TimeError> {
+    let _span = tracing::debug_span!("find_file", path = %vcd.path.display()).entered();
+    let mut sections = FileSections::new(vcd, report).collect::<Result<Vec<_>, _>>()?;
+    reverse_descending(&mut sections);
+    Ok(sections)
+}
+
+/// The sections of a single input, in the order of the file, found as they are iterated. `report`
+/// is called with the number of bytes scanned since the last call.
+struct FileSections<'a, R> {
+    vcd: &'a Vcd,
+    report: R,
+    /// The offset of the next line to scan, or `None` at the end of the file.
+    pos: Option<usize>,
+    /// The offset up to which the scanned bytes were reported.
+    reported: usize,
+    line_count: usize,
+    /// The `$dumpvars` block among the declarations was not yielded yet.
+    early_dumpvars: bool,
+    /// The offset, start time and last time of the section being scanned.
+    curr_section: Option<(usize, u64, u64)>,
+    before_first_timestamp: bool,
+}
+impl<'a, R: Fn(u64)> FileSections<'a, R> {
+    fn new(vcd: &'a Vcd, report: R) -> Self {
+        Self {
+            vcd,
+            report,
+            pos: Some(vcd.end_of_definitions),
+            reported: 0,
+            line_count: 0,
+            early_dumpvars: true,
+            curr_section: None,
+            before_first_timestamp: true,
+        }
+    }
+
+    /// The section from `offset` up to `end`, or to the end of the file.
+    fn section(&self, offset: usize, value: u64, end: Option<usize>) -> Section<'a> {
+        let file = &self.vcd.file[..];
+        Section {
+            value,
+            section: &file[offset..end.unwrap_or(file.len())],
+            vcd: self.vcd,
+        }
+    }
+
+    /// Scan up to the end of the next section.
+    fn next_section(&mut self) -> Result<Option<Section<'a>>, TimeError> {
+        let vcd = self.vcd;
+        if std::mem::take(&mut self.early_dumpvars) {
+            if let Some(section) = early_dumpvars_section(vcd)? {
+                return Ok(Some(section));
+            }
+        }
+
+        while let Some(offset) = self.pos {
+            let line_end = vcd.file[offset..].iter().position(|&b| b == b'\n');
+            self.pos = line_end.map(|x| offset + x + 1);
+            let line = &vcd.file[offset..line_end.map_or(vcd.file.len(), |x| offset + x)];
+
+            self.line_count += 1;
+
+            // Same logic as the one described in write_output, but this is 3 times faster
+            if self.line_count.is_multiple_of(0xC_0000) {
+                (self.report)((offset - self.reported) as u64);
+                self.reported = offset;
+            }
+
+            // changes before the first timestamp are at time 0, so start a section on them.
+            if self.before_first_timestamp
+                && self.curr_section.is_none()
+                && !matches!(line.trim_ascii(), [] | [b'#' | b'$', ..])
+            {
+                if let Some(value) = vcd.time(0)? {
+                    self.curr_section = Some((offset, value, value));
+                }
+                continue;
+            }
+
+            if let [b'#', ..] = line {
+                self.before_first_timestamp = false;
+                let Some(curr_line_value) = vcd.timestamp(&line[1..])? else {
+                    // this timestamp is dropped, so end the current section here, and only start a
+                    // new one in the next timestamp that is not dropped.
+                    if let Some((section_offset, section_value, _)) = self.curr_section.take() {
+                        return Ok(Some(self.section(
+                            section_offset,
+                            section_value,
+                            Some(offset),
+                        )));
+                    }
+                    continue;
+                };
+
+                // if this is the first line, start a new section
+                let Some((section_offset, section_value, last_line_value)) = self.curr_section
+                else {
+                    self.curr_section = Some((offset, curr_line_value, curr_line_value));
+                    continue;
+                };
+
+                // if out of order, end this section here
+                if curr_line_value < last_line_value {
+                    self.curr_section = Some((offset, curr_line_value, curr_line_value));
+                    return Ok(Some(self.section(
+                        section_offset,
+                        section_value,
+                        Some(offset),
+                    )));
+                }
+                self.curr_section = Some((section_offset, section_value, curr_line_value));
+            }
+        }
+
+        // add the last section
+        if let Some((offset, value, _)) = self.curr_section.take() {
+            return Ok(Some(self.section(offset, value, None)));
+        }
+        if self.reported < vcd.file.len() {
+            (self.report)((vcd.file.len() - self.reported) as u64);
+            self.reported = vcd.file.len();
+        }
+        Ok(None)
+    }
+}
+impl<'a, R: Fn(u64)> Iterator for FileSections<'a, R> {
+    type Item = Result<Section<'a>, TimeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_section().transpose()
+    }
+}
+
+/// A file in reverse time order has a section for each timestamp, in descending order. Put them
+/// back in time order, like the sections of a sorted file. There are no sections with the same
+/// start, so this doesn't change the order of changes at the same timestamp.
+fn reverse_descending(sections: &mut [Section]) {
+    if sections.len() > 1 && sections.windows(2).all(|w| w[0].value > w[1].value) {
+        sections.reverse();
+    }
+}
+
+/// The sections of the inputs to merge with [`write_output`], made from the ones of
+/// [`find_sections`] or [`file_sections`], or by [`lazy_sections`]. Inputs whose sections start in
+/// ascending time order, like fragmented inputs with many small jumps back in time, are streamed:
+/// their sections are only found while merging, so only a few of them are in memory at once.
+pub struct LazySections<'a> {
+    /// The sections found up front, each with its order among the sections that start at the
+    /// same time: the index of its input, and its position in the input.
+    sections: Vec<(Section<'a>, (usize, usize))>,
+    /// The streamed inputs, each with its index.
+    streams: Vec<(usize, StreamedSections<'a>)>,
+}
+
+/// The sections of a streamed input of [`LazySections`], found while merging, whose progress is
+/// reported by the merge instead.
+type StreamedSections<'a> = FileSections<'a, fn(u64)>;
+impl<'a> From<Vec<Section<'a>>> for LazySections<'a> {
+    fn from(sections: Vec<Section<'a>>) -> Self {
+        // the sections are already in the order of the inputs.
+        let sections = sections
+            .into_iter()
+            .enumerate()
+            .map(|(i, section)| (section, (0, i)))
+            .collect();
+        LazySections {
+            sections,
+            streams: Vec::new(),
+        }
+    }
+}
+impl LazySections<'_> {
+    /// The number of bytes of the data of all sections, streamed or not.
+    pub fn data_len(&self) -> u64 {
+        let sections = self.sections.iter().map(|(s, _)| s.section.len() as u64);
+        let streams = self.streams.iter().map(|(_, stream)| {
+            let vcd = stream.vcd;
+            let early_dumpvars = vcd.early_dumpvars.as_ref().map_or(0, |x| x.len());
+            (vcd.file.len() - vcd.end_of_definitions + early_dumpvars) as u64
+        });
+        sections.sum::<u64>() + streams.sum::<u64>()
+    }
+
+    /// The number of sections found up front, and of streamed inputs.
+    pub fn counts(&self) -> (usize, usize) {
+        (self.sections.len(), self.streams.len())
+    }
+}
+
+/// Find the sections of the inputs like [`find_sections`], but only keep the ones of inputs whose
+/// sections don't start in ascending time order. The others are streamed, finding their sections
+/// again while merging.
+///
+/// Each input is scanned by one of up to `threads` threads. The sections are merged in the same
+/// order as the ones of [`find_sections`], but checkpoints are not written while there are
+/// streamed inputs, see [`WriteOptions::checkpoint_path`].
+pub fn lazy_sections(
+    vcds: &[Vcd],
+    threads: usize,
+    on_progress: impl Fn(u64) + Sync,
+) -> Result<LazySections<'_>, TimeError> {
+    let _span = tracing::info_span!("finding").entered();
+    let progress = AtomicU64::new(0);
+    let report = |bytes| on_progress(progress.fetch_add(bytes, Ordering::Relaxed) + bytes);
+
+    let next_vcd = AtomicUsize::new(0);
+    let scanned = Mutex::new(Vec::new());
+    std::thread::scope(|s| {
+        for _ in 0..threads.clamp(1, vcds.len().max(1)) {
+            s.spawn(|| loop {
+                let i = next_vcd.fetch_add(1, Ordering::Relaxed);
+                let Some(vcd) = vcds.get(i) else {
+                    break;
+                };
+                let sections = unordered_file_sections(vcd, &report);
+                scanned.lock().unwrap().push((i, sections));
+            });
+        }
+    });
+
+    // like in find_sections, the error of the first input is kept.
+    let mut scanned = scanned.into_inner().unwrap();
+    scanned.sort_unstable_by_key(|(i, _)| *i);
+
+    let mut lazy = LazySections {
+        sections: Vec::new(),
+        streams: Vec::new(),
+    };
+    for (input, sections) in scanned {
+        match sections? {
+            Some(sections) => {
+                let sections = sections.into_iter().enumerate();
+                lazy.sections
+                    .extend(sections.map(|(i, section)| (section, (input, i))));
+            }
+            None => lazy
+                .streams
+                .push((input, FileSections::new(&vcds[input], |_| {}))),
+        }
+    }
+    Ok(lazy)
+}
+
+/// The sections of a input like [`find_file_sections`], or `None` if they start in ascending time
+/// order, so they can be streamed by [`lazy_sections`] instead.
+fn unordered_file_sections<'a>(
+    vcd: &'a Vcd,
+    report: &impl Fn(u64),
+) -> Result<Option<Vec<Section<'a>>>, TimeError> {
+    let mut scan = FileSections::new(vcd, report);
+    let mut last_value = 0;
+    while let Some(section) = scan.next_section()? {
+        if section.value < last_value {
+            report((vcd.file.len() - scan.reported) as u64);
+            return find_file_sections(vcd, &|_| {}).map(Some);
+        }
+        last_value = section.value;
+    }
+    Ok(None)
+}
+
+/// The error of writing `output`, or of the timestamp that could not be merged, if that is the
+/// cause.
+fn write_error(output: &Path, source: std::io::Error) -> Error {
+    let time_error = source.get_ref().and_then(|x| x.downcast_ref::<TimeError>());
+    match time_error {
+        Some(err) => Error::Time(err.clone()),
+        None => Error::Write {
+            path: output.to_path_buf(),
+            source,
+        },
+    }
+}
+
+/// Write the merged VCD to `output`, merge-sorting the timestamps of all sections.
+///
+/// The output is first written to [`partial_path`], and only renamed to `output` when complete, so
+/// a failed merge never leaves a incomplete file at `output`.
+pub fn write_output<'a>(
+    output: impl AsRef<Path>,
+    headers: Header,
+    vcds: &'a [Vcd],
+    sections: impl Into<LazySections<'a>>,
+    options: &WriteOptions,
+    on_progress: impl FnMut(u64),
+) -> Result<(), Error> {
+    let _span = tracing::info_span!("merging").entered();
+    let partial = partial_path(output.as_ref());
+    let out_file = std::fs::OpenOptions::new()
+        .read(options.mmap_output)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&partial)
+        .map_err(|source| Error::Create {
+            path: partial.clone(),
+            source,
+        })?;
+
+    let sections = sections.into();
+    let result = write_output_file(out_file, headers, vcds, sections, options, on_progress);
+    finish_output(&partial, output.as_ref(), result, options)
+        .map_err(|source| write_error(output.as_ref(), source))
+}
+
+/// The path where the output is written before it is complete: `output` followed by `.partial`.
+pub fn partial_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".partial");
+    PathBuf::from(path)
+}
+
+/// Move the `partial` output to `output` if the merge succeeded, and remove its checkpoint, as
+/// there is nothing left to resume. Otherwise it is removed, unless it can still be resumed from a
+/// checkpoint.
+fn finish_output(
+    partial: &Path,
+    output: &Path,
+    result: std::io::Result<()>,
+    options: &WriteOptions,
+) -> std::io::Result<()> {
+    match result {
+        Ok(()) => {
+            std::fs::rename(partial, output)?;
+            if let Some(checkpoint) = &options.checkpoint_path {
+                let _ = std::fs::remove_file(checkpoint);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            if options.checkpoint_path.is_none() {
+                let _ = std::fs::remove_file(partial);
+            }
+            Err(err)
+        }
+    }
+}
+
+fn write_output_file<'a>(
+    out_file: std::fs::File,
+    headers: Header,
+    vcds: &'a [Vcd],
+    sections: LazySections<'a>,
+    options: &WriteOptions,
+    on_progress: impl FnMut(u64),
+) -> std::io::Result<()> {
+    if options.mmap_output {
+        // the output is usually about the size of the inputs, and grows if this is not enough.
+        let estimate = vcds
+            .iter()
+            .map(|vcd| vcd.end_of_definitions as u64)
+            .sum::<u64>()
+            + sections.data_len();
+        let mut out_writer = CountingWriter::new(MmapWriter::new(out_file, estimate)?, 0);
+        write_header(&mut out_writer, headers, vcds, options)?;
+        merge_sections(&mut out_writer, vcds, sections, None, options, on_progress)?;
+        out_writer.inner.finish()
+    } else {
+        let out_writer = BufWriter::with_capacity(0x1_0000, out_file); // 64KiB
+        let mut out_writer = CountingWriter::new(out_writer, 0);
+        write_header(&mut out_writer, headers, vcds, options)?;
+        merge_sections(&mut out_writer, vcds, sections, None, options, on_progress)?;
+        out_writer.flush()
+    }
+}
+
+/// Continue a merge interrupted after writing `checkpoint`, truncating the [`partial_path`] of
+/// `output` to the length it had at the checkpoint. `sections` are the [`Checkpoint::sections`],
+/// and the inputs and options must be the same as the ones of the interrupted merge.
+pub fn resume_output<'a>(
+    output: impl AsRef<Path>,
+    vcds: &'a [Vcd],
+    sections: Vec<Section<'a>>,
+    checkpoint: &Checkpoint,
+    options: &WriteOptions,
+    on_progress: impl FnMut(u64),
+) -> Result<(), Error> {
+    let _span = tracing::info_span!("merging").entered();
+    let partial = partial_path(output.as_ref());
+    let open = || -> std::io::Result<_> {
+        let mut out_file = std::fs::OpenOptions::new().write(true).open(&partial)?;
+        out_file.set_len(checkpoint.output_len)?;
+        out_file.seek(SeekFrom::End(0))?;
+        Ok(out_file)
+    };
+    let out_file = open().map_err(|source| Error::Create {
+        path: partial.clone(),
+        source,
+    })?;
+
+    let out_writer = BufWriter::with_capacity(0x1_0000, out_file); // 64KiB
+    let mut out_writer = CountingWriter::new(out_writer, checkpoint.output_len);
+    let last_timestamp = checkpoint.last_timestamp;
+    let result = merge_sections(
+        &mut out_writer,
+        vcds,
+        sections.into(),
+        last_timestamp,
+        options,
+        on_progress,
+    )
+    .and_then(|()| out_writer.flush());
+    drop(out_writer);
+    finish_output(&partial, output.as_ref(), result, options)
+        .map_err(|source| write_error(output.as_ref(), source))
+}
+
+/// A writer that counts the bytes written through it.
+struct CountingWriter<W> {
+    inner: W,
+    /// The number of bytes written, starting from the initial count.
+    count: u64,
+}
+impl<W> CountingWriter<W> {
+    fn new(inner: W, count: u64) -> Self {
+        Self { inner, count }
+    }
+}
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Write the header commands and the declarations of all inputs.
+fn write_header(
+    out_writer: &mut impl Write,
+    headers: Header,
+    vcds: &[Vcd],
+    options: &WriteOptions,
+) -> std::io::Result<()> {
+    if let Some(date) = headers.date {
+        out_writer.write_all(b"$date ")?;
+        out_writer.write_all(date.as_bytes())?;
+        out_writer.write_all(b"$end\n")?;
+    }
+    if let Some(version) = headers.version {
+        out_writer.write_all(b"$version ")?;
+        out_writer.write_all(version.as_bytes())?;
+        out_writer.write_all(b"$end\n")?;
+    }
+    if let Some(mut timescale) = headers.timescale {
+        if let Some(label) = options.timescale_label {
+            if let Some(femtoseconds) = parse_timescale(&timescale) {
+                timescale = label.format(femtoseconds);
+            }
+        }
+        out_writer.write_all(b"$timescale ")?;
+        out_writer.write_all(timescale.as_bytes())?;
+        out_writer.write_all(b" $end\n")?;
+    }
+
+    if options.embed_map {
+        write_symbol_map_comment(out_writer, vcds)?;
+    }
+
+    if options.sort_signals || !options.signal_order.is_empty() {
+        write_sorted_declarations(out_writer, vcds, options)?;
+    } else {
+        let mut flat_names = HashSet::default();
+        for vcd in vcds.iter() {
+            for declaration in vcd.declarations.iter() {
+                if options.flatten_scopes {
+                    write_flat_declaration(out_writer, declaration, &mut flat_names)?;
+                } else {
+                    write_declaration(out_writer, declaration, options)?;
+                }
+            }
+        }
+    }
+
+    out_writer.write_all(b"$enddefinitions $end\n")?;
+
+    Ok(())
+}
+
+/// Write the merge-sorted changes of `sections`, continuing after `last_timestamp`, if any.
+fn merge_sections<'a, W: Write>(
+    out_writer: &mut CountingWriter<W>,
+    vcds: &'a [Vcd],
+    sections: LazySections<'a>,
+    mut last_timestamp: Option<u64>,
+    options: &WriteOptions,
+    mut on_progress: impl FnMut(u64),
+) -> std::io::Result<()> {
+    let LazySections {
+        sections,
+        mut streams,
+    } = sections;
+    let sections = sections
+        .into_iter()
+        .filter(|(s, _)| options.max_time.is_none_or(|max_time| s.value <= max_time));
+    let (mut sections, orders): (Vec<_>, Vec<_>) = sections.unzip();
+
+    // sections are ordered by time, and then by their input and position in it.
+    let mut heap = std::collections::BinaryHeap::from(
+        sections
+            .iter()
+            .zip(orders)
+            .enumerate()
+            .map(|(i, (s, order))| Reverse((s.value, order, i)))
+            .collect::<Vec<_>>(),
+    );
+
+    // the stream to take the next section from when each section is the earliest for the first
+    // time, and the slots of the sections that were fully written.
+    let mut pull = vec![None; sections.len()];
+    let mut free = Vec::new();
+    let mut stream_positions = vec![0; streams.len()];
+    for (stream, (input, file_sections)) in streams.iter_mut().enumerate() {
+        let Some(section) = file_sections.next().transpose()? else {
+            continue;
+        };
+        if options
+            .max_time
+            .is_none_or(|max_time| section.value <= max_time)
+        {
+            let order = (*input, 0);
+            heap.push(Reverse((section.value, order, sections.len())));
+            sections.push(section);
+            pull.push(Some(stream));
+        }
+    }
+
+    let mut progress = 0;
+    let mut line_count: usize = 0;
+
+    let mut warned_unsorted = false;
+    let mut steps = 0;
+
+    // the changes written at the current timestamp, to find conflicts.
+    let mut changes = HashMap::default();
+
+    // the last value written of each real signal, for `delta_epsilon`.
+    let mut last_reals = HashMap::default();
+
+    let mut downsample = Downsample::new(options.downsample, options.spaced);
+
+    let mut symbol_cache = SymbolCache::new();
+
+    'sections: loop {
+        // only checkpoint between timestamps, when there are no `changes` to save. The sections
+        // of streamed inputs that were not found yet can't be saved.
+        if let (Some(path), true) = (&options.checkpoint_path, streams.is_empty()) {
+            let next = heap.peek().map(|Reverse((value, _, _))| *value);
+            if steps >= options.checkpoint_interval && next.is_some() && next != last_timestamp {
+                out_writer.flush()?;
+                let mut remaining = heap.iter().map(|Reverse((_, _, i))| *i).collect::<Vec<_>>();
+                remaining.sort();
+                let remaining = remaining.iter().map(|&i| &sections[i]);
+                Checkpoint::new(vcds, out_writer.count, last_timestamp, remaining).write(path)?;
+                steps = 0;
+            }
+        }
+        steps += 1;
+
+        // the sections of a streamed input start in ascending time order, so the next one is
+        // only needed once the one before it is the earliest.
+        if let Some(&Reverse((_, _, index))) = heap.peek() {
+            if let Some(stream) = pull[index].take() {
+                let (input, file_sections) = &mut streams[stream];
+                let next = file_sections.next().transpose()?;
+                if let Some(section) =
+                    next.filter(|s| options.max_time.is_none_or(|max_time| s.value <= max_time))
+                {
+                    stream_positions[stream] += 1;
+                    let order = (*input, stream_positions[stream]);
+                    let value = section.value;
+                    let index = match free.pop() {
+                        Some(index) => {
+                            sections[index] = section;
+                            pull[index] = Some(stream);
+                            index
+                        }
+                        None => {
+                            sections.push(section);
+                            pull.push(Some(stream));
+                            sections.len() - 1
+                        }
+                    };
+                    heap.push(Reverse((value, order, index)));
+                }
+            }
+        }
+
+        let Some(mut heap_entry) = heap.peek_mut() else {
+            break;
+        };
+        let Reverse((_, order, index)) = *heap_entry;
+        let section = &mut sections[index];
+        // a section is written until its next timestamp is not the earliest, so a span covers
+        // each of these runs, not the whole section.
+        let _span = tracing::trace_span!(
+            "write_section",
+            path = %section.vcd.path.display(),
+            time = section.value
+        )
+        .entered();
+        let mut lines = section.section.split(|x| *x == b'\n');
+
+        // write the timestamp. A section that doesn't start with one, like the changes before the
+        // first timestamp of a input, inherits its start time as the section value.
+        if let [b'#', ..] = section.section {
+            lines.next();
+        }
+        if last_timestamp != Some(section.value) {
+            downsample.start_timestamp(out_writer, section.value)?;
+            changes.clear();
+        }
+        last_timestamp = Some(section.value);
+
+        for line in lines {
+            progress += line.len() as u64 + 1;
+
+            line_count += 1;
+
+            // My test file runs at 17 millions lines per second. Thats is about 270 thousands
+            // lines every 16ms, around ~2^18 = 4 * 2^16 = 0x4_0000.
+            // But I am running this on a SSD, so maybe it is not the best calibration for a HDD
+            // user (if the disk is the bottleneck, that is);
+            if line_count.is_multiple_of(0x4_0000) {
+                on_progress(progress);
+            }
+
+            match &line {
+                [b'#', ..] => {
+                    let offset = line.as_ptr() as usize - section.section.as_ptr() as usize;
+                    let Some(value) = section.vcd.timestamp(&line[1..])? else {
+                        // dropped timestamps always end a section
+                        break;
+                    };
+                    if options.max_time.is_some_and(|max_time| value > max_time) {
+                        break;
+                    }
+                    if value < section.value && !warned_unsorted && !options.quiet_unsorted {
+                        eprintln!(
+                            "warning: the timestamps of {} are not sorted, use --reorder to sort them",
+                            section.vcd.path.display()
+                        );
+                        warned_unsorted = true;
+                    }
+                    *section = Section {
+                        value,
+                        section: &section.section[offset..],
+                        vcd: section.vcd,
+                    };
+                    *heap_entry = Reverse((value, order, index));
+
+                    continue 'sections;
+                }
+                [b'b' | b'B' | b'r' | b'R' | b's' | b'S', ..] => {
+                    // some writers separate the value from the identifier with a tab.
+                    let Some(pos) = line.iter().rposition(|c| matches!(c, b' ' | b'\t')) else {
+                        return Err(malformed_change(section.vcd, line));
+                    };
+                    let (value, symbol) = (&line[..pos], trim_cr(&line[pos + 1..]));
+                    if symbol.is_empty() {
+                        return Err(malformed_change(section.vcd, line));
+                    }
+                    let Some(new_symbol) = symbol_cache.new_symbol(section.vcd, line, symbol)?
+                    else {
+                        continue;
+                    };
+
+                    let value = msb_first(section.vcd, value);
+                    let value = transform(options, section.vcd, symbol, &value);
+
+                    if !resolve_conflict(
+                        &mut changes,
+                        options,
+                        section,
+                        symbol,
+                        new_symbol,
+                        &value,
+                    )? {
+                        continue;
+                    }
+
+                    if let (Some(epsilon), [b'r' | b'R', ..]) = (options.delta_epsilon, line) {
+                        if !real_changed(&mut last_reals, new_symbol, &value, epsilon) {
+                            continue;
+                        }
+                    }
+
+                    let change = [&value, &b" "[..], new_symbol.as_bytes(), b"\n"];
+                    downsample.write(out_writer, new_symbol, &change)?;
+                }
+                [b'$', ..] => {
+                    // println!("skipping {}", std::str::from_utf8(line).unwrap());
+                }
+                [] => {
+                    // println!("empty line");
+                }
+                _ => {
+                    let value = &line[0..1];
+                    let symbol = trim_cr(&line[1..]);
+                    if symbol.is_empty() {
+                        return Err(malformed_change(section.vcd, line));
+                    }
+                    let Some(new_symbol) = section.vcd.new_symbol(line, symbol)? else {
+                        continue;
+                    };
+
+                    let value = transform(options, section.vcd, symbol, value);
+
+                    if !resolve_conflict(
+                        &mut changes,
+                        options,
+                        section,
+                        symbol,
+                        new_symbol,
+                        &value,
+                    )? {
+                        continue;
+                    }
+
+                    let change = [&value, new_symbol.as_bytes(), b"\n"];
+                    downsample.write(out_writer, new_symbol, &change)?;
+                }
+            }
+        }
+
+        // All lines in this section has been written
+        PeekMut::pop(heap_entry);
+        free.push(index);
+    }
+
+    downsample.finish(out_writer)
+}
+
+/// The number of entries in a [`SymbolCache`], a power of two.
+const SYMBOL_CACHE_SLOTS: usize = 64;
+
+/// A direct-mapped cache of the new identifiers of the vector changes in [`merge_sections`], in
+/// front of [`Vcd::new_symbol`]. Wide buses that change often are most of the lines of some files,
+/// and each of them would otherwise be a lookup in the symbol map of its input.
+struct SymbolCache<'a> {
+    /// The input, old identifier and new identifier of the last lookup that fell in each slot.
+    slots: [Option<(&'a Vcd, IdCode, Option<IdCode>)>; SYMBOL_CACHE_SLOTS],
+}
+impl<'a> SymbolCache<'a> {
+    fn new() -> Self {
+        Self {
+            slots: [None; SYMBOL_CACHE_SLOTS],
+        }
+    }
+
+    /// The same as [`Vcd::new_symbol`], but remembering the result.
+    fn new_symbol(
+        &mut self,
+        vcd: &'a Vcd,
+        line: &[u8],
+        symbol: &[u8],
+    ) -> std::io::Result<Option<IdCode>> {
+        let Some(id) = IdCode::parse(symbol) else {
+            return vcd.new_symbol(line, symbol);
+        };
+        // a multiplicative hash, whose top bits select the slot.
+        let hash = u32::from_le_bytes(id.0).wrapping_mul(0x9E37_79B9);
+        let slot = &mut self.slots[(hash >> (32 - SYMBOL_CACHE_SLOTS.trailing_zeros())) as usize];
+        if let Some((cached_vcd, cached_id, new_id)) = *slot {
+            if std::ptr::eq(cached_vcd, vcd) && cached_id == id {
+                return Ok(new_id);
+            }
+        }
+        let new_id = vcd.new_symbol(line, symbol)?;
+        *slot = Some((vcd, id, new_id));
+        Ok(new_id)
+    }
+}
+
+/// Writes the timestamps and changes of the merge, following [`WriteOptions::downsample`] and
+/// [`WriteOptions::spaced`]. When downsampling, only the last change of each signal between two
+/// kept timestamps is written.
+struct Downsample {
+    /// Write only every `every` timestamps.
+    every: usize,
+    /// Write a blank line before each timestamp, for [`WriteOptions::spaced`].
+    spaced: bool,
+    /// The number of distinct timestamps seen.
+    timestamps: usize,
+    /// The current timestamp, and if it is kept.
+    current: Option<(u64, bool)>,
+    /// The last change of each signal since the last kept timestamp was written, in the order the
+    /// signals first changed.
+    pending: Vec<(IdCode, Vec<u8>)>,
+    /// The index of each signal in `pending`.
+    pending_index: HashMap<IdCode, usize>,
+}
+impl Downsample {
+    fn new(every: usize, spaced: bool) -> Self {
+        Self {
+            every,
+            spaced,
+            timestamps: 0,
+            current: None,
+            pending: Vec::new(),
+            pending_index: HashMap::default(),
+        }
+    }
+
+    fn write_timestamp(&self, out: &mut impl Write, timestamp: u64) -> std::io::Result<()> {
+        if self.spaced {
+            out.write_all(b"\n")?;
+        }
+        out.write_all(b"#")?;
+        out.write_all(u64_to_bytes(timestamp, &mut [0; 20]))?;
+        out.write_all(b"\n")
+    }
+
+    /// Start the changes of a new `timestamp`, writing the changes of the previous one if it was
+    /// kept.
+    fn start_timestamp(&mut self, out: &mut impl Write, timestamp: u64) -> std::io::Result<()> {
+        if self.every < 2 {
+            return self.write_timestamp(out, timestamp);
+        }
+        if let Some((_, true)) = self.current {
+            self.flush(out)?;
+        }
+        let keep = self.timestamps.is_multiple_of(self.every);
+        self.timestamps += 1;
+        if keep {
+            self.write_timestamp(out, timestamp)?;
+        }
+        self.current = Some((timestamp, keep));
+        Ok(())
+    }
+
+    /// Write the `change` of `symbol`, given in parts, or keep it until the changes of the
+    /// current kept timestamp are written.
+    fn write(
+        &mut self,
+        out: &mut impl Write,
+        symbol: IdCode,
+        change: &[&[u8]],
+    ) -> std::io::Result<()> {
+        if self.every < 2 {
+            for part in change {
+                out.write_all(part)?;
+            }
+            return Ok(());
+        }
+        let change = change.concat();
+        match self.pending_index.get(&symbol) {
+            Some(&i) => self.pending[i].1 = change,
+            None => {
+                self.pending_index.insert(symbol, self.pending.len());
+                self.pending.push((symbol, change));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the changes of the last kept timestamp, or the ones after it at the last timestamp.
+    fn finish(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        match self.current {
+            Some((_, true)) => self.flush(out),
+            Some((timestamp, false)) if !self.pending.is_empty() => {
+                self.write_timestamp(out, timestamp)?;
+                self.flush(out)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn flush(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        for (_, change) in self.pending.drain(..) {
+            out.write_all(&change)?;
+        }
+        self.pending_index.clear();
+        Ok(())
+    }
+}
+
+/// Whether the real `value`, like `r1.5`, is further than `epsilon` from the `last` value written
+/// for `symbol`, updating it if it is. Values that can't be parsed are always written.
+fn real_changed(
+    last: &mut HashMap<IdCode, f64>,
+    symbol: IdCode,
+    value: &[u8],
+    epsilon: f64,
+) -> bool {
+    let value = value
+        .get(1..)
+        .and_then(|x| std::str::from_utf8(x).ok())
+        .and_then(|x| x.parse::<f64>().ok());
+    let Some(value) = value else {
+        last.remove(&symbol);
+        return true;
+    };
+    match last.get(&symbol) {
+        Some(last) if (value - last).abs() <= epsilon => false,
+        _ => {
+            last.insert(symbol, value);
+            true
+        }
+    }
+}
+
+/// Check a change against the `changes` of other inputs at the same timestamp, following
+/// [`WriteOptions::priority`] and [`WriteOptions::conflict`]. Returns if the change should be
+/// written.
+fn resolve_conflict<'a>(
+    changes: &mut HashMap<IdCode, (&'a Vcd, Vec<u8>)>,
+    options: &WriteOptions,
+    section: &Section<'a>,
+    symbol: &[u8],
+    new_symbol: IdCode,
+    value: &[u8],
+) -> std::io::Result<bool> {
+    if options.conflict == Conflict::Last && options.priority.is_empty() {
+        return Ok(true);
+    }
+
+    // the inputs listed first are the most trusted, and the ones not listed the least.
+    let trust = |vcd: &Vcd| {
+        let priority = &options.priority;
+        priority
+            .iter()
+            .position(|path| *path == vcd.path)
+            .map_or(0, |i| priority.len() - i)
+    };
+
+    let vcd = section.vcd;
+    match changes.get(&new_symbol) {
+        Some((other, other_value)) if !std::ptr::eq(*other, vcd) && other_value != value => {
+            if trust(vcd) != trust(other) {
+                // a later change of the signal overrides the ones before it.
+                let wins = trust(vcd) > trust(other);
+                if wins {
+                    changes.insert(new_symbol, (vcd, value.to_vec()));
+                }
+                return Ok(wins);
+            }
+            match options.conflict {
+                Conflict::First => Ok(false),
+                Conflict::Last => {
+                    changes.insert(new_symbol, (vcd, value.to_vec()));
+                    Ok(true)
+                }
+                Conflict::Error => {
+                    let name = vcd
+                        .signals
+                        .get(&IdCode::from(symbol))
+                        .map_or("", |signal| signal.name.as_str());
+                    Err(std::io::Error::other(format!(
+                        "conflicting changes of {} at {}: {} in {}, but {} in {}",
+                        name,
+                        section.value,
+                        String::from_utf8_lossy(other_value),
+                        other.path.display(),
+                        String::from_utf8_lossy(value),
+                        vcd.path.display(),
+                    )))
+                }
+            }
+        }
+        // the first input to change the signal at this timestamp keeps it.
+        Some((other, _)) if !std::ptr::eq(*other, vcd) => Ok(true),
+        _ => {
+            changes.insert(new_symbol, (vcd, value.to_vec()));
+            Ok(true)
+        }
+    }
+}
+
+/// Write the declarations of all inputs in the order of [`WriteOptions::signal_order`], or sorted
+/// by their hierarchical name, for [`WriteOptions::sort_signals`]. Vars with the same name keep
+/// the order of the inputs.
+fn write_sorted_declarations(
+    out_writer: &mut impl Write,
+    vcds: &[Vcd],
+    options: &WriteOptions,
+) -> std::io::Result<()> {
+    let order = options
+        .signal_order
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (path.as_str(), i))
+        .rev()
+        .collect::<HashMap<_, _>>();
+    // the rank of the declarations that are not in `signal_order`. They all have the same rank
+    // when sorted by name, otherwise they keep their order.
+    let mut next_rank = order.len();
+    let mut unlisted_rank = || {
+        let rank = next_rank;
+        if !options.sort_signals {
+            next_rank += 1;
+        }
+        rank
+    };
+
+    // each scope and var, keyed by the name and kind of the scopes enclosing it, followed by its
+    // own name, together with its rank.
+    let mut entries = Vec::new();
+    for vcd in vcds {
+        let mut scopes = Vec::new();
+        for declaration in vcd.declarations.iter() {
+            match declaration {
+                Declaration::Scope { kind, name } => {
+                    scopes.push((name.as_str(), kind.as_str()));
+                    entries.push((scopes.clone(), unlisted_rank(), None));
+                }
+                Declaration::Var { name, path, .. } => {
+                    let mut key = scopes.clone();
+                    key.push((name.as_str(), ""));
+                    let rank = order.get(path.as_str()).copied();
+                    let rank = rank.unwrap_or_else(&mut unlisted_rank);
+                    entries.push((key, rank, Some(declaration)));
+                }
+                Declaration::Upscope => {
+                    scopes.pop();
+                }
+            }
+        }
+    }
+
+    // each scope takes the lowest rank of its declarations, so it is written before them.
+    let mut scope_ranks: HashMap<&[(&str, &str)], usize> = HashMap::default();
+    for (key, rank, _) in &entries {
+        for len in 1..=key.len() {
+            let scope_rank = scope_ranks.entry(&key[..len]).or_insert(*rank);
+            *scope_rank = (*scope_rank).min(*rank);
+        }
+    }
+
+    // sorting the keys of ranks and names places each declaration inside its scope.
+    let mut entries = entries
+        .iter()
+        .map(|(key, rank, declaration)| {
+            let ranked = (1..=key.len())
+                .map(|len| {
+                    let (name, kind) = key[len - 1];
+                    let rank = scope_ranks.get(&key[..len]).copied().unwrap_or(*rank);
+                    (rank, name, kind)
+                })
+                .collect::<Vec<_>>();
+            (ranked, key, declaration)
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut flat_names = HashSet::default();
+    let mut open: &[(&str, &str)] = &[];
+    for (_, key, declaration) in entries {
+        if options.flatten_scopes {
+            if let Some(declaration) = declaration {
+                write_flat_declaration(out_writer, declaration, &mut flat_names)?;
+            }
+            continue;
+        }
+
+        let scopes = match declaration {
+            Some(_) => &key[..key.len() - 1],
+            None => &key[..],
+        };
+        let common = open.iter().zip(scopes).take_while(|(a, b)| a == b).count();
+        for _ in common..open.len() {
+            Declaration::Upscope.write(out_writer)?;
+        }
+        for (name, kind) in &scopes[common..] {
+            writeln!(out_writer, "$scope {} {} $end", kind, name)?;
+        }
+        open = scopes;
+
+        if let Some(declaration) = declaration {
+            write_declaration(out_writer, declaration, options)?;
+        }
+    }
+    for _ in 0..open.len() {
+        Declaration::Upscope.write(out_writer)?;
+    }
+
+    Ok(())
+}
+
+/// Write a declaration, with the name of vars as in the input, or with a single space around it
+/// for [`WriteOptions::canonical_names`].
+fn write_declaration(
+    out_writer: &mut impl Write,
+    declaration: &Declaration,
+    options: &WriteOptions,
+) -> std::io::Result<()> {
+    match declaration {
+        Declaration::Var {
+            ty,
+            width,
+            new_id,
+            name,
+            ..
+        } if options.canonical_names => {
+            write!(out_writer, "$var {} {} ", ty, width)?;
+            out_writer.write_all(new_id.as_bytes())?;
+            writeln!(out_writer, " {} $end", name)
+        }
+        _ => declaration.write(out_writer),
+    }
+}
+
+/// Write a var declaration with its hierarchical name, and skip scopes. Names that are already in
+/// `names` are renamed, with a warning.
+fn write_flat_declaration(
+    out_writer: &mut impl Write,
+    declaration: &Declaration,
+    names: &mut HashSet<String>,
+) -> std::io::Result<()> {
+    let Declaration::Var {
+        ty,
+        width,
+        new_id,
+        path,
+        ..
+    } = declaration
+    else {
+        return Ok(());
+    };
+
+    let name = unique_name(path.clone(), names);
+    if &name != path {
+        eprintln!(
+            "warning: {} is declared more than once after flattening, renamed to {}",
+            path, name
+        );
+    }
+
+    write!(out_writer, "$var {} {} ", ty, width)?;
+    out_writer.write_all(new_id.as_bytes())?;
+    writeln!(out_writer, " {} $end", name)
+}
+
+/// Write the old to new identifier map of each input, as a `$comment` block.
+fn write_symbol_map_comment(out_writer: &mut impl Write, vcds: &[Vcd]) -> std::io::Result<()> {
+    out_writer.write_all(b"$comment\n")?;
+    for vcd in vcds {
+        writeln!(out_writer, "  identifiers of {}:", vcd.path.display())?;
+
+        let mut symbols = vcd.symbol_map.iter().collect::<Vec<_>>();
+        // sort by new identifier, which is the order they were declared in.
+        symbols.sort_by_key(|(_, new_id)| new_id.index());
+
+        for (old_id, new_id) in symbols {
+            out_writer.write_all(b"  ")?;
+            out_writer.write_all(old_id.as_bytes())?;
+            out_writer.write_all(b" -> ")?;
+            out_writer.write_all(new_id.as_bytes())?;
+            out_writer.write_all(b"\n")?;
+        }
+    }
+    out_writer.write_all(b"$end\n")?;
+    Ok(())
+}
+
+/// The value of a change of `vcd` with its bits most significant first, as written in the
+/// output, reversing the binary vectors of inputs with [`BitOrder::Lsb`].
+fn msb_first<'a>(vcd: &Vcd, value: &'a [u8]) -> Cow<'a, [u8]> {
+    match (vcd.bit_order, value) {
+        (BitOrder::Lsb, [prefix @ (b'b' | b'B'), bits @ ..]) => Cow::Owned(
+            [prefix]
+                .into_iter()
+                .chain(bits.iter().rev())
+                .copied()
+                .collect(),
+        ),
+        _ => Cow::Borrowed(value),
+    }
+}
+
+/// Split a value change line into its value and identifier. Returns `None` for lines that are not
+/// value changes, like timestamps and commands, or that have no identifier.
+fn split_value_change(line: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (value, symbol) = match line {
+        [] | [b'#', ..] | [b'$', ..] => return None,
+        [b'b' | b'B' | b'r' | b'R' | b's' | b'S', ..] => {
+            let pos = line.iter().rposition(|c| matches!(c, b' ' | b'\t'))?;
+            (&line[..pos], trim_cr(&line[pos + 1..]))
+        }
+        _ => (&line[..1], trim_cr(&line[1..])),
+    };
+    (!symbol.is_empty()).then_some((value, symbol))
+}
+
+/// Remove the `\r` left at the end of a identifier by a line ending in CRLF, so the identifier is
+/// found even in files that mix line endings.
+fn trim_cr(symbol: &[u8]) -> &[u8] {
+    symbol.strip_suffix(b"\r").unwrap_or(symbol)
+}
+
+/// The error for a value change `line` of `vcd` without a value or identifier.
+fn malformed_change(vcd: &Vcd, line: &[u8]) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!(
+            "malformed value change {:?} in {}, expected a value followed by a identifier",
+            String::from_utf8_lossy(line),
+            vcd.path.display()
+        ),
+    )
+}
+
+/// The error for a value change `line` of `vcd` whose identifier `symbol` is not declared.
+fn undeclared_identifier(vcd: &Vcd, line: &[u8], symbol: &[u8]) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!(
+            "undeclared identifier {:?} in the value change {:?} in {}",
+            String::from_utf8_lossy(symbol),
+            String::from_utf8_lossy(line),
+            vcd.path.display()
+        ),
+    )
+}
+
+/// Apply the [`WriteOptions::transform`] callback, if any, to a value, followed by
+/// [`WriteOptions::normalize_values`].
+fn transform<'v>(
+    options: &WriteOptions,
+    vcd: &Vcd,
+    symbol: &[u8],
+    value: &'v [u8],
+) -> Cow<'v, [u8]> {
+    let value = match options.transform {
+        Some(transform) => match vcd.signals.get(&IdCode::from(symbol)) {
+            Some(signal) => transform(signal, value),
+            None => Cow::Borrowed(value),
+        },
+        None => Cow::Borrowed(value),
+    };
+    match options.normalize_values {
+        Some(case) => normalize_value(value, case),
+        None => value,
+    }
+}
+
+/// Write the `x` and `z` states of a scalar or vector value in `case`. Reals and strings are left
+/// as they are.
+fn normalize_value(value: Cow<[u8]>, case: ValueCase) -> Cow<[u8]> {
+    let (from, to) = match case {
+        ValueCase::Lower => (b"XZ", b"xz"),
+        ValueCase::Upper => (b"xz", b"XZ"),
+    };
+    if let [b'r' | b'R' | b's' | b'S', ..] = &*value {
+        return value;
+    }
+    if !value.iter().any(|b| from.contains(b)) {
+        return value;
+    }
+    let mut value = value.into_owned();
+    for b in value.iter_mut() {
+        if let Some(i) = from.iter().position(|x| x == b) {
+            *b = to[i];
+        }
+    }
+    Cow::Owned(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u64_to_bytes_zero() {
+        assert_eq!(u64_to_bytes(0, &mut [0; 20]), b"0");
+        assert_eq!(u64_to_bytes(10, &mut [0; 20]), b"10");
+        assert_eq!(
+            u64_to_bytes(u64::MAX, &mut [0; 20]),
+            u64::MAX.to_string().as_bytes()
+        );
+    }
+
+    #[test]
+    fn ordinal() {
+        assert_eq!(IdCode::from(&b"!"[..]).ordinal(), 1);
+        assert_eq!(IdCode::from(&b"~"[..]).ordinal(), 94);
+        assert_eq!(IdCode::from(&b"!!"[..]).ordinal(), 95);
+        assert_eq!(IdCode::from(&b"~~~~"[..]).ordinal(), IDENTIFIER_CAPACITY);
+    }
+
+    #[test]
+    fn file_scope_names() {
+        let mut used = HashSet::default();
+        let mut name = |path: &str| file_scope_name(Path::new(path), &mut used);
+        assert_eq!(name("dir/cpu.vcd"), "cpu");
+        assert_eq!(name("/abs/dir/cpu.vcd"), "cpu_1");
+        assert_eq!(name("mem trace.vcd"), "mem_trace");
+        assert_eq!(name("-"), "stdin");
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn file_scope_names_windows() {
+        let mut used = HashSet::default();
+        let mut name = |path: &str| file_scope_name(Path::new(path), &mut used);
+        assert_eq!(name(r"C:\dir\cpu.vcd"), "cpu");
+        assert_eq!(name(r"\\server\share\dir\mem.vcd"), "mem");
+        assert_eq!(name(r"\\?\C:\dir\io.vcd"), "io");
+        assert_eq!(name("C:/dir/gpu.vcd"), "gpu");
+
+        assert_eq!(
+            partial_path(Path::new(r"\\server\share\out.vcd")),
+            Path::new(r"\\server\share\out.vcd.partial")
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        assert!(is_timestamp(b"#10"));
+        assert!(is_timestamp(b"#0x1F4"));
+        assert!(!is_timestamp(b"#"));
+        assert!(!is_timestamp(b"#a"));
+        assert!(!is_timestamp("é".as_bytes()));
+        assert!(!is_timestamp("\u{FFFD}".as_bytes()));
+        assert!(!is_timestamp(b"#99999999999999999999999"));
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match(b"top.*", b"top.cpu.pc"));
+        assert!(glob_match(b"*.pc", b"top.cpu.pc"));
+        assert!(glob_match(b"top.c?u.*", b"top.cpu.pc"));
+        assert!(glob_match(b"*cpu*", b"top.cpu.pc"));
+        assert!(glob_match(b"*", b""));
+        assert!(!glob_match(b"top.*", b"tops"));
+        assert!(!glob_match(b"*.pc", b"top.cpu.pcx"));
+        assert!(!glob_match(b"top.?", b"top."));
+    }
+
+    #[test]
+    fn next_code_without_whitespace() {
+        let mut last = next_code(&[]);
+        for _ in 0..1_000_000 {
+            let code = next_code(&[]);
+            assert!(code.as_bytes().iter().all(|b| (0x21..=0x7E).contains(b)));
+            assert!(code.index() > last.index());
+            last = code;
+        }
+    }
+}
@@ -1,635 +1,1428 @@
-use clap::Parser;
-use fxhash::FxHashMap as HashMap;
-use memmap2::Mmap;
+mod config;
+
+use clap::{Parser, ValueEnum};
 use std::{
-    cmp::Reverse,
-    collections::binary_heap::PeekMut,
-    io::{BufRead, BufWriter, Write},
+    ffi::OsString,
+    num::{NonZeroU64, NonZeroUsize},
     path::{Path, PathBuf},
-    sync::Mutex,
 };
-
-// this can only represent 94^4 = 78_074_896 symbols.
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
-struct IdCode([u8; 4]);
-impl From<&[u8]> for IdCode {
-    fn from(s: &[u8]) -> Self {
-        let mut code = [0; 4];
-        for (i, b) in s.iter().enumerate() {
-            code[i] = *b;
-        }
-        IdCode(code)
-    }
-}
-impl IdCode {
-    fn as_bytes(&self) -> &[u8] {
-        for i in 0..4 {
-            if self.0[i] == 0 {
-                return &self.0[..i];
-            }
-        }
-        &self.0
-    }
-}
-impl std::fmt::Debug for IdCode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "IdCode(")?;
-        for b in self.as_bytes() {
-            write!(f, "{}", *b as char)?;
-        }
-        write!(f, ")")?;
-        Ok(())
-    }
-}
-
-struct Vcd {
-    /// Map from old symbol to new symbol.
-    symbol_map: HashMap<IdCode, IdCode>,
-    /// All scope and var declarations.
-    declarations: Vec<String>,
-    file: Mmap,
-    end_of_definitions: usize,
-    /// The timescale ratio between this input timescale and the output timescale.
-    timescale: u64,
-}
-
-#[derive(Default)]
-struct Header {
-    date: Option<String>,
-    version: Option<String>,
-    timescale: Option<String>,
-}
+use vcd_merger::{
+    compare, count_changes, file_sections, find_sections, identifiers_used, lazy_sections,
+    manifest_path, missing_signals, parse_headers, parse_timescale, prune_unused, read_symbol_map,
+    reset_identifiers, resume_output, split_output, validate, write_activity_csv,
+    write_json_signals, write_output, write_preview, write_sections_tsv, write_split_manifest,
+    write_symbol_map, BitOrder, Checkpoint, Conflict, Error, Header, NegativeTime, ParseOptions,
+    Phase, Section, SignalFilter, TimeUnit, TimescaleLabel, ValueCase, Vcd, WriteOptions,
+    IDENTIFIER_CAPACITY,
+};
 
 /// A tool for merging multiple VCD (Value Change Dump) files together. This will
 /// concatenate all signals from all the input files, side-by-side, merge-sorting
 /// the timestamps, making it easier to view all files at the same time in a wave
 /// visualizer, like GTKWave.
 #[derive(Parser)]
+#[command(version, args_override_self = true)]
 struct Cli {
-    /// VCD files to be merged together.
+    /// VCD files to be merged together, which may be compressed with gzip, zstd or lz4. For
+    /// directories, all `*.vcd` files inside it, and their compressed `*.vcd.gz`, `*.vcd.zst` and
+    /// `*.vcd.lz4` versions, are merged in the order of their names. `-` reads a input from stdin.
     input: Vec<PathBuf>,
 
+    /// Also merge the `*.vcd` files in subdirectories of the input directories.
+    #[arg(long)]
+    recursive: bool,
+
+    /// Merge a file given more than once as input once for each time, instead of only once. This
+    /// also keeps inputs with the same content as another, like copies of the same file.
+    #[arg(long)]
+    allow_duplicate_inputs: bool,
+
     /// File where the merged VCD will be written.
-    #[arg(short, long)]
-    output: PathBuf,
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = [
+            "count_only",
+            "input_timescale_report",
+            "preview",
+            "validate_only"
+        ]
+    )]
+    output: Option<PathBuf>,
 
     /// Don't assume that timestamps in each VCD files are sorted, and
     /// sort them too.
     #[arg(short, long)]
     reorder: bool,
-}
 
-const PROGRESS_BAR_TEMPLATE: &str = "\
-{elapsed_precise} █{bar:60.cyan/blue}█ {bytes}/{total_bytes} {binary_bytes_per_sec} ({eta})";
-
-fn main() {
-    let args = Cli::parse();
+    /// Merge each input as a single section, without looking for timestamps that go back in time,
+    /// even if `--reorder` is set, like in a `--from-config` file. Inputs that are not sorted
+    /// produce output that is not sorted; `--validate-only` reports unsorted timestamps.
+    #[arg(long, conflicts_with = "canonicalize")]
+    assume_sorted: bool,
+
+    /// Only merge the first N sections, ordered by their start time. Useful for quickly debugging
+    /// the merge of huge files.
+    #[arg(long, value_name = "N")]
+    limit_sections: Option<usize>,
+
+    /// Print the number of sections of each input, and how many of them have each size, to find
+    /// inputs that jump back in time often, which are slow to merge.
+    #[arg(long)]
+    profile_sections: bool,
+
+    /// Write the sections to merge to PATH as TSV, with the input, byte range, start value, and
+    /// first and last timestamps of each, to reproduce problems with the merge.
+    #[arg(long, value_name = "PATH")]
+    dump_sections: Option<PathBuf>,
+
+    /// Look for `$scope` and `$var` declarations in the entire file, instead of only before the
+    /// first value change. This is slower, but is needed for files that declare signals
+    /// mid-stream.
+    #[arg(long)]
+    rescan_declarations: bool,
+
+    /// Maximum number of threads used for finding sections. Defaults to the number of logical
+    /// CPUs.
+    #[arg(long, value_name = "N")]
+    threads: Option<NonZeroUsize>,
+
+    /// Write the map from the original to the new identifiers of each input in a `$comment`
+    /// block, after the header.
+    #[arg(long)]
+    embed_map: bool,
+
+    /// Offset the timestamps of the input at INDEX (starting from 0) by OFFSET, in the units of
+    /// its timescale. Can be negative, and can be given multiple times.
+    #[arg(long, value_name = "INDEX=OFFSET", value_parser = parse_time_offset, allow_hyphen_values = true)]
+    time_offset: Vec<(usize, i64)>,
+
+    /// The order of the bits in the vector values of the inputs, `msb` for the most significant
+    /// bit first, or `lsb` for the least significant bit first, whose values are reversed in the
+    /// output. Prefixed by the path of a input and a `:`, like `a.vcd:lsb`, it only applies to
+    /// that input. Defaults to `msb`.
+    #[arg(long, value_name = "[PATH:]ORDER", value_parser = parse_bit_order)]
+    bit_order: Vec<(Option<PathBuf>, BitOrder)>,
+
+    /// What to do with changes that a negative time offset moves before time 0.
+    #[arg(long, value_name = "ACTION", default_value = "clamp")]
+    negative_time: NegativeTime,
+
+    /// A previously merged VCD to append the inputs to. Its identifiers are kept, signals with
+    /// the same hierarchical name are merged into it, and new signals receive new identifiers.
+    #[arg(long, value_name = "EXISTING")]
+    append: Option<PathBuf>,
+
+    /// Merge the vars of all inputs with the same hierarchical name into a single signal, even
+    /// when the inputs give them different identifiers. Otherwise, the vars of each input are
+    /// declared separately.
+    #[arg(long)]
+    unify_by_name: bool,
+
+    /// Also write the merged scope hierarchy, with all variables, as JSON to PATH.
+    #[arg(long, value_name = "PATH")]
+    json_signals: Option<PathBuf>,
+
+    /// Also write the hierarchical name and identifier of each signal in the output as TSV to
+    /// PATH, to give them the same identifiers in a later merge with `--symbol-map-in`.
+    #[arg(long, value_name = "PATH")]
+    symbol_map: Option<PathBuf>,
+
+    /// Give the signals the identifiers they have in a map written by `--symbol-map`, matching
+    /// them by hierarchical name. Signals that are not in it receive new identifiers.
+    #[arg(long, value_name = "PATH")]
+    symbol_map_in: Option<PathBuf>,
+
+    /// After merging, compare the output with a REFERENCE file, matching signals by their
+    /// hierarchical name, and report the first difference in their signals or value changes.
+    #[arg(long, value_name = "REFERENCE")]
+    compare: Option<PathBuf>,
+
+    /// Timescale of the output, like `1ns` or `10 ps`, instead of the finest timescale of the
+    /// inputs. The timestamps of finer inputs are rounded down to it. Also used for inputs whose
+    /// `$timescale` can't be parsed, which otherwise use 1ns.
+    #[arg(long, value_name = "SCALE", value_parser = parse_timescale_arg)]
+    timescale: Option<u64>,
+
+    /// Experimental: pre-allocate the output file to its estimated size and write it through a
+    /// memory map, instead of buffered writes.
+    #[arg(long)]
+    mmap_output: bool,
+
+    /// Read each input into memory, instead of memory mapping it, for file systems where mapping
+    /// is slow or not supported. Needs enough memory for all inputs.
+    #[arg(long)]
+    no_mmap: bool,
+
+    /// Scan the data of the inputs, and drop the declarations of signals that never change
+    /// value.
+    #[arg(long)]
+    prune_unused: bool,
+
+    /// Stop the merge at TIME, like `100ns` or `2 ms`. No changes after it are written.
+    #[arg(long, value_name = "TIME", value_parser = parse_max_time)]
+    max_time: Option<u64>,
+
+    /// Don't write the output, only count the value changes of each signal and print the most
+    /// active ones.
+    #[arg(long)]
+    count_only: bool,
+
+    /// Only write the merged header, up to `$enddefinitions $end`, without any value changes, for
+    /// tools that only need the signal hierarchy. The data of the inputs is not read.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "prune_unused",
+            "count_only",
+            "preview",
+            "activity",
+            "profile_sections",
+            "checkpoint_interval",
+            "resume"
+        ]
+    )]
+    header_only: bool,
+
+    /// Number of signals printed by `--count-only`.
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    top: usize,
+
+    /// Wrap the signals of each input in a scope named after its file. Inputs with the same file
+    /// name get a `_1`, `_2`, ... suffix.
+    #[arg(long)]
+    scope_per_file: bool,
+
+    /// Leave the vars of these types, like `parameter` or `real`, and their value changes out of
+    /// the output.
+    #[arg(long, value_name = "TYPE,...", value_delimiter = ',')]
+    exclude_types: Vec<String>,
+
+    /// Only keep the vars whose hierarchical name matches this glob, like `top.cpu.*`. Prefixed by
+    /// the path of a input and a `:`, like `a.vcd:top.cpu.*`, it only applies to that input. Can
+    /// be given multiple times, keeping the vars that match any of them.
+    #[arg(long, value_name = "[PATH:]GLOB", value_parser = parse_filter)]
+    include: Vec<(Option<PathBuf>, String)>,
+
+    /// Leave out the vars whose hierarchical name matches this glob, and their value changes. Can
+    /// be prefixed by the path of a input and a `:`, like `--include`.
+    #[arg(long, value_name = "[PATH:]GLOB", value_parser = parse_filter)]
+    exclude: Vec<(Option<PathBuf>, String)>,
+
+    /// What to do when different inputs change the same signal to different values at the same
+    /// timestamp. This can only happen for signals merged by name, like with `--unify-by-name` or
+    /// `--append`.
+    #[arg(long, value_name = "POLICY", default_value = "last")]
+    conflict: Conflict,
+
+    /// Inputs from the most to the least trusted. When inputs change the same signal to different
+    /// values at the same timestamp, the change of the most trusted one takes effect, independent
+    /// of the order of the inputs. `--conflict` only applies between inputs that are not listed.
+    #[arg(long, value_name = "PATH,...", value_delimiter = ',')]
+    priority: Vec<PathBuf>,
+
+    /// Check each input for problems, like unbalanced scopes, an invalid timescale, timestamps
+    /// out of order or changes of undeclared signals, and exit without merging. Fails if any
+    /// problem is found.
+    #[arg(long)]
+    validate_only: bool,
+
+    /// Print the `$timescale` of each input, and the timescale parsed from it, and exit without
+    /// merging.
+    #[arg(long)]
+    input_timescale_report: bool,
+
+    /// Don't write any `$scope`, and name each signal by its full hierarchical name, like
+    /// `top.dut.data`, for viewers that don't support scopes.
+    #[arg(long)]
+    flatten_scopes: bool,
+
+    /// Write the declarations sorted by their hierarchical name, merging scopes with the same
+    /// name, so merges of the same signals have the same header independent of the input order.
+    #[arg(long)]
+    sort_signals: bool,
+
+    /// Write the declarations of the signals listed in FILE first, in the order they are listed,
+    /// one hierarchical name per line, like `top.dut.data`. The other signals are written after
+    /// them, in the default order.
+    #[arg(long, value_name = "FILE")]
+    signal_order: Option<PathBuf>,
+
+    /// Save the state of the merge to `<OUTPUT>.checkpoint` every N steps, where each step writes
+    /// the changes of an input at a single timestamp, so it can be continued with `--resume`. The
+    /// checkpoint is removed when the merge completes.
+    #[arg(long, value_name = "N")]
+    checkpoint_interval: Option<NonZeroUsize>,
+
+    /// Continue an interrupted merge from a checkpoint written by `--checkpoint-interval`. The
+    /// inputs and all other options must be the same as the ones of the interrupted merge.
+    #[arg(long, value_name = "CHECKPOINT")]
+    resume: Option<PathBuf>,
+
+    /// Write the number of value changes of each signal, and their rate per microsecond over the
+    /// time range of the merge, as CSV to PATH.
+    #[arg(long, value_name = "PATH")]
+    activity: Option<PathBuf>,
+
+    /// Rename the var with hierarchical name OLD, like `top.clk`, to NEW, like `top.clock`. Only
+    /// the last component can change. Can be given multiple times.
+    #[arg(long, value_name = "OLD=NEW", value_parser = parse_rename)]
+    rename: Vec<(String, String)>,
+
+    /// Don't write the output, only print its first N lines, including the header, going through
+    /// the same steps as writing it.
+    #[arg(long, value_name = "N")]
+    preview: Option<usize>,
+
+    /// Fail if the inputs don't all declare the same signals, by hierarchical name, listing the
+    /// signals missing in each input.
+    #[arg(long, conflicts_with = "scope_per_file")]
+    require_same_signals: bool,
+
+    /// Lossy: leave out the changes of real signals that are within `--epsilon` of the last value
+    /// written for the signal.
+    #[arg(long)]
+    delta_encode: bool,
+
+    /// The largest difference from the last written value of a real signal that `--delta-encode`
+    /// leaves out.
+    #[arg(
+        long,
+        value_name = "E",
+        default_value_t = 0.0,
+        requires = "delta_encode"
+    )]
+    epsilon: f64,
+
+    /// Lossy: only write every Nth timestamp. The last change of each signal at the timestamps in
+    /// between is written at the next timestamp that is kept, so the kept timestamps have the
+    /// same values as the full merge.
+    #[arg(long, value_name = "N", conflicts_with_all = ["checkpoint_interval", "resume"])]
+    downsample: Option<NonZeroUsize>,
+
+    /// Normalize a single input instead of merging: sort its timestamps, like `--reorder`, and
+    /// write its timescale, declarations and value changes in a canonical form, without comments.
+    #[arg(long)]
+    canonicalize: bool,
+
+    /// Split a single input instead of merging: write each of its top-level scopes, with only
+    /// their signals, to a file named after the scope in the `--output` directory.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "count_only",
+            "preview",
+            "header_only",
+            "limit_sections",
+            "dump_sections",
+            "checkpoint_interval",
+            "resume",
+            "compare",
+            "scope_per_file"
+        ]
+    )]
+    split_by_scope: bool,
+
+    /// Write the output in chunks of at least BYTES of value changes, split before a timestamp,
+    /// instead of a single file. The chunks are named after `--output` with their index before the
+    /// extension, like `out.0.vcd`, and each one starts with the values of the signals at its
+    /// first timestamp. The file name and `[start_time, end_time]` of each chunk are written as
+    /// JSON to a manifest, like `out.manifest.json`.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        conflicts_with_all = ["split_by_scope", "header_only", "compare", "preview", "count_only"]
+    )]
+    split_size: Option<NonZeroU64>,
+
+    /// Warn when more than 90% of the identifiers are used, since they are limited to 4
+    /// characters.
+    #[arg(long)]
+    warn_wide_ids: bool,
+
+    /// Write a blank line before each timestamp, to make the output easier to read.
+    #[arg(long)]
+    spaced: bool,
+
+    /// Only merge the first N signals, in the order of the inputs, for a quick look at a large
+    /// design. The other signals and their value changes are left out.
+    #[arg(long, value_name = "N")]
+    limit_signals: Option<usize>,
+
+    /// Write the `x` and `z` states of all values in the same case, lowercase unless CASE is
+    /// `upper`, like `--normalize-values=upper`.
+    #[arg(
+        long,
+        value_name = "CASE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "lower"
+    )]
+    normalize_values: Option<ValueCase>,
+
+    /// How to spell the timescale of the output, without changing it: a unit like `ps` to write
+    /// `1000ps` instead of `1ns`, or `auto` for the largest unit that divides it, optionally
+    /// followed by `-spaced` to write a space before the unit, like `1 ns`.
+    #[arg(long, value_name = "FORMAT", value_parser = parse_timescale_label)]
+    output_timescale_label: Option<TimescaleLabel>,
+
+    /// Don't show the progress bars, only the messages of each step, like for logs of CI jobs.
+    #[arg(long)]
+    no_progress: bool,
+
+    /// Instead of the progress bars, write the progress of finding sections and merging to
+    /// stderr as JSON lines, like `{"phase":"merging","bytes":1024,"total":4096}`, for wrappers
+    /// to parse.
+    #[arg(long, conflicts_with = "no_progress")]
+    progress_json: bool,
+
+    /// Characters to never use in the generated identifiers, like `~\`, for tools that don't
+    /// accept them.
+    #[arg(long, value_name = "CHARS", value_parser = parse_avoid_chars)]
+    avoid_chars: Option<String>,
+
+    /// Only generate alphanumeric identifiers, which all versions of GTKWave accept. The
+    /// identifiers of `--append` are kept as they are.
+    #[arg(long)]
+    gtkwave_compat: bool,
+
+    /// Read the inputs and options from a TOML file, with a key for each option, like
+    /// `reorder = true`, and a `[[input]]` table for each input, with its `path` and optionally
+    /// its `time_offset`, `bit_order`, and `include` and `exclude` globs. Its inputs are merged
+    /// before the ones in the command line, and options in the command line override the ones in
+    /// it, except options that can be given more than once, like `--include`, which are added to
+    /// the ones in it. `--time-offset` can't be given in the command line, as the indices of the
+    /// inputs would include the ones of the config.
+    #[arg(long, value_name = "TOML")]
+    from_config: Option<PathBuf>,
+}
 
-    let inputs = &args.input;
-    let output = &args.output;
-    let reorder = args.reorder;
+/// The command line arguments, with the ones of the `--from-config` file, if any, inserted
+/// before them, so options in the command line override the ones in the file.
+fn args_with_config() -> Vec<OsString> {
+    let mut args = std::env::args_os().collect::<Vec<_>>();
 
-    let style = indicatif::ProgressStyle::default_bar()
-        .template(PROGRESS_BAR_TEMPLATE)
-        .unwrap()
-        .progress_chars("█▉▊▋▌▍▎▏  ");
+    let position = args.iter().position(|arg| {
+        let arg = arg.to_string_lossy();
+        arg == "--from-config" || arg.starts_with("--from-config=")
+    });
+    let Some(position) = position else {
+        return args;
+    };
+    let arg = args[position].to_string_lossy();
+    let path = match arg.strip_prefix("--from-config=") {
+        Some(path) => PathBuf::from(path),
+        // a missing value is reported by clap.
+        None => match args.get(position + 1) {
+            Some(path) => PathBuf::from(path),
+            None => return args,
+        },
+    };
 
-    let mut count = 0;
-    let total = if reorder { 3 } else { 2 };
+    let time_offset = args.iter().any(|arg| {
+        let arg = arg.to_string_lossy();
+        arg == "--time-offset" || arg.starts_with("--time-offset=")
+    });
+    if time_offset {
+        eprintln!(
+            "--time-offset can't be used with --from-config, set the time_offset of the [[input]] \
+             in the config instead"
+        );
+        std::process::exit(1);
+    }
 
-    count += 1;
-    println!("[{count}/{total}] gathering symbols");
+    match config::config_args(&path) {
+        Ok(config) => {
+            args.splice(1..1, config);
+            args
+        }
+        Err(err) => {
+            eprintln!("Could not read the config {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
 
-    let mut headers = Header::default();
+fn parse_time_offset(s: &str) -> Result<(usize, i64), String> {
+    let (index, offset) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected INDEX=OFFSET, found {}", s))?;
+    let index = index
+        .parse()
+        .map_err(|err| format!("invalid index: {}", err))?;
+    let offset = offset
+        .parse()
+        .map_err(|err| format!("invalid offset: {}", err))?;
+    Ok((index, offset))
+}
 
-    let vcds = parse_headers(inputs.iter(), &mut headers);
-
-    let sections = if !reorder {
-        vcds.iter()
-            .map(|vcd| {
-                let needle = b"\n$enddefinitions";
-                let end_of_header = vcd
-                    .file
-                    .windows(needle.len())
-                    .position(|x| x == needle)
-                    .map(|x| x + needle.len())
-                    .unwrap();
-                let needle2 = b"$end";
-                let end = vcd.file[end_of_header..]
-                    .windows(needle2.len())
-                    .position(|x| x == needle2)
-                    .map(|x| end_of_header + x + needle2.len())
-                    .unwrap();
-                Section {
-                    value: 0,
-                    section: &vcd.file[end..],
-                    vcd,
-                }
-            })
-            .collect()
-    } else {
-        count += 1;
-        println!("[{count}/{total}] finding sections");
+fn parse_rename(s: &str) -> Result<(String, String), String> {
+    let (old, new) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected OLD=NEW, found {}", s))?;
+    let scope = |path: &str| path.rsplit_once('.').map(|(scope, _)| scope.to_string());
+    if scope(old) != scope(new) {
+        return Err(format!("{} and {} are not in the same scope", old, new));
+    }
+    Ok((old.to_string(), new.to_string()))
+}
 
-        let total_len = vcds.iter().map(|vcd| vcd.file.len() as u64).sum::<u64>();
-        let bar = indicatif::ProgressBar::new(total_len).with_style(style.clone());
-        let on_progress = |progress| bar.set_position(progress);
+fn parse_avoid_chars(s: &str) -> Result<String, String> {
+    if !s.bytes().all(|b| (0x21..=0x7E).contains(&b)) {
+        return Err(format!(
+            "{:?} contains characters that are not used in identifiers",
+            s
+        ));
+    }
+    if (0x21..=0x7E).all(|b| s.as_bytes().contains(&b)) {
+        return Err("can't avoid all identifier characters".to_string());
+    }
+    Ok(s.to_string())
+}
 
-        let sections = find_sections(&vcds, on_progress);
+/// The characters to avoid in the generated identifiers, from `--avoid-chars` and
+/// `--gtkwave-compat`.
+fn avoid_chars(args: &Cli) -> Vec<u8> {
+    let mut avoid = args.avoid_chars.clone().unwrap_or_default().into_bytes();
+    if args.gtkwave_compat {
+        avoid.extend((0x21..=0x7E).filter(|b: &u8| !b.is_ascii_alphanumeric()));
+    }
+    avoid
+}
 
-        bar.finish();
+fn parse_timescale_arg(s: &str) -> Result<u64, String> {
+    match parse_timescale(s) {
+        Some(0) => Err(format!(
+            "invalid timescale: {}, timescale must be positive",
+            s
+        )),
+        Some(x) => Ok(x),
+        None => Err(format!("invalid timescale: {}", s)),
+    }
+}
 
-        sections
+fn parse_filter(s: &str) -> Result<(Option<PathBuf>, String), String> {
+    let (input, pattern) = match s.rsplit_once(':') {
+        Some((input, pattern)) => (Some(PathBuf::from(input)), pattern),
+        None => (None, s),
     };
+    if pattern.is_empty() {
+        return Err(format!("empty pattern in {:?}", s));
+    }
+    Ok((input, pattern.to_string()))
+}
 
-    count += 1;
-    println!("[{count}/{total}] merging {} sections", sections.len());
-
-    let total_len = sections.iter().map(|s| s.section.len() as u64).sum::<u64>();
-    let bar = indicatif::ProgressBar::new(total_len).with_style(style);
-    let on_progress = |progress| bar.set_position(progress);
-
-    write_output(output, headers, &vcds, sections, on_progress).unwrap();
+fn parse_bit_order(s: &str) -> Result<(Option<PathBuf>, BitOrder), String> {
+    let (input, order) = match s.rsplit_once(':') {
+        Some((input, order)) => (Some(PathBuf::from(input)), order),
+        None => (None, s),
+    };
+    Ok((input, BitOrder::from_str(order, false)?))
+}
 
-    bar.finish();
+fn parse_timescale_label(s: &str) -> Result<TimescaleLabel, String> {
+    let (unit, spaced) = match s.strip_suffix("-spaced") {
+        Some(unit) => (unit, true),
+        None => (s, false),
+    };
+    let unit = match unit {
+        "auto" => None,
+        unit => Some(TimeUnit::from_str(unit, false)?),
+    };
+    Ok(TimescaleLabel { unit, spaced })
 }
 
-fn next_code() -> IdCode {
-    static CURR_CODE: Mutex<IdCode> = Mutex::new(IdCode([0; 4])); // '!'
-    let mut code = CURR_CODE.lock().unwrap();
+fn parse_max_time(s: &str) -> Result<u64, String> {
+    parse_timescale(s).ok_or_else(|| format!("invalid time: {}", s))
+}
 
-    for b in code.0.iter_mut() {
-        // '~'
-        if *b == 0x0 {
-            // '!'
-            *b = 0x21;
-            break;
+/// Replace each directory in `inputs` by the `*.vcd` files inside it, compressed or not, sorted by
+/// path.
+fn expand_inputs(inputs: &[PathBuf], recursive: bool) -> Vec<PathBuf> {
+    let mut expanded = Vec::new();
+    for input in inputs {
+        if !input.is_dir() {
+            expanded.push(input.clone());
+            continue;
         }
-        if *b < 0x7E {
-            *b += 1;
-            break;
-        } else {
-            // '!'
-            *b = 0x21;
+        let mut files = Vec::new();
+        if let Err(err) = find_vcd_files(input, recursive, &mut files) {
+            eprintln!("Could not read directory {}: {}", input.display(), err);
+            std::process::exit(2);
+        }
+        if files.is_empty() {
+            eprintln!("No .vcd files found in the directory {}", input.display());
+            std::process::exit(1);
         }
+        files.sort();
+        expanded.extend(files);
     }
-
-    *code
+    expanded
 }
 
-fn take_to_end(tokens: &mut impl Iterator<Item = String>) -> String {
-    let mut scale = String::with_capacity(8);
-    for token in tokens.by_ref() {
-        if token == "$end" {
-            break;
+/// Remove the inputs that are the same file, or have the same content, as a previous input with the
+/// same time offset, together with their time offset, warning about each one. Inputs in `bound`,
+/// which options apply to by path, are only compared by file, as a copy may be merged differently.
+fn dedup_inputs(
+    inputs: &mut Vec<PathBuf>,
+    time_offsets: &mut Vec<i64>,
+    bound: &std::collections::HashSet<PathBuf>,
+) {
+    let mut seen = std::collections::HashSet::new();
+    let mut keep = Vec::with_capacity(inputs.len());
+    let mut is_bound = Vec::with_capacity(inputs.len());
+    for (input, &offset) in inputs.iter().zip(time_offsets.iter()) {
+        let canonical = std::fs::canonicalize(input).unwrap_or_else(|_| input.clone());
+        is_bound.push(bound.contains(&canonical));
+        let first = seen.insert((canonical, offset));
+        if !first {
+            eprintln!(
+                "warning: {} is given more than once as input with the same time offset, it is \
+                 only merged once",
+                input.display()
+            );
         }
-        scale.push_str(&token);
-        scale.push(' ');
+        keep.push(first);
     }
-    scale
-}
 
-fn parse_headers<'a, 'b>(
-    inputs: impl Iterator<Item = impl AsRef<Path>> + 'b,
-    header: &mut Header,
-) -> Vec<Vcd> {
-    let mut vcds: Vec<Vcd> = inputs
-        .map(|input| parse_header(input.as_ref(), header))
-        .collect();
+    // only files with the same length can be identical, so only these are hashed.
+    let lengths = inputs
+        .iter()
+        .zip(&is_bound)
+        .map(|(input, &bound)| match input == Path::new("-") || bound {
+            true => None,
+            false => std::fs::metadata(input).ok().map(|m| m.len()),
+        })
+        .collect::<Vec<_>>();
+    let mut contents = std::collections::HashMap::new();
+    for (i, input) in inputs.iter().enumerate() {
+        let Some(len) = lengths[i] else { continue };
+        let shared = (0..inputs.len()).any(|j| j != i && keep[j] && lengths[j] == Some(len));
+        if !keep[i] || !shared {
+            continue;
+        }
+        let Some(data) = map_file(input) else {
+            continue;
+        };
+        let key = (fxhash::hash64(&data[..]), time_offsets[i]);
+        let previous = contents.entry(key).or_insert_with(Vec::new);
+        // compare the content, in case of a hash collision.
+        match previous
+            .iter()
+            .find(|(_, x): &&(usize, memmap2::Mmap)| x[..] == data[..])
+        {
+            Some(&(j, _)) => {
+                eprintln!(
+                    "warning: {} has the same content as {}, it is only merged once",
+                    input.display(),
+                    inputs[j].display()
+                );
+                keep[i] = false;
+            }
+            None => previous.push((i, data)),
+        }
+    }
+
+    let mut keep_iter = keep.iter();
+    inputs.retain(|_| *keep_iter.next().unwrap());
+    let mut keep_iter = keep.iter();
+    time_offsets.retain(|_| *keep_iter.next().unwrap());
+}
 
-    set_common_timescale(&mut vcds, header);
+/// The canonical paths of the inputs that a `--bit-order`, `--include`, `--exclude` or
+/// `--priority` applies to by path.
+fn bound_inputs(args: &Cli) -> std::collections::HashSet<PathBuf> {
+    let bit_orders = args
+        .bit_order
+        .iter()
+        .filter_map(|(input, _)| input.as_ref());
+    let includes = args.include.iter().filter_map(|(input, _)| input.as_ref());
+    let excludes = args.exclude.iter().filter_map(|(input, _)| input.as_ref());
+    bit_orders
+        .chain(includes)
+        .chain(excludes)
+        .chain(&args.priority)
+        .map(|path| std::fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+        .collect()
+}
 
-    vcds
+/// Whether the sections of the inputs can be found while merging, with [`lazy_sections`], because
+/// no other step needs all of them up front.
+fn stream_sections(args: &Cli) -> bool {
+    args.resume.is_none()
+        && args.checkpoint_interval.is_none()
+        && !args.profile_sections
+        && args.limit_sections.is_none()
+        && args.dump_sections.is_none()
+        && args.activity.is_none()
+        && !args.count_only
+        && args.preview.is_none()
 }
 
-fn parse_header(input: &Path, header: &mut Header) -> Vcd {
-    let file = std::fs::File::open(input);
+/// A memory map of the file at `path`, or `None` if it can't be mapped.
+fn map_file(path: &Path) -> Option<memmap2::Mmap> {
+    let file = std::fs::File::open(path).ok()?;
+    unsafe { memmap2::Mmap::map(&file).ok() }
+}
 
-    let file = match file {
-        Ok(file) => file,
+/// Read the hierarchical names of a `--signal-order` file, one per line, skipping blank lines.
+fn read_signal_order(path: &Path) -> Vec<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect(),
         Err(err) => {
-            eprintln!("Coud not open {}: {}", input.display(), err);
+            eprintln!("Could not read {}: {}", path.display(), err);
             std::process::exit(2);
         }
-    };
+    }
+}
 
-    // let mut reader = BufReader::with_capacity(0x1_0000, file);
-    let memmap = unsafe { memmap2::MmapOptions::new().map(&file) };
+/// The path of the input given to `--priority` as each of `paths`, which may be written
+/// differently.
+fn priority_paths(paths: &[PathBuf], vcds: &[Vcd]) -> Vec<PathBuf> {
+    let inputs = vcds.iter().map(Vcd::path).collect::<Vec<_>>();
+    paths
+        .iter()
+        .map(|path| input_path("--priority", path, &inputs))
+        .collect()
+}
 
-    let memmap = match memmap {
-        Ok(x) => x,
-        Err(err) => {
-            eprintln!("Could not memmap {}: {}", input.display(), err);
-            std::process::exit(3);
+/// The input of `inputs` at `path`, which may be written differently. Exits if there is none.
+fn input_path(option: &str, path: &Path, inputs: &[&Path]) -> PathBuf {
+    let canonical = |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.into());
+    let target = canonical(path);
+    match inputs.iter().find(|&&input| canonical(input) == target) {
+        Some(input) => input.to_path_buf(),
+        None => {
+            eprintln!("{}: {} is not an input", option, path.display());
+            std::process::exit(1);
         }
-    };
-
-    let mut reader = std::io::Cursor::new(memmap);
-
-    let mut lines = (&mut reader).lines().map_while(Result::ok);
-
-    let mut tokens = lines.by_ref().flat_map(|line| {
-        line.split_ascii_whitespace()
-            .map(String::from)
-            .collect::<Vec<_>>()
-    });
+    }
+}
 
-    let mut symbol_map = HashMap::default();
+/// The `--bit-order` of each input, from the last option that applies to it.
+fn bit_orders(args: &Cli, inputs: &[PathBuf]) -> Vec<BitOrder> {
+    let paths = inputs.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+    let orders = args
+        .bit_order
+        .iter()
+        .map(|(input, order)| {
+            let input = input
+                .as_deref()
+                .map(|input| input_path("--bit-order", input, &paths));
+            (input, *order)
+        })
+        .collect::<Vec<_>>();
+    inputs
+        .iter()
+        .map(|path| {
+            orders
+                .iter()
+                .rev()
+                .find(|(input, _)| input.as_ref().is_none_or(|input| input == path))
+                .map_or(BitOrder::Msb, |&(_, order)| order)
+        })
+        .collect()
+}
 
-    let mut declarations = Vec::new();
+/// The `--include` and `--exclude` filters, with their paths resolved to the inputs.
+fn signal_filters(args: &Cli, inputs: &[PathBuf]) -> Vec<SignalFilter> {
+    let inputs = inputs.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+    let includes = args
+        .include
+        .iter()
+        .map(|filter| ("--include", filter, true));
+    let excludes = args
+        .exclude
+        .iter()
+        .map(|filter| ("--exclude", filter, false));
+    includes
+        .chain(excludes)
+        .map(|(option, (input, pattern), include)| SignalFilter {
+            input: input
+                .as_deref()
+                .map(|input| input_path(option, input, &inputs)),
+            pattern: pattern.clone(),
+            include,
+        })
+        .collect()
+}
 
-    let mut timescale = 0;
+/// The options of [`write_output`] given by `args`.
+fn write_options(
+    args: &Cli,
+    priority: Vec<PathBuf>,
+    signal_order: Option<Vec<String>>,
+    max_time: Option<u64>,
+) -> WriteOptions<'static> {
+    WriteOptions {
+        embed_map: args.embed_map,
+        mmap_output: args.mmap_output,
+        max_time,
+        conflict: args.conflict,
+        priority,
+        flatten_scopes: args.flatten_scopes,
+        sort_signals: args.sort_signals,
+        signal_order: signal_order.unwrap_or_default(),
+        canonical_names: args.canonicalize,
+        checkpoint_path: args
+            .checkpoint_interval
+            .and(args.output.as_ref())
+            .map(|output| {
+                let mut path = output.clone().into_os_string();
+                path.push(".checkpoint");
+                PathBuf::from(path)
+            }),
+        checkpoint_interval: args.checkpoint_interval.map_or(0, NonZeroUsize::get),
+        delta_epsilon: args.delta_encode.then_some(args.epsilon),
+        downsample: args.downsample.map_or(0, NonZeroUsize::get),
+        spaced: args.spaced,
+        normalize_values: args.normalize_values,
+        timescale_label: args.output_timescale_label,
+        ..Default::default()
+    }
+}
 
-    while let Some(token) = tokens.next() {
-        match token.as_str() {
-            "$date" => {
-                let date = take_to_end(&mut tokens);
-                if header.date.is_none() {
-                    header.date = Some(date);
-                }
-            }
-            "$version" => {
-                let version = take_to_end(&mut tokens);
-                if header.version.is_none() {
-                    header.version = Some(version);
-                }
-            }
-            "$timescale" => {
-                let scale = take_to_end(&mut tokens);
-
-                // parse .*\d*.*(fs|ps|ns|us|ms|s)
-                let n = scale
-                    .find(|x: char| x.is_ascii_digit())
-                    .expect("invalid timestamp");
-                let e = scale[n..]
-                    .find(|x: char| !x.is_ascii_digit())
-                    .expect("invalid timestamp");
-                let u = scale[n + e..]
-                    .find(['f', 'p', 'n', 'u', 'm', 's'])
-                    .expect("invalid timestamp");
-
-                let number = parse_u64(scale[n..n + e].as_bytes()).unwrap();
-                let unit = &scale[n + e + u..];
-                timescale = match unit[..2].as_bytes() {
-                    b"fs" => number,
-                    b"ps" => number * 1_000,
-                    b"ns" => number * 1_000_000,
-                    b"us" => number * 1_000_000_000,
-                    b"ms" => number * 1_000_000_000_000,
-                    [b's', _] => number * 1_000_000_000_000_000,
-                    _ => panic!("invalid timestamp"),
-                };
-            }
-            "$scope" => {
-                let module = tokens.next().unwrap();
-                let name = tokens.next().unwrap();
-                let end = tokens.next().unwrap();
+/// Write each top-level scope of `input` to its own file in the `--output` directory, named after
+/// the scope, for `--split-by-scope`. `vcd` is `input` parsed with `parse_options`, which are
+/// extended with filters that leave out the other scopes.
+fn split_by_scope(
+    args: &Cli,
+    vcd: &Vcd,
+    mut parse_options: ParseOptions,
+    mut options: WriteOptions,
+    reorder: bool,
+) {
+    let scopes = vcd.top_scopes();
+
+    // vars outside of any scope don't belong to a single file, so they are left out.
+    let top_vars = vcd.top_vars();
+    if !top_vars.is_empty() {
+        eprintln!(
+            "warning: {} vars declared outside of any scope are not written to any file",
+            top_vars.len()
+        );
+    }
 
-                assert_eq!(end, "$end");
+    let dir = args.output.as_ref().unwrap();
+    if let Err(err) = std::fs::create_dir_all(dir) {
+        eprintln!("Could not create {}: {}", dir.display(), err);
+        std::process::exit(6);
+    }
 
-                declarations.push(format!("$scope {} {} $end\n", module, name));
-            }
-            "$var" => {
-                let ty = tokens.next().unwrap();
-                let width = tokens.next().unwrap();
-                let old_id = tokens.next().unwrap();
-                let name = take_to_end(&mut tokens);
-
-                let old_id = IdCode::from(old_id.as_bytes());
-                let new_id = symbol_map.entry(old_id).or_insert_with(next_code);
-
-                declarations.push(format!(
-                    "$var {} {} {} {} $end\n",
-                    ty,
-                    width,
-                    std::str::from_utf8(new_id.as_bytes()).unwrap(),
-                    name.trim()
-                ));
-            }
-            "$upscope" => {
-                let end = tokens.next().unwrap();
-                assert_eq!(end, "$end");
-                declarations.push("$upscope $end\n".to_string());
-            }
-            "$enddefinitions" => {
-                let end = tokens.next().unwrap();
-                assert_eq!(end, "$end");
-                break;
-            }
-            "$dumpvars" => {
-                break;
-            }
-            _ => {
-                break;
-            }
+    let filters = parse_options.filters.len();
+    let mut file_names = std::collections::HashSet::new();
+    for scope in &scopes {
+        let others = scopes.iter().filter(|&other| other != scope);
+        let excluded = others
+            .map(|other| format!("{}.*", other))
+            .chain(top_vars.iter().map(|var| var.to_string()));
+        parse_options
+            .filters
+            .extend(excluded.map(|pattern| SignalFilter {
+                input: None,
+                pattern,
+                include: false,
+            }));
+
+        // each file has its own identifiers, starting from `!`.
+        reset_identifiers();
+
+        let mut headers = Header::default();
+        let vcds = parse_headers(std::iter::once(vcd.path()), &mut headers, &parse_options)
+            .unwrap_or_else(|err| exit_with(err));
+        let mut sections = match reorder {
+            true => find_sections(&vcds, threads(args), |_| {}),
+            false => file_sections(&vcds),
+        }
+        .unwrap_or_else(|err| exit_with(err.into()));
+        if let Some(max_time) = options.max_time {
+            sections.retain(|s| s.value <= max_time);
         }
-    }
 
-    if timescale == 0 {
-        panic!("missing timescale");
-    }
+        let output = dir.join(scope_file_name(scope, &mut file_names));
+        if let Err(err) = write_output(&output, headers, &vcds, sections, &options, |_| {}) {
+            exit_with(err);
+        }
+        println!("{}", output.display());
 
-    Vcd {
-        symbol_map,
-        declarations,
-        end_of_definitions: reader.position() as usize,
-        file: reader.into_inner(),
-        timescale,
+        // all files have the same timestamps, so unsorted ones were already reported.
+        options.quiet_unsorted = true;
+        parse_options.filters.truncate(filters);
     }
 }
 
-fn set_common_timescale(vcds: &mut [Vcd], header: &mut Header) {
-    let gcd = vcds
-        .iter()
-        .map(|vcd| vcd.timescale)
-        .fold(vcds[0].timescale, gcd);
-
-    for vcd in vcds.iter_mut() {
-        vcd.timescale /= gcd;
+/// The name of the file of `scope` for `--split-by-scope`, with the characters that are not
+/// allowed in file names replaced by `_`, and different from the ones in `used`.
+fn scope_file_name(scope: &str, used: &mut std::collections::HashSet<String>) -> String {
+    let mut name = scope
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    // `.` and `..` are the directory itself and its parent.
+    if name.bytes().all(|b| b == b'.') {
+        name.insert(0, '_');
+    }
+    let mut unique = name.clone();
+    let mut count = 1;
+    while !used.insert(unique.clone()) {
+        count += 1;
+        unique = format!("{}_{}", name, count);
     }
+    format!("{}.vcd", unique)
+}
 
-    let timescale = match gcd {
-        x if x % 1_000_000_000_000_000 == 0 => format!("{}s", x / 1_000_000_000_000_000),
-        x if x % 1_000_000_000_000 == 0 => format!("{}ms", x / 1_000_000_000_000),
-        x if x % 1_000_000_000 == 0 => format!("{}us", x / 1_000_000_000),
-        x if x % 1_000_000 == 0 => format!("{}ns", x / 1_000_000),
-        x if x % 1_000 == 0 => format!("{}ps", x / 1_000),
-        x => format!("{}fs", x),
+/// Print `err` and exit with the code of its kind.
+fn exit_with(err: Error) -> ! {
+    let code = match err {
+        Error::Read { .. } => 2,
+        Error::Map { .. } => 3,
+        Error::Create { .. } => 4,
+        Error::Header { .. } => 5,
+        Error::Write { .. } => 6,
+        Error::Time(_) => 9,
+        Error::NoInputs => 1,
     };
+    eprintln!("error: {}", err);
+    std::process::exit(code)
+}
 
-    println!("timescale set to {}", timescale);
+/// Split the merged `output` in chunks of `split_size` bytes, for `--split-size`, and write their
+/// manifest. The merged file is removed once all chunks are written.
+fn split_output_file(output: &Path, split_size: u64) {
+    let chunks = split_output(output, split_size).unwrap_or_else(|err| {
+        eprintln!("Could not split {}: {}", output.display(), err);
+        std::process::exit(6);
+    });
+    let manifest = manifest_path(output);
+    if let Err(err) = write_split_manifest(&manifest, &chunks) {
+        eprintln!("Could not write {}: {}", manifest.display(), err);
+        std::process::exit(4);
+    }
+    let _ = std::fs::remove_file(output);
+    println!(
+        "{} chunks written, listed in {}",
+        chunks.len(),
+        manifest.display()
+    );
+}
 
-    header.timescale = Some(timescale);
+/// The number of threads used for finding sections.
+fn threads(args: &Cli) -> usize {
+    args.threads
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+}
 
-    let symbol_count: usize = vcds.iter().map(|vcd| vcd.symbol_map.len()).sum();
+/// Print the problems found in each input, and exit with a error if there are any.
+fn validate_inputs(inputs: &[PathBuf]) {
+    let mut valid = true;
+    for input in inputs {
+        let problems = match validate(input) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("Could not open {}: {}", input.display(), err);
+                std::process::exit(2);
+            }
+        };
+        if problems.is_empty() {
+            println!("{}: ok", input.display());
+        }
+        for problem in &problems {
+            println!("{}: {}", input.display(), problem);
+        }
+        valid &= problems.is_empty();
+    }
+    if !valid {
+        std::process::exit(1);
+    }
+}
 
-    println!("{} signals found", symbol_count);
+/// Print the number of sections of each input, and a histogram of their sizes in bytes, with a
+/// bucket for each power of two.
+fn print_section_profile(vcds: &[Vcd], sections: &[Section]) {
+    for vcd in vcds {
+        let mut buckets = std::collections::BTreeMap::<usize, usize>::new();
+        let mut count = 0;
+        for section in sections.iter().filter(|s| std::ptr::eq(s.vcd, vcd)) {
+            *buckets
+                .entry(section.section.len().next_power_of_two())
+                .or_default() += 1;
+            count += 1;
+        }
+        println!("{}: {} sections", vcd.path().display(), count);
+        for (size, count) in buckets {
+            println!("{:>12} up to {} bytes", count, size);
+        }
+    }
 }
 
-fn gcd(mut n: u64, mut m: u64) -> u64 {
-    assert!(n != 0 && m != 0);
-    while m != 0 {
-        if m < n {
-            (m, n) = (n, m);
+/// The extensions of the files that [`find_vcd_files`] finds.
+const VCD_EXTENSIONS: [&str; 4] = [".vcd", ".vcd.gz", ".vcd.zst", ".vcd.lz4"];
+
+fn find_vcd_files(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                find_vcd_files(&path, recursive, files)?;
+            }
+        } else if path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            VCD_EXTENSIONS
+                .iter()
+                .any(|extension| name.ends_with(extension))
+        }) {
+            files.push(path);
         }
-        m %= n;
     }
-    n
+    Ok(())
 }
 
-struct Section<'a> {
-    value: u64,
-    section: &'a [u8],
-    vcd: &'a Vcd,
+const PROGRESS_BAR_TEMPLATE: &str = "\
+{elapsed_precise} █{bar:60.cyan/blue}█ {bytes}/{total_bytes} {binary_bytes_per_sec} ({eta})";
+
+/// How the progress of a step is shown: a progress bar, or JSON lines for `--progress-json`.
+enum StepProgress {
+    Bar(indicatif::ProgressBar),
+    Json { phase: Phase, total: u64 },
 }
-impl<'a> PartialEq for Section<'a> {
-    fn eq(&self, other: &Self) -> bool {
-        self.value == other.value
+impl StepProgress {
+    fn new(phase: Phase, total: u64, style: indicatif::ProgressStyle, args: &Cli) -> Self {
+        match args.progress_json {
+            true => StepProgress::Json { phase, total },
+            false => StepProgress::Bar(progress_bar(total, style, args.no_progress)),
+        }
     }
-}
-impl<'a> PartialOrd for Section<'a> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+
+    fn set_position(&self, bytes: u64) {
+        match self {
+            StepProgress::Bar(bar) => bar.set_position(bytes),
+            &StepProgress::Json { phase, total } => {
+                let phase = match phase {
+                    Phase::Gathering => "gathering",
+                    Phase::Finding => "finding",
+                    Phase::Merging => "merging",
+                };
+                eprintln!(
+                    "{{\"phase\":\"{}\",\"bytes\":{},\"total\":{}}}",
+                    phase, bytes, total
+                );
+            }
+        }
     }
-}
-impl<'a> Ord for Section<'a> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.value.cmp(&other.value)
+
+    fn finish(&self) {
+        match self {
+            StepProgress::Bar(bar) => bar.finish(),
+            &StepProgress::Json { total, .. } => self.set_position(total),
+        }
     }
 }
-impl<'a> Eq for Section<'a> {}
 
-fn parse_u64(s: &[u8]) -> Result<u64, ()> {
-    let mut value = 0;
-    for &b in s {
-        if !b.is_ascii_digit() {
-            return Err(());
-        }
-        value = value * 10 + (b - b'0') as u64;
+/// A progress bar of `total` bytes, or a hidden one if `hidden` or if there is nothing to process,
+/// since the rate and ETA of a empty bar are meaningless.
+fn progress_bar(
+    total: u64,
+    style: indicatif::ProgressStyle,
+    hidden: bool,
+) -> indicatif::ProgressBar {
+    if hidden || total == 0 {
+        return indicatif::ProgressBar::hidden();
     }
-    Ok(value)
+    indicatif::ProgressBar::new(total).with_style(style)
 }
 
-fn u64_to_bytes(mut value: u64, buffer: &mut [u8; 20]) -> &[u8] {
-    if value == 0 {
-        return b"0";
+fn main() {
+    let args = Cli::parse_from(args_with_config());
+
+    let mut inputs = expand_inputs(&args.input, args.recursive);
+    if inputs.is_empty() && args.append.is_none() {
+        eprintln!("No inputs given");
+        std::process::exit(1);
     }
-    let mut i = buffer.len();
-    while value > 0 {
-        i -= 1;
-        buffer[i] = (value % 10) as u8 + b'0';
-        value /= 10;
+    let reorder = (args.reorder || args.canonicalize) && !args.assume_sorted;
+
+    if args.split_by_scope && inputs.len() != 1 {
+        eprintln!(
+            "--split-by-scope takes a single input, but {} were given",
+            inputs.len()
+        );
+        std::process::exit(1);
     }
-    &buffer[i..]
-}
 
-// Find sections of sorted signal changes. These will be merged sorted when written to the output
-// file.
-fn find_sections(vcds: &[Vcd], mut on_progress: impl FnMut(u64)) -> Vec<Section> {
-    let mut sections = Vec::new();
+    // each file parses the input again, which can't be done with the standard input.
+    if args.split_by_scope && inputs[0] == Path::new("-") {
+        eprintln!("--split-by-scope can't read the input from the standard input");
+        std::process::exit(1);
+    }
 
-    let mut line_count: usize = 0;
-    let mut progress = 0;
+    if args.canonicalize && inputs.len() != 1 {
+        eprintln!(
+            "--canonicalize takes a single input, but {} were given",
+            inputs.len()
+        );
+        std::process::exit(1);
+    }
 
-    for vcd in vcds {
-        let lines = vcd.file[vcd.end_of_definitions..].split(|&b| b == b'\n');
-        let mut curr_section = None;
+    if args.validate_only {
+        validate_inputs(&inputs);
+        return;
+    }
 
-        for line in lines {
-            line_count += 1;
+    let signal_order = args.signal_order.as_deref().map(read_signal_order);
+    let symbol_map = match &args.symbol_map_in {
+        Some(path) => read_symbol_map(path).unwrap_or_else(|err| {
+            eprintln!("Could not read {}: {}", path.display(), err);
+            std::process::exit(2);
+        }),
+        None => Vec::new(),
+    };
 
-            // Same logic as the one described in write_output, but this is 3 times faster
-            if line_count % 0xC_0000 == 0 {
-                let offset = line.as_ptr() as usize - vcd.file.as_ptr() as usize;
-                on_progress(progress + offset as u64);
-            }
+    let style = indicatif::ProgressStyle::default_bar()
+        .template(PROGRESS_BAR_TEMPLATE)
+        .unwrap()
+        .progress_chars("█▉▊▋▌▍▎▏  ");
 
-            if let [b'#', ..] = line {
-                let offset = line.as_ptr() as usize - vcd.file.as_ptr() as usize;
-                let curr_line_value = parse_u64(&line[1..]).unwrap() * vcd.timescale;
+    let mut count = 0;
+    let mut total = if reorder && !args.header_only && !args.split_by_scope {
+        3
+    } else {
+        2
+    };
+    if args.split_size.is_some() {
+        total += 1;
+    }
 
-                // if this is the first line, start a new section
-                let Some((section_offset, section_value, last_line_value)) = curr_section else {
-                    curr_section = Some((offset, curr_line_value, curr_line_value));
-                    continue;
-                };
+    // a preview is written to stdout, so the progress messages go to stderr instead.
+    macro_rules! progress {
+        ($($arg:tt)*) => {
+            if args.preview.is_some() {
+                eprintln!($($arg)*);
+            } else {
+                println!($($arg)*);
+            }
+        };
+    }
 
-                // if out of order, end this section here
-                if curr_line_value < last_line_value {
-                    let section = &vcd.file[section_offset..offset];
+    count += 1;
+    progress!("[{count}/{total}] gathering symbols");
 
-                    sections.push(Section {
-                        value: section_value,
-                        section,
-                        vcd,
-                    });
+    let mut headers = Header::default();
 
-                    curr_section = Some((offset, curr_line_value, curr_line_value));
-                } else {
-                    curr_section = Some((section_offset, section_value, curr_line_value));
-                }
+    let mut time_offsets = vec![0; inputs.len()];
+    for &(index, offset) in &args.time_offset {
+        match time_offsets.get_mut(index) {
+            Some(x) => *x = offset,
+            None => {
+                eprintln!("Invalid time offset: there is no input {}", index);
+                std::process::exit(1);
             }
         }
+    }
 
-        // add the last section
-        if let Some((last_line_offset, last_line_value, _)) = curr_section {
-            let section = &vcd.file[last_line_offset..];
-            sections.push(Section {
-                value: last_line_value,
-                section,
-                vcd,
-            });
-        }
+    if !args.allow_duplicate_inputs {
+        dedup_inputs(&mut inputs, &mut time_offsets, &bound_inputs(&args));
+    }
 
-        progress += vcd.file.len() as u64;
+    let parse_options = ParseOptions {
+        rescan_declarations: args.rescan_declarations,
+        time_offsets,
+        negative_time: args.negative_time,
+        bit_orders: bit_orders(&args, &inputs),
+        append: args.append.clone(),
+        unify_by_name: args.unify_by_name,
+        fallback_timescale: args.timescale,
+        output_timescale: args.timescale,
+        scope_per_file: args.scope_per_file,
+        exclude_types: args.exclude_types.clone(),
+        filters: signal_filters(&args, &inputs),
+        renames: args.rename.clone(),
+        avoid_chars: avoid_chars(&args),
+        limit_signals: args.limit_signals,
+        symbol_map,
+        no_mmap: args.no_mmap,
+    };
+
+    let mut vcds = parse_headers(inputs.iter(), &mut headers, &parse_options)
+        .unwrap_or_else(|err| exit_with(err));
+    for warning in vcds.iter().flat_map(Vcd::warnings) {
+        eprintln!("warning: {}", warning);
     }
+    progress!("timescale set to {}", headers.timescale_name().unwrap());
+    progress!(
+        "{} signals found",
+        vcds.iter().map(Vcd::signal_count).sum::<usize>()
+    );
+    let priority = priority_paths(&args.priority, &vcds);
 
-    sections
-}
+    if args.split_by_scope {
+        count += 1;
+        progress!("[{count}/{total}] splitting by scope");
+
+        let max_time = args
+            .max_time
+            .map(|max_time| max_time / headers.timescale().unwrap());
+        let options = write_options(&args, priority, signal_order, max_time);
+        split_by_scope(&args, &vcds[0], parse_options, options, reorder);
+        return;
+    }
 
-fn write_output<'a>(
-    output: impl AsRef<Path>,
-    headers: Header,
-    vcds: &'a [Vcd],
-    mut sections: Vec<Section<'a>>,
-    mut on_progress: impl FnMut(u64),
-) -> std::io::Result<()> {
-    let out_file = std::fs::File::create(output.as_ref());
+    if args.input_timescale_report {
+        for vcd in &vcds {
+            progress!(
+                "{}: {:?} = {}fs",
+                vcd.path().display(),
+                vcd.raw_timescale().unwrap_or_default(),
+                vcd.input_timescale()
+            );
+        }
+        return;
+    }
 
-    let out_file = match out_file {
-        Ok(x) => x,
-        Err(err) => {
+    if args.warn_wide_ids {
+        let used = identifiers_used();
+        if used * 10 > IDENTIFIER_CAPACITY * 9 {
             eprintln!(
-                "Could not create file {}: {}",
-                output.as_ref().display(),
-                err
+                "warning: {} of the {} identifiers are used, merging more signals may run out of \
+                 identifiers",
+                used, IDENTIFIER_CAPACITY
             );
+        }
+    }
+
+    if args.require_same_signals {
+        let missing = missing_signals(&vcds);
+        if !missing.is_empty() {
+            eprintln!("The inputs don't declare the same signals:");
+            for (name, inputs) in &missing {
+                let inputs = inputs
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>();
+                eprintln!("  {} is missing in {}", name, inputs.join(", "));
+            }
+            std::process::exit(8);
+        }
+    }
+
+    if args.prune_unused {
+        let pruned = prune_unused(&mut vcds);
+        progress!("{} unused signals pruned", pruned);
+    }
+
+    if let Some(path) = &args.json_signals {
+        if let Err(err) = write_json_signals(path, &vcds) {
+            eprintln!("Could not write {}: {}", path.display(), err);
             std::process::exit(4);
         }
+    }
+
+    if let Some(path) = &args.symbol_map {
+        if let Err(err) = write_symbol_map(path, &vcds) {
+            eprintln!("Could not write {}: {}", path.display(), err);
+            std::process::exit(4);
+        }
+    }
+
+    let checkpoint = args
+        .resume
+        .as_ref()
+        .map(|path| match Checkpoint::read(path) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("Could not read checkpoint {}: {}", path.display(), err);
+                std::process::exit(7);
+            }
+        });
+
+    // the sections of streamed inputs, found while merging.
+    let mut lazy = None;
+
+    let mut sections = if let Some(checkpoint) = &checkpoint {
+        match checkpoint.sections(&vcds) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("Could not resume the merge: {}", err);
+                std::process::exit(7);
+            }
+        }
+    } else if args.header_only {
+        Vec::new()
+    } else if !reorder {
+        file_sections(&vcds).unwrap_or_else(|err| exit_with(err.into()))
+    } else {
+        count += 1;
+        progress!("[{count}/{total}] finding sections");
+
+        let total_len = vcds.iter().map(|vcd| vcd.file().len() as u64).sum::<u64>();
+        let bar = StepProgress::new(Phase::Finding, total_len, style.clone(), &args);
+        let on_progress = |progress| bar.set_position(progress);
+
+        let sections = if stream_sections(&args) {
+            let sections = lazy_sections(&vcds, threads(&args), on_progress)
+                .unwrap_or_else(|err| exit_with(err.into()));
+            lazy = Some(sections);
+            Vec::new()
+        } else {
+            find_sections(&vcds, threads(&args), on_progress)
+                .unwrap_or_else(|err| exit_with(err.into()))
+        };
+
+        bar.finish();
+
+        sections
     };
 
-    let mut out_writer = BufWriter::with_capacity(0x1_0000, out_file); // 64KiB
+    if args.profile_sections {
+        print_section_profile(&vcds, &sections);
+    }
+
+    // the max time in the units of the output timescale, rounded down.
+    let max_time = args
+        .max_time
+        .map(|max_time| max_time / headers.timescale().unwrap());
 
-    if let Some(date) = headers.date {
-        out_writer.write_all(b"$date ")?;
-        out_writer.write_all(date.as_bytes())?;
-        out_writer.write_all(b"$end\n")?;
+    if let Some(max_time) = max_time {
+        sections.retain(|s| s.value <= max_time);
     }
-    if let Some(version) = headers.version {
-        out_writer.write_all(b"$version ")?;
-        out_writer.write_all(version.as_bytes())?;
-        out_writer.write_all(b"$end\n")?;
+
+    // the sections of a checkpoint are already limited.
+    if let (Some(limit), None) = (args.limit_sections, &checkpoint) {
+        sections.sort_by_key(|s| s.value);
+        sections.truncate(limit);
     }
-    if let Some(timescale) = headers.timescale {
-        out_writer.write_all(b"$timescale ")?;
-        out_writer.write_all(timescale.as_bytes())?;
-        out_writer.write_all(b" $end\n")?;
+
+    if let Some(path) = &args.dump_sections {
+        if let Err(err) = write_sections_tsv(path, &sections) {
+            eprintln!("Could not write {}: {}", path.display(), err);
+            std::process::exit(4);
+        }
     }
 
-    for vcd in vcds.iter() {
-        for line in vcd.declarations.iter() {
-            out_writer.write_all(line.as_bytes())?;
+    if let Some(path) = &args.activity {
+        let counts =
+            count_changes(&vcds, &sections, max_time).unwrap_or_else(|err| exit_with(err.into()));
+        let timescale = headers.timescale().unwrap();
+        if let Err(err) = write_activity_csv(path, &counts, timescale) {
+            eprintln!("Could not write {}: {}", path.display(), err);
+            std::process::exit(4);
         }
     }
 
-    out_writer.write_all(b"$enddefinitions $end\n")?;
+    count += 1;
 
-    let mut heap = std::collections::BinaryHeap::from(
-        sections
-            .iter()
-            .enumerate()
-            .map(|(i, s)| Reverse((s.value, i)))
-            .collect::<Vec<_>>(),
-    );
+    if args.count_only {
+        println!("[{count}/{total}] counting {} sections", sections.len());
 
-    let mut progress = 0;
-    let mut line_count: usize = 0;
+        let counts = count_changes(&vcds, &sections, max_time)
+            .unwrap_or_else(|err| exit_with(err.into()))
+            .signals;
+        let changes = counts.iter().map(|(_, count)| count).sum::<u64>();
+        println!("{} value changes in {} signals", changes, counts.len());
+        for (name, count) in counts.iter().take(args.top) {
+            println!("{:>12} {}", count, name);
+        }
+        return;
+    }
 
-    let mut last_timestamp = None;
+    let label_unit = args.output_timescale_label.and_then(|label| label.unit);
+    if let (Some(unit), Some(timescale)) = (label_unit, headers.timescale()) {
+        if !timescale.is_multiple_of(unit.femtoseconds()) {
+            eprintln!(
+                "warning: the output timescale is not a whole number of {}, using the largest \
+                 unit that divides it instead",
+                unit.name()
+            );
+        }
+    }
 
-    'sections: while let Some(mut heap_entry) = heap.peek_mut() {
-        let Reverse((_, index)) = *heap_entry;
-        let section = &mut sections[index];
-        let mut lines = section.section.split(|x| *x == b'\n');
+    let options = write_options(&args, priority, signal_order, max_time);
 
-        // write the timestamp
-        if let Some(_line) = lines.next() {
-            if last_timestamp != Some(section.value) {
-                out_writer.write_all(b"#")?;
-                out_writer.write_all(u64_to_bytes(section.value, &mut [0; 20]))?;
-                out_writer.write_all(b"\n")?;
-            }
-            last_timestamp = Some(section.value);
-        } else {
-            unreachable!("a section always start with a timestamp");
+    if let Some(lines) = args.preview {
+        progress!("[{count}/{total}] previewing {} lines", lines);
+
+        let options = WriteOptions {
+            checkpoint_path: None,
+            ..options
+        };
+        let stdout = std::io::stdout().lock();
+        if let Err(err) = write_preview(stdout, headers, &vcds, sections, &options, lines) {
+            eprintln!("Could not write the preview: {}", err);
+            std::process::exit(6);
         }
+        return;
+    }
 
-        for line in lines {
-            progress += line.len() as u64 + 1;
+    if args.header_only {
+        println!("[{count}/{total}] writing the header");
+    } else if let Some(lazy) = &lazy {
+        let (sections, streamed) = lazy.counts();
+        println!(
+            "[{count}/{total}] merging {} sections and {} streamed inputs",
+            sections, streamed
+        );
+    } else {
+        println!("[{count}/{total}] merging {} sections", sections.len());
+    }
 
-            line_count += 1;
+    let total_len = match &lazy {
+        Some(lazy) => lazy.data_len(),
+        None => sections.iter().map(|s| s.section.len() as u64).sum::<u64>(),
+    };
+    let bar = StepProgress::new(Phase::Merging, total_len, style, &args);
+    let on_progress = |progress| bar.set_position(progress);
 
-            // My test file runs at 17 millions lines per second. Thats is about 270 thousands
-            // lines every 16ms, around ~2^18 = 4 * 2^16 = 0x4_0000.
-            // But I am running this on a SSD, so maybe it is not the best calibration for a HDD
-            // user (if the disk is the bottleneck, that is);
-            if line_count % 0x4_0000 == 0 {
-                on_progress(progress);
-            }
+    let output = args.output.as_ref().unwrap();
 
-            match &line {
-                [b'#', ..] => {
-                    let offset = line.as_ptr() as usize - section.section.as_ptr() as usize;
-                    let value = parse_u64(&line[1..]).unwrap() * section.vcd.timescale;
-                    *section = Section {
-                        value,
-                        section: &section.section[offset..],
-                        vcd: section.vcd,
-                    };
-                    *heap_entry = Reverse((value, index));
-
-                    continue 'sections;
-                }
-                [b'b', ..] | [b'r', ..] => {
-                    let pos = line.iter().position(|c| *c == b' ').unwrap();
-                    let (name, symbol) = line.split_at(pos + 1);
-                    let new_symbol = section
-                        .vcd
-                        .symbol_map
-                        .get(&IdCode::from(symbol))
-                        .unwrap_or_else(|| {
-                            panic!(
-                                "symbol not found: {:?}, {:?}",
-                                &IdCode::from(symbol),
-                                section.vcd.symbol_map
-                            )
-                        });
-
-                    out_writer.write_all(name)?;
-                    out_writer.write_all(new_symbol.as_bytes())?;
-                    out_writer.write_all(b"\n")?;
-                }
-                [b'$', ..] => {
-                    // println!("skipping {}", std::str::from_utf8(line).unwrap());
-                }
-                [] => {
-                    // println!("empty line");
-                }
-                _ => {
-                    let value = &line[0..1];
-                    let symbol = &line[1..];
-                    let new_symbol = section.vcd.symbol_map.get(&IdCode::from(symbol)).unwrap();
-
-                    out_writer.write_all(value)?;
-                    out_writer.write_all(new_symbol.as_bytes())?;
-                    out_writer.write_all(b"\n")?;
-                }
-            }
+    let result = match &checkpoint {
+        Some(checkpoint) => {
+            resume_output(output, &vcds, sections, checkpoint, &options, on_progress)
         }
+        None => match lazy {
+            Some(lazy) => write_output(output, headers, &vcds, lazy, &options, on_progress),
+            None => write_output(output, headers, &vcds, sections, &options, on_progress),
+        },
+    };
+    if let Err(err) = result {
+        exit_with(err);
+    }
+    // the merge is complete, so the checkpoint it was resumed from can't be resumed again.
+    if let Some(path) = &args.resume {
+        let _ = std::fs::remove_file(path);
+    }
 
-        // All lines in this section has been written
-        PeekMut::pop(heap_entry);
+    bar.finish();
+
+    if let Some(split_size) = args.split_size {
+        count += 1;
+        progress!("[{count}/{total}] splitting in chunks");
+        split_output_file(output, split_size.get());
     }
 
-    Ok(())
+    if let Some(reference) = &args.compare {
+        match compare(output, reference) {
+            Ok(None) => println!("output is equivalent to {}", reference.display()),
+            Ok(Some(difference)) => {
+                println!(
+                    "output differs from {}: {}",
+                    reference.display(),
+                    difference
+                );
+                std::process::exit(1);
+            }
+            Err(err) => {
+                eprintln!("Could not compare with {}: {}", reference.display(), err);
+                std::process::exit(1);
+            }
+        }
+    }
 }
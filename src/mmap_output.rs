@@ -0,0 +1,79 @@
+//! Writing the output through a memory map of the output file.
+
+use memmap2::MmapMut;
+use std::{fs::File, io::Write};
+
+/// A writer that copies the data into a memory map of a pre-allocated file.
+///
+/// The file is allocated with a estimated size, and grown if the estimate is too small. When
+/// finished, the file is truncated to the length actually written.
+pub(crate) struct MmapWriter {
+    file: File,
+    map: MmapMut,
+    len: usize,
+}
+impl MmapWriter {
+    /// Allocate `file` to `capacity` bytes and map it. `file` must be open for reading and
+    /// writing.
+    pub(crate) fn new(file: File, capacity: u64) -> std::io::Result<Self> {
+        // a empty map is not allowed.
+        let capacity = capacity.max(1);
+        file.set_len(capacity)?;
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self { file, map, len: 0 })
+    }
+
+    /// Make room for at least `additional` more bytes, doubling the size of the file.
+    fn grow(&mut self, additional: usize) -> std::io::Result<()> {
+        let capacity = (self.map.len() * 2).max(self.len + additional);
+        self.map.flush()?;
+        self.file.set_len(capacity as u64)?;
+        self.map = unsafe { MmapMut::map_mut(&self.file)? };
+        Ok(())
+    }
+
+    /// Flush the map and truncate the file to the written length.
+    pub(crate) fn finish(self) -> std::io::Result<()> {
+        self.map.flush()?;
+        drop(self.map);
+        self.file.set_len(self.len as u64)
+    }
+}
+impl Write for MmapWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.len + buf.len() > self.map.len() {
+            self.grow(buf.len())?;
+        }
+        self.map[self.len..self.len + buf.len()].copy_from_slice(buf);
+        self.len += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.map.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grow_past_estimate() {
+        let file = assert_fs::NamedTempFile::new("out.vcd").unwrap();
+        let out = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(file.path())
+            .unwrap();
+
+        let mut writer = MmapWriter::new(out, 4).unwrap();
+        writer.write_all(b"#0\n1!\n").unwrap();
+        writer.write_all(b"#10\n0!\n").unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(std::fs::read(file.path()).unwrap(), b"#0\n1!\n#10\n0!\n");
+    }
+}
@@ -0,0 +1,91 @@
+//! Planning the merge without borrowing the inputs, to distribute it to other processes.
+
+use crate::{find_sections, parse_time, Section, TimeError, Vcd};
+use std::{
+    io::{BufWriter, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// The location and time range of a [`Section`](crate::Section), found by [`plan_sections`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionInfo {
+    /// The path of the input that contains the section.
+    pub path: PathBuf,
+    /// The index of the input, in the order they were parsed.
+    pub input: usize,
+    /// The byte range of the section in the input, starting at its first timestamp.
+    pub range: Range<usize>,
+    /// The first and last timestamps of the section, in the output timescale.
+    pub time_range: (u64, u64),
+}
+
+/// Find the sections of `vcds`, like [`find_sections`], but describe them by their byte ranges
+/// instead of borrowing the inputs, without writing the merge.
+pub fn plan_sections(vcds: &[Vcd]) -> Result<Vec<SectionInfo>, TimeError> {
+    find_sections(vcds, 1, |_| {})?
+        .into_iter()
+        .map(|section| {
+            let input = vcds
+                .iter()
+                .position(|vcd| std::ptr::eq(vcd, section.vcd))
+                .unwrap();
+            let (_, last) = timestamps(&section);
+            let last = match last {
+                Some(time) => section.vcd.time(time)?,
+                None => None,
+            };
+
+            Ok(SectionInfo {
+                path: section.vcd.path().to_path_buf(),
+                input,
+                range: byte_range(&section),
+                time_range: (section.value, last.unwrap_or(section.value)),
+            })
+        })
+        .collect()
+}
+
+/// Write each of `sections` to `output` as TSV, with the columns `path`, `start` and `end` of its
+/// byte range in the input, its start `value` in the output timescale, and the `first` and `last`
+/// timestamps in it, as written in the input. These are empty if the section has no timestamps.
+pub fn write_sections_tsv(output: impl AsRef<Path>, sections: &[Section]) -> std::io::Result<()> {
+    let mut out = BufWriter::new(std::fs::File::create(output)?);
+
+    let or_empty = |time: Option<u64>| time.map_or(String::new(), |x| x.to_string());
+
+    writeln!(out, "path\tstart\tend\tvalue\tfirst\tlast")?;
+    for section in sections {
+        let range = byte_range(section);
+        let (first, last) = timestamps(section);
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            section.vcd.path().display(),
+            range.start,
+            range.end,
+            section.value,
+            or_empty(first),
+            or_empty(last),
+        )?;
+    }
+
+    out.flush()
+}
+
+/// The byte range of `section` in its input.
+fn byte_range(section: &Section) -> Range<usize> {
+    let start = section.section.as_ptr() as usize - section.vcd.file.as_ptr() as usize;
+    start..start + section.section.len()
+}
+
+/// The first and last timestamps of `section`, as written in its input. The timestamps of a
+/// section are sorted, so these are its smallest and largest ones.
+fn timestamps(section: &Section) -> (Option<u64>, Option<u64>) {
+    let time = |line: &[u8]| match line {
+        [b'#', time @ ..] => parse_time(time.trim_ascii_end()).ok(),
+        _ => None,
+    };
+    let lines = || section.section.split(|&b| b == b'\n');
+    (lines().find_map(time), lines().rev().find_map(time))
+}
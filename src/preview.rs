@@ -0,0 +1,70 @@
+//! Writing only the first lines of the merge, to check it without writing the whole output.
+
+use crate::{merge_sections, write_header, CountingWriter, Header, Section, Vcd, WriteOptions};
+use std::io::Write;
+
+/// The error returned by [`LineLimit`] after it wrote all its lines.
+#[derive(Debug)]
+struct PreviewFull;
+impl std::fmt::Display for PreviewFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the preview is full")
+    }
+}
+impl std::error::Error for PreviewFull {}
+
+/// A writer that passes the first `lines` lines to `inner`, and fails with [`PreviewFull`] after
+/// them, stopping the merge.
+struct LineLimit<W> {
+    inner: W,
+    lines: usize,
+}
+impl<W: Write> Write for LineLimit<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.lines == 0 {
+            return Err(std::io::Error::other(PreviewFull));
+        }
+        // write at most one line at a time, to count them.
+        let len = buf
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(buf.len(), |x| x + 1);
+        let n = self.inner.write(&buf[..len])?;
+        if buf[..n].last() == Some(&b'\n') {
+            self.lines -= 1;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Write the first `lines` lines of the merged VCD to `out`, going through the same steps as
+/// [`write_output`](crate::write_output), and stop the merge after them.
+pub fn write_preview<'a>(
+    out: impl Write,
+    headers: Header,
+    vcds: &'a [Vcd],
+    sections: Vec<Section<'a>>,
+    options: &WriteOptions,
+    lines: usize,
+) -> std::io::Result<()> {
+    let mut out_writer = CountingWriter::new(LineLimit { inner: out, lines }, 0);
+    let result = write_header(&mut out_writer, headers, vcds, options).and_then(|()| {
+        merge_sections(
+            &mut out_writer,
+            vcds,
+            sections.into(),
+            None,
+            options,
+            |_| {},
+        )
+    });
+    match result {
+        Err(err) if err.get_ref().is_some_and(|err| err.is::<PreviewFull>()) => {}
+        result => result?,
+    }
+    out_writer.flush()
+}
@@ -0,0 +1,107 @@
+//! Merging files in a single call, reporting the progress of all steps to a single callback.
+
+use crate::{
+    find_sections, parse_headers, write_output, Error, Header, ParseOptions, WriteOptions,
+};
+use std::{path::Path, sync::Mutex};
+
+/// The step of the merge that a [`Progress`] refers to, in the order they happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// Parsing the headers of the inputs, see [`parse_headers`].
+    Gathering,
+    /// Splitting the data of the inputs in sections, see [`find_sections`].
+    Finding,
+    /// Merging the sections into the output, see [`write_output`].
+    Merging,
+}
+
+/// The progress of a [`merge`]: `bytes` of the `total` bytes of the current phase are processed.
+///
+/// The total of [`Phase::Gathering`] is the size of the inputs, of [`Phase::Finding`] the size of
+/// their mapped files, and of [`Phase::Merging`] the size of the sections being merged. Each phase
+/// ends with a report where `bytes` is equal to `total`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: Phase,
+    pub bytes: u64,
+    pub total: u64,
+}
+impl Progress {
+    /// The progress of the current phase, from 0.0 to 1.0. A empty phase is complete.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.bytes as f64 / self.total as f64
+    }
+}
+
+/// Merge `inputs` into `output`, doing all the steps of the merge with `threads` threads for
+/// finding sections, and reporting the progress of each one to `on_progress`.
+pub fn merge(
+    inputs: &[impl AsRef<Path>],
+    output: impl AsRef<Path>,
+    parse_options: &ParseOptions,
+    write_options: &WriteOptions,
+    threads: usize,
+    mut on_progress: impl FnMut(Progress) + Send,
+) -> Result<(), Error> {
+    let sizes = inputs
+        .iter()
+        .map(|input| std::fs::metadata(input).map_or(0, |m| m.len()))
+        .collect::<Vec<_>>();
+    let total = sizes.iter().sum();
+
+    let mut header = Header::default();
+    let vcds = {
+        let mut bytes = 0;
+        let inputs = inputs.iter().zip(&sizes).map(|(input, &size)| {
+            on_progress(Progress {
+                phase: Phase::Gathering,
+                bytes,
+                total,
+            });
+            bytes += size;
+            input
+        });
+        parse_headers(inputs, &mut header, parse_options)?
+    };
+    on_progress(Progress {
+        phase: Phase::Gathering,
+        bytes: total,
+        total,
+    });
+
+    let total = vcds.iter().map(|vcd| vcd.file().len() as u64).sum();
+    let on_progress = Mutex::new(on_progress);
+    let sections = find_sections(&vcds, threads, |bytes| {
+        (on_progress.lock().unwrap())(Progress {
+            phase: Phase::Finding,
+            bytes,
+            total,
+        })
+    })?;
+    let mut on_progress = on_progress.into_inner().unwrap();
+    on_progress(Progress {
+        phase: Phase::Finding,
+        bytes: total,
+        total,
+    });
+
+    let total = sections.iter().map(|s| s.section.len() as u64).sum();
+    write_output(output, header, &vcds, sections, write_options, |bytes| {
+        on_progress(Progress {
+            phase: Phase::Merging,
+            bytes,
+            total,
+        })
+    })?;
+    on_progress(Progress {
+        phase: Phase::Merging,
+        bytes: total,
+        total,
+    });
+
+    Ok(())
+}
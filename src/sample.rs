@@ -0,0 +1,85 @@
+//! Sampling the value of signals at any time, without writing the output.
+
+use crate::{msb_first, split_value_change, Declaration, IdCode, Section, TimeError, Vcd};
+use fxhash::FxHashMap as HashMap;
+use std::borrow::Cow;
+
+/// The time of a value change, in the output timescale, and its value.
+type Change<'a> = (u64, Cow<'a, [u8]>);
+
+/// The value changes of each output signal in a set of sections, indexed by signal to sample
+/// their values at any time with [`Samples::sample`].
+pub struct Samples<'a> {
+    /// The output identifier of each signal, by hierarchical name.
+    ids: HashMap<&'a str, IdCode>,
+    /// The time, in the output timescale, and value of the changes of each signal, sorted by
+    /// time. Changes at the same time are in the order they are written to the output. Vectors of
+    /// inputs with [`BitOrder::Lsb`](crate::BitOrder::Lsb) are reversed, like in the output.
+    changes: HashMap<IdCode, Vec<Change<'a>>>,
+}
+impl<'a> Samples<'a> {
+    /// Index the value changes of `sections`, the same ones given to
+    /// [`write_output`](crate::write_output).
+    ///
+    /// Changes of a signal shared by inputs, like with
+    /// [`ParseOptions::append`](crate::ParseOptions::append), at the same time take the value of
+    /// the last input, as with [`Conflict::Last`](crate::Conflict::Last).
+    pub fn new(vcds: &'a [Vcd], sections: &[Section<'a>]) -> Result<Self, TimeError> {
+        let mut ids = HashMap::default();
+        for vcd in vcds {
+            for declaration in vcd.declarations.iter() {
+                if let Declaration::Var { path, new_id, .. } = declaration {
+                    ids.entry(path.as_str()).or_insert(*new_id);
+                }
+            }
+        }
+
+        let mut changes: HashMap<IdCode, Vec<Change<'a>>> = HashMap::default();
+        for section in sections {
+            let mut time = section.value;
+            let mut lines = section.section.split(|&b| b == b'\n');
+            // the first line is the timestamp of the section, if it starts with one.
+            if let [b'#', ..] = section.section {
+                lines.next();
+            }
+            for line in lines {
+                if let [b'#', ..] = line {
+                    match section.vcd.timestamp(&line[1..])? {
+                        Some(value) => time = value,
+                        // dropped timestamps always end a section
+                        None => break,
+                    }
+                    continue;
+                }
+                let Some((value, symbol)) = split_value_change(line) else {
+                    continue;
+                };
+                let id = IdCode::parse(symbol);
+                if let Some(new_id) = id.and_then(|id| section.vcd.symbol_map.get(&id)) {
+                    let value = msb_first(section.vcd, value);
+                    changes.entry(*new_id).or_default().push((time, value));
+                }
+            }
+        }
+
+        // the sort is stable, so changes at the same time keep the order of the sections.
+        for signal_changes in changes.values_mut() {
+            signal_changes.sort_by_key(|&(time, _)| time);
+        }
+
+        Ok(Samples { ids, changes })
+    }
+
+    /// The value of the signal with the hierarchical name `signal_path`, like `top.clk`, at
+    /// `at_time`, in the output timescale, after all its changes at that time. The value of
+    /// vectors and reals includes their `b` or `r` prefix, like `b101`.
+    ///
+    /// Returns `None` if there is no such signal, or if it doesn't change before `at_time`.
+    pub fn sample(&self, signal_path: &str, at_time: u64) -> Option<&str> {
+        let id = self.ids.get(signal_path)?;
+        let changes = self.changes.get(id)?;
+        let after = changes.partition_point(|(time, _)| *time <= at_time);
+        let (_, value) = changes.get(after.checked_sub(1)?)?;
+        std::str::from_utf8(value).ok()
+    }
+}
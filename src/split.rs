@@ -0,0 +1,171 @@
+//! Splitting of a merged file in chunks of about the same size, and the manifest of their time
+//! ranges.
+
+use crate::{json::write_str, parse_time, split_value_change};
+use fxhash::FxHashMap as HashMap;
+use std::{
+    collections::hash_map::Entry,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// A chunk written by [`split_output`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// The path the chunk was written to.
+    pub path: PathBuf,
+    /// The first timestamp of the chunk, in the timescale of the output.
+    pub start_time: u64,
+    /// The last timestamp of the chunk, in the timescale of the output.
+    pub end_time: u64,
+}
+
+/// The path of the chunk `index` of `output`, with the index before the extension, like
+/// `out.0.vcd` for `out.vcd`.
+pub fn chunk_path(output: &Path, index: usize) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match output.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, index),
+    };
+    output.with_file_name(name)
+}
+
+/// The path of the manifest of the chunks of `output`, like `out.manifest.json` for `out.vcd`.
+pub fn manifest_path(output: &Path) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    output.with_file_name(format!("{}.manifest.json", stem))
+}
+
+/// Split the merged VCD at `output` in chunks, written to [`chunk_path`], and return them in time
+/// order. `output` itself is left as it is.
+///
+/// A chunk ends before the first timestamp after `split_size` bytes of value changes, so each
+/// chunk is at least that large, except the last one. Every chunk is a complete VCD with the
+/// header of `output`, and the chunks after the first start with a `$dumpvars` of the last value
+/// of each signal before them.
+pub fn split_output(output: impl AsRef<Path>, split_size: u64) -> std::io::Result<Vec<Chunk>> {
+    let output = output.as_ref();
+    let file = std::fs::File::open(output)?;
+    let data = unsafe { memmap2::Mmap::map(&file)? };
+    let Some(header_len) = header_len(&data) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("no $enddefinitions in {}", output.display()),
+        ));
+    };
+    let (header, data) = data.split_at(header_len);
+
+    let create = |index| -> std::io::Result<_> {
+        let mut out = BufWriter::new(std::fs::File::create(chunk_path(output, index))?);
+        out.write_all(header)?;
+        Ok(out)
+    };
+
+    let mut chunks = Vec::new();
+    let mut out = create(0)?;
+    // the start of the data of the current chunk, including its first timestamp, the start of the
+    // data not written to it yet, and its first and last timestamps.
+    let mut start = 0;
+    let mut unwritten = 0;
+    let mut times = None;
+
+    // the last change of each signal, in the order the signals first changed.
+    let mut values: Vec<&[u8]> = Vec::new();
+    let mut value_index: HashMap<&[u8], usize> = HashMap::default();
+
+    let mut pos = 0;
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        let line_start = pos;
+        pos += line.len();
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+
+        if let [b'#', time @ ..] = line {
+            let time = parse_time(time).map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid timestamp {}", String::from_utf8_lossy(line)),
+                )
+            })?;
+            if let Some((start_time, end_time)) = times {
+                if (line_start - start) as u64 >= split_size {
+                    out.write_all(&data[unwritten..line_start])?;
+                    out.flush()?;
+                    chunks.push(Chunk {
+                        path: chunk_path(output, chunks.len()),
+                        start_time,
+                        end_time,
+                    });
+
+                    out = create(chunks.len())?;
+                    writeln!(out, "#{}", time)?;
+                    if !values.is_empty() {
+                        out.write_all(b"$dumpvars\n")?;
+                        for value in &values {
+                            out.write_all(value)?;
+                            out.write_all(b"\n")?;
+                        }
+                        out.write_all(b"$end\n")?;
+                    }
+                    start = line_start;
+                    unwritten = pos;
+                    times = None;
+                }
+            }
+            times = Some((times.map_or(time, |(start_time, _)| start_time), time));
+        } else if let Some((_, symbol)) = split_value_change(line) {
+            match value_index.entry(symbol) {
+                Entry::Occupied(entry) => values[*entry.get()] = line,
+                Entry::Vacant(entry) => {
+                    entry.insert(values.len());
+                    values.push(line);
+                }
+            }
+        }
+    }
+
+    out.write_all(&data[unwritten..])?;
+    out.flush()?;
+    let (start_time, end_time) = times.unwrap_or_default();
+    chunks.push(Chunk {
+        path: chunk_path(output, chunks.len()),
+        start_time,
+        end_time,
+    });
+
+    Ok(chunks)
+}
+
+/// The length of the header of a merged file, up to the line of its `$enddefinitions $end`.
+fn header_len(data: &[u8]) -> Option<usize> {
+    const END: &[u8] = b"$enddefinitions $end\n";
+    data.windows(END.len())
+        .position(|x| x == END)
+        .map(|pos| pos + END.len())
+}
+
+/// Write the `chunks` of a split output to `output` as JSON.
+///
+/// The manifest is a object with `chunks`, a list of objects with the `file` name of each chunk and
+/// its `time_range`, as `[start_time, end_time]` in the timescale of the output.
+pub fn write_split_manifest(output: impl AsRef<Path>, chunks: &[Chunk]) -> std::io::Result<()> {
+    let mut out = BufWriter::new(std::fs::File::create(output)?);
+
+    out.write_all(b"{\"chunks\":[")?;
+    for (i, chunk) in chunks.iter().enumerate() {
+        if i != 0 {
+            out.write_all(b",")?;
+        }
+        let file = chunk.path.file_name().unwrap_or_default();
+        out.write_all(b"{\"file\":")?;
+        write_str(&mut out, file.to_string_lossy().as_bytes())?;
+        write!(
+            out,
+            ",\"time_range\":[{},{}]}}",
+            chunk.start_time, chunk.end_time
+        )?;
+    }
+    out.write_all(b"]}\n")?;
+
+    out.flush()
+}
@@ -0,0 +1,59 @@
+//! Export and import of the identifier assigned to each signal, to keep them stable across runs.
+
+use crate::{Declaration, Vcd};
+use std::{
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// Write the hierarchical name and output identifier of each var declared in the output, in the
+/// order they are declared, to `output` as TSV with the columns `name` and `id`.
+///
+/// A name is listed once for each var with that name, so inputs that declare the same signal
+/// without merging it keep their own identifiers when the map is read by [`read_symbol_map`].
+pub fn write_symbol_map(output: impl AsRef<Path>, vcds: &[Vcd]) -> std::io::Result<()> {
+    let mut out = BufWriter::new(std::fs::File::create(output)?);
+
+    writeln!(out, "name\tid")?;
+    for vcd in vcds {
+        for declaration in vcd.declarations.iter() {
+            if let Declaration::Var { path, new_id, .. } = declaration {
+                out.write_all(path.as_bytes())?;
+                out.write_all(b"\t")?;
+                out.write_all(new_id.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+        }
+    }
+
+    out.flush()
+}
+
+/// Read a symbol map written by [`write_symbol_map`], as pairs of hierarchical names and
+/// identifiers, for [`ParseOptions::symbol_map`](crate::ParseOptions::symbol_map).
+pub fn read_symbol_map(path: impl AsRef<Path>) -> std::io::Result<Vec<(String, String)>> {
+    let invalid = |line: &str| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid symbol map line {:?}", line),
+        )
+    };
+
+    let mut lines = BufReader::new(std::fs::File::open(path)?).lines();
+    match lines.next().transpose()? {
+        Some(line) if line == "name\tid" => {}
+        line => return Err(invalid(line.as_deref().unwrap_or_default())),
+    }
+
+    let mut map = Vec::new();
+    for line in lines {
+        let line = line?;
+        let (name, id) = line.rsplit_once('\t').ok_or_else(|| invalid(&line))?;
+        let valid_id = (1..=4).contains(&id.len()) && id.bytes().all(|b| b.is_ascii_graphic());
+        if name.is_empty() || !valid_id {
+            return Err(invalid(&line));
+        }
+        map.push((name.to_string(), id.to_string()));
+    }
+    Ok(map)
+}
@@ -0,0 +1,162 @@
+//! Checking a VCD file for problems, without merging it.
+
+use crate::{
+    parse_header_data, parse_time, split_value_change, Declaration, Declarations, Header,
+    HeaderError, IdCode, InputData, ParseOptions,
+};
+use fxhash::FxHashSet as HashSet;
+use std::path::Path;
+
+/// A problem found by [`validate`]. Lines are numbered from 1.
+#[derive(Debug)]
+pub enum Problem {
+    /// The header could not be parsed, so the rest of the file is not checked.
+    Header(HeaderError),
+    /// A `$timescale` that could not be parsed.
+    InvalidTimescale(String),
+    /// A `$upscope` without a `$scope` to close.
+    UnmatchedUpscope,
+    /// Scopes that are still open at the end of the header.
+    UnclosedScopes(usize),
+    /// A timestamp that is not a number.
+    InvalidTimestamp { line: usize, text: String },
+    /// A timestamp smaller than the one before it.
+    Unsorted { line: usize, time: u64, last: u64 },
+    /// A value change without a value or identifier.
+    MalformedChange { line: usize, text: String },
+    /// Value changes among the declarations, which are skipped. Consecutive value changes are
+    /// reported once, at the line of the first.
+    StrayChange { line: usize },
+    /// Tokens after the `$end` of a directive in the same line, which are skipped.
+    TrailingTokens { line: usize },
+    /// A value change of a identifier that is not declared. Only its first use is reported.
+    UndeclaredIdentifier { line: usize, id: String },
+}
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Header(err) => write!(f, "invalid header: {}", err),
+            Problem::InvalidTimescale(scale) => write!(f, "invalid timescale {:?}", scale),
+            Problem::UnmatchedUpscope => write!(f, "$upscope without a matching $scope"),
+            Problem::UnclosedScopes(count) => {
+                write!(f, "{} $scope without a matching $upscope", count)
+            }
+            Problem::InvalidTimestamp { line, text } => {
+                write!(f, "line {}: invalid timestamp {:?}", line, text)
+            }
+            Problem::Unsorted { line, time, last } => {
+                write!(f, "line {}: timestamp {} is before {}", line, time, last)
+            }
+            Problem::MalformedChange { line, text } => {
+                write!(f, "line {}: malformed value change {:?}", line, text)
+            }
+            Problem::StrayChange { line } => {
+                write!(f, "line {}: value change among the declarations", line)
+            }
+            Problem::TrailingTokens { line } => write!(f, "line {}: tokens after $end", line),
+            Problem::UndeclaredIdentifier { line, id } => {
+                write!(f, "line {}: identifier {:?} is not declared", line, id)
+            }
+        }
+    }
+}
+
+/// Check the VCD file at `path` for problems: a header that can't be parsed, unbalanced scopes,
+/// an invalid timescale, and timestamps or value changes that are invalid, out of order, or of
+/// undeclared identifiers. Returns all problems found, in the order they are in the file.
+pub fn validate(path: impl AsRef<Path>) -> std::io::Result<Vec<Problem>> {
+    let file = std::fs::File::open(path)?;
+    let data = InputData::Mmap(unsafe { memmap2::Mmap::map(&file)? }).decompressed()?;
+
+    // the identifiers are only read, so the ones generated by a merge don't depend on them.
+    let declarations = Declarations {
+        keep_ids: true,
+        read_only: true,
+        ..Default::default()
+    };
+    let parsed = match parse_header_data(
+        &data,
+        &mut Header::default(),
+        &ParseOptions::default(),
+        declarations,
+    ) {
+        Ok(x) => x,
+        Err(err) => return Ok(vec![Problem::Header(err)]),
+    };
+
+    let mut problems = Vec::new();
+    if let Some(scale) = parsed.invalid_timescale {
+        problems.push(Problem::InvalidTimescale(scale.trim().to_string()));
+    }
+
+    let line_of = |pos: usize| data[..pos].iter().filter(|&&b| b == b'\n').count() + 1;
+    for &pos in &parsed.stray_changes {
+        problems.push(Problem::StrayChange { line: line_of(pos) });
+    }
+    for &pos in &parsed.trailing_tokens {
+        problems.push(Problem::TrailingTokens { line: line_of(pos) });
+    }
+
+    let mut depth = 0usize;
+    for declaration in parsed.declarations.list.iter() {
+        match declaration {
+            Declaration::Scope { .. } => depth += 1,
+            Declaration::Var { .. } => {}
+            Declaration::Upscope => match depth.checked_sub(1) {
+                Some(x) => depth = x,
+                None => problems.push(Problem::UnmatchedUpscope),
+            },
+        }
+    }
+    if depth > 0 {
+        problems.push(Problem::UnclosedScopes(depth));
+    }
+
+    let declared = &parsed.declarations.symbol_map;
+    let mut undeclared = HashSet::default();
+    let mut last = None;
+    let mut in_comment = false;
+
+    let first_line = line_of(parsed.end_of_definitions);
+    let lines = data[parsed.end_of_definitions..].split(|&b| b == b'\n');
+    for (line, text) in (first_line..).zip(lines) {
+        let text = text.trim_ascii();
+        if in_comment || text.starts_with(b"$comment") {
+            in_comment = !text.windows(4).any(|x| x == b"$end");
+            continue;
+        }
+        let to_string = |x: &[u8]| String::from_utf8_lossy(x).into_owned();
+        match text {
+            [] | [b'$', ..] => {}
+            [b'#', time @ ..] => match parse_time(time) {
+                Ok(time) => {
+                    if let Some(last) = last.filter(|&last| time < last) {
+                        problems.push(Problem::Unsorted { line, time, last });
+                    }
+                    last = Some(time);
+                }
+                Err(()) => problems.push(Problem::InvalidTimestamp {
+                    line,
+                    text: to_string(text),
+                }),
+            },
+            _ => match split_value_change(text) {
+                Some((_, id)) => {
+                    let is_declared = id.len() <= 4 && declared.contains_key(&IdCode::from(id));
+                    if !is_declared && undeclared.insert(id) {
+                        problems.push(Problem::UndeclaredIdentifier {
+                            line,
+                            id: to_string(id),
+                        });
+                    }
+                }
+                None => problems.push(Problem::MalformedChange {
+                    line,
+                    text: to_string(text),
+                }),
+            },
+        }
+    }
+
+    Ok(problems)
+}
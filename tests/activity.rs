@@ -0,0 +1,27 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn activity() -> Result<(), Box<dyn std::error::Error>> {
+    let activity = assert_fs::NamedTempFile::new("activity.csv")?;
+
+    merge(&[
+        "tests/unused.vcd",
+        "tests/same_start2.vcd",
+        "--activity",
+        activity.path().to_str().unwrap(),
+    ])?;
+
+    // the changes go from 0ns to 10ns, so 1 change is 100 changes per microsecond.
+    let csv = std::fs::read_to_string(activity.path())?;
+    assert_eq!(
+        csv,
+        "name,changes,changes_per_us\n\
+         top.clk,3,300\n\
+         b.clk,2,200\n\
+         top.data,2,200\n"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use common::{merge, vcd_merger};
+
+#[test]
+fn append() -> Result<(), Box<dyn std::error::Error>> {
+    let existing = assert_fs::NamedTempFile::new("existing.vcd")?;
+
+    vcd_merger()
+        .arg("tests/test1.vcd")
+        .arg("-o")
+        .arg(existing.path())
+        .arg("--reorder")
+        .assert()
+        .success();
+
+    let existing = existing.path().to_str().unwrap();
+    let content = merge(&["--append", existing, "tests/test2.vcd", "--reorder"])?;
+
+    // Same as merging both files at once.
+    assert_eq!(content, std::fs::read_to_string("tests/expected.vcd")?);
+
+    Ok(())
+}
+
+#[test]
+fn append_shard() -> Result<(), Box<dyn std::error::Error>> {
+    let existing = assert_fs::NamedTempFile::new("existing.vcd")?;
+
+    vcd_merger()
+        .arg("tests/same_start1.vcd")
+        .arg("tests/same_start2.vcd")
+        .arg("-o")
+        .arg(existing.path())
+        .assert()
+        .success();
+
+    let existing = existing.path().to_str().unwrap();
+    let content = merge(&["--append", existing, "tests/append_shard.vcd"])?;
+
+    // `a.clk` keeps its identifier, and the new `a.rst` gets a new one.
+    let expected = "\
+$timescale 1ns $end
+$scope module a $end
+$var wire 1 ! clk $end
+$upscope $end
+$scope module b $end
+$var wire 1 \" clk $end
+$upscope $end
+$scope module a $end
+$var wire 1 # rst $end
+$upscope $end
+$enddefinitions $end
+#5
+1!
+0\"
+#7
+0!
+1\"
+#9
+1!
+1#
+#11
+0!
+0#
+";
+    assert_eq!(content, expected);
+
+    Ok(())
+}
+
+#[test]
+fn append_different_identifiers() -> Result<(), Box<dyn std::error::Error>> {
+    // `tests/recoded.vcd` uses `!` for `a.rst` and `%` for `a.clk`, while the other inputs use `!`
+    // for `a.clk` and `"` for `a.rst`. Signals are unified by name, not by identifier.
+    let content = merge(&[
+        "--append",
+        "tests/same_start1.vcd",
+        "tests/append_shard.vcd",
+        "tests/recoded.vcd",
+    ])?;
+    let (header, data) = content.split_once("$enddefinitions $end\n").unwrap();
+    assert_eq!(header.matches("$var").count(), 2);
+    assert!(header.contains("$var wire 1 ! clk $end\n"));
+    assert!(header.contains("$var wire 1 \" rst $end\n"));
+    assert_eq!(
+        data,
+        "#5\n1!\n#7\n0!\n#9\n1!\n1\"\n#10\n0\"\n0!\n#11\n0!\n0\"\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn append_wide_ids() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let existing = dir.child("existing.vcd");
+    existing.write_str(
+        "$timescale 1ns $end\n\
+         $scope module kept $end\n\
+         $var wire 1 ! a $end\n\
+         $var wire 1 a~ b $end\n\
+         $var wire 1 #!!! c $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n\
+         1!\n\
+         1a~\n\
+         1#!!!\n",
+    )?;
+
+    // the kept identifiers are 1 to 4 characters long, and the inputs need new ones.
+    let content = merge(&[
+        "--append",
+        existing.path().to_str().unwrap(),
+        "tests/nested.vcd",
+        "tests/test2.vcd",
+        "--reorder",
+    ])?;
+    let ids = content
+        .lines()
+        .filter_map(|line| line.strip_prefix("$var "))
+        .map(|var| var.split_whitespace().nth(2).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(&ids[..3], ["!", "a~", "#!!!"]);
+
+    // test2.vcd declares foo and same_foo with the same identifier.
+    assert_eq!(ids.len(), 3 + 3 + 3);
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), ids.len() - 1, "{:?}", ids);
+    assert!(ids[3..].iter().all(|id| id.len() == 4));
+
+    Ok(())
+}
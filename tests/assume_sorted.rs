@@ -0,0 +1,26 @@
+mod common;
+
+use common::run;
+
+fn merge(args: &[&str]) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let inputs = ["tests/two_scopes.vcd", "tests/msb_bus.vcd"];
+    let (assert, content) = run(&[&inputs[..], args].concat())?;
+    let stdout = String::from_utf8(assert.success().get_output().stdout.clone())?;
+    Ok((content, stdout))
+}
+
+#[test]
+fn assume_sorted() -> Result<(), Box<dyn std::error::Error>> {
+    let (reordered, stdout) = merge(&["--reorder"])?;
+    assert!(stdout.contains("finding sections"));
+
+    // `--assume-sorted` skips finding sections, even with `--reorder`.
+    let (sorted, stdout) = merge(&["--reorder", "--assume-sorted"])?;
+    assert!(!stdout.contains("finding sections"));
+
+    // the inputs are sorted, so the output is the same.
+    assert_eq!(sorted, reordered);
+    assert_eq!(merge(&[])?.0, reordered);
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use common::vcd_merger;
+use predicates::prelude::*;
+
+#[test]
+fn failed_merge_keeps_output() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let output = dir.child("out.vcd");
+    output.write_str("previous output\n")?;
+
+    // the conflict at #5 makes the merge fail after writing the header.
+    vcd_merger()
+        .arg("--append")
+        .arg("tests/same_start1.vcd")
+        .arg("tests/conflict.vcd")
+        .arg("-o")
+        .arg(output.path())
+        .arg("--conflict")
+        .arg("error")
+        .assert()
+        .code(6);
+
+    output.assert("previous output\n");
+    dir.child("out.vcd.partial")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn successful_merge_replaces_output() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let output = dir.child("out.vcd");
+    output.write_str("previous output\n")?;
+
+    vcd_merger()
+        .arg("tests/test1.vcd")
+        .arg("tests/test2.vcd")
+        .arg("-o")
+        .arg(output.path())
+        .arg("--reorder")
+        .assert()
+        .success();
+
+    output.assert(predicate::path::eq_file("tests/expected.vcd"));
+    dir.child("out.vcd.partial")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
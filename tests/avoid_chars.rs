@@ -0,0 +1,42 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{data, merge};
+
+#[test]
+fn avoid_chars() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // enough signals to use every identifier character, and some with two characters.
+    let mut input = String::from("$timescale 1 ns $end\n$scope module top $end\n");
+    for i in 0..300 {
+        input.push_str(&format!("$var wire 1 s{} sig{} $end\n", i, i));
+    }
+    input.push_str("$upscope $end\n$enddefinitions $end\n#0\n");
+    for i in 0..300 {
+        input.push_str(&format!("1s{}\n", i));
+    }
+    let input_file = dir.child("in.vcd");
+    input_file.write_str(&input)?;
+
+    let content = merge(&[input_file.path().to_str().unwrap(), "--avoid-chars", "~\\"])?;
+    let ids = content
+        .lines()
+        .filter(|line| line.starts_with("$var"))
+        .map(|line| line.split(' ').nth(3).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids.len(), 300);
+    for id in &ids {
+        assert!(!id.contains(['~', '\\']), "generated identifier {}", id);
+    }
+
+    // the value changes use the same identifiers.
+    let changed = data(&content)
+        .lines()
+        .skip(1)
+        .map(|line| &line[1..])
+        .collect::<Vec<_>>();
+    assert_eq!(changed, ids);
+
+    Ok(())
+}
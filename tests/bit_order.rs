@@ -0,0 +1,42 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{merge_data, run, vcd_merger};
+use predicates::prelude::*;
+
+#[test]
+fn bit_order() -> Result<(), Box<dyn std::error::Error>> {
+    let existing = assert_fs::NamedTempFile::new("existing.vcd")?;
+
+    vcd_merger()
+        .arg("tests/msb_bus.vcd")
+        .arg("-o")
+        .arg(existing.path())
+        .assert()
+        .success();
+
+    // the same signals, continued by a tool that writes vectors LSB first.
+    let data = merge_data(&[
+        "--append",
+        existing.path().to_str().unwrap(),
+        "tests/lsb_bus.vcd",
+        "--bit-order",
+        "tests/lsb_bus.vcd:lsb",
+    ])?;
+    assert_eq!(
+        data,
+        "#0\nb0011 !\n0\"\n#2\nb1000 !\n1\"\n#4\nb0011 !\n0\"\n#6\nb1000 !\n1\"\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bit_order_unknown_input() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/msb_bus.vcd", "--bit-order", "tests/lsb_bus.vcd:lsb"])?;
+    assert.code(1).stderr(predicate::str::contains(
+        "--bit-order: tests/lsb_bus.vcd is not an input",
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn bom() -> Result<(), Box<dyn std::error::Error>> {
+    // `bom.vcd` is `same_start1.vcd` prefixed with a UTF-8 byte order mark.
+    let with_bom = merge(&["tests/bom.vcd", "tests/same_start2.vcd"])?;
+    let without_bom = merge(&["tests/same_start1.vcd", "tests/same_start2.vcd"])?;
+
+    assert_eq!(with_bom, without_bom);
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{merge, run};
+use predicates::prelude::*;
+
+#[test]
+fn canonicalize() -> Result<(), Box<dyn std::error::Error>> {
+    // `tests/messy.vcd` has comments, extra whitespace, a tab separated vector change and
+    // unsorted timestamps.
+    let content = merge(&["tests/messy.vcd", "--canonicalize"])?;
+    assert_eq!(
+        content,
+        std::fs::read_to_string("tests/messy_canonical.vcd")?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn canonicalize_many_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/test1.vcd", "tests/test2.vcd", "--canonicalize"])?;
+    assert.code(1).stderr(predicate::str::contains(
+        "--canonicalize takes a single input, but 2 were given",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn canonicalize_dollar_words() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("in.vcd")?;
+    input.write_str(
+        "\
+$date $today $end
+$version tool $v1 $end
+$comment cost in $USD $end
+$timescale 1ns $end
+$var wire 1 ! clk $end
+$enddefinitions $end
+#0
+0!
+",
+    )?;
+
+    // words starting with `$` that are not keywords are part of the text of the directives.
+    let content = merge(&[input.path().to_str().unwrap(), "--canonicalize"])?;
+    assert!(content.starts_with("$date $today $end\n$version tool $v1 $end\n"));
+
+    Ok(())
+}
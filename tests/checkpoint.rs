@@ -0,0 +1,97 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use common::vcd_merger;
+use predicates::prelude::*;
+use std::{io::Write, process::Command};
+
+#[test]
+fn checkpoint_resume() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let output = dir.child("out.vcd");
+    let checkpoint = dir.child("out.vcd.checkpoint");
+
+    let merge = || -> Result<Command, Box<dyn std::error::Error>> {
+        let mut cmd = vcd_merger();
+        cmd.arg("tests/test1.vcd")
+            .arg("tests/test2.vcd")
+            .arg("-o")
+            .arg(output.path())
+            .arg("--reorder");
+        Ok(cmd)
+    };
+
+    merge()?
+        .arg("--checkpoint-interval")
+        .arg("4")
+        .assert()
+        .success();
+    output.assert(predicate::path::eq_file("tests/expected.vcd"));
+    // the merge is complete, so there is nothing to resume.
+    checkpoint.assert(predicate::path::missing());
+
+    // a directory at the output makes the merge fail at the end, leaving its checkpoint.
+    std::fs::remove_file(output.path())?;
+    output.child("file").touch()?;
+    merge()?
+        .arg("--checkpoint-interval")
+        .arg("4")
+        .assert()
+        .code(6);
+    checkpoint.assert(predicate::path::exists());
+    std::fs::remove_dir_all(output.path())?;
+
+    // simulate a crash after the checkpoint, that left a partial line in the unfinished output.
+    let partial = dir.child("out.vcd.partial");
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(partial.path())?;
+    file.write_all(b"#99\nb10")?;
+    drop(file);
+
+    merge()?
+        .arg("--resume")
+        .arg(checkpoint.path())
+        .assert()
+        .success();
+    output.assert(predicate::path::eq_file("tests/expected.vcd"));
+    partial.assert(predicate::path::missing());
+    checkpoint.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn checkpoint_changed_input() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let output = dir.child("out.vcd");
+
+    // a directory at the output makes the merge fail at the end, leaving its checkpoint.
+    output.child("file").touch()?;
+    vcd_merger()
+        .arg("tests/test1.vcd")
+        .arg("-o")
+        .arg(output.path())
+        .arg("--reorder")
+        .arg("--checkpoint-interval")
+        .arg("2")
+        .assert()
+        .code(6);
+    std::fs::remove_dir_all(output.path())?;
+
+    vcd_merger()
+        .arg("tests/test2.vcd")
+        .arg("-o")
+        .arg(output.path())
+        .arg("--reorder")
+        .arg("--resume")
+        .arg(dir.child("out.vcd.checkpoint").path())
+        .assert()
+        .code(7)
+        .stderr(predicate::str::contains(
+            "the inputs changed since the checkpoint",
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use assert_cmd::{assert::Assert, prelude::*};
+use std::{ffi::OsStr, process::Command};
+
+/// The `vcd-merger` binary, to run it with arguments other than a merge to a temporary output.
+pub fn vcd_merger() -> Command {
+    Command::cargo_bin("vcd-merger").unwrap()
+}
+
+/// Run a merge with `args`, which include the inputs, to a temporary output, and return its
+/// result and the content of the output, which is empty if it was not written.
+pub fn run(args: &[impl AsRef<OsStr>]) -> Result<(Assert, String), Box<dyn std::error::Error>> {
+    let output = assert_fs::NamedTempFile::new("out.vcd")?;
+
+    let assert = vcd_merger()
+        .args(args)
+        .arg("-o")
+        .arg(output.path())
+        .assert();
+
+    Ok((
+        assert,
+        std::fs::read_to_string(output.path()).unwrap_or_default(),
+    ))
+}
+
+/// Like [`run`], but fail the test if the merge doesn't succeed, and only return the content of
+/// the output.
+pub fn merge(args: &[impl AsRef<OsStr>]) -> Result<String, Box<dyn std::error::Error>> {
+    let (assert, content) = run(args)?;
+    assert.success();
+    Ok(content)
+}
+
+/// Like [`merge`], but only return the value changes of the output, after its
+/// `$enddefinitions $end`.
+pub fn merge_data(args: &[impl AsRef<OsStr>]) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(data(&merge(args)?).to_string())
+}
+
+/// The value changes of the output `content`, after its `$enddefinitions $end`.
+pub fn data(content: &str) -> &str {
+    content.split_once("$enddefinitions $end\n").unwrap().1
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::run;
+use predicates::prelude::*;
+use vcd_merger::{compare, identifiers_used, Difference};
+
+#[test]
+fn compare_equivalent() -> Result<(), Box<dyn std::error::Error>> {
+    // Same changes as `expected.vcd`, but with different identifiers and timescale.
+    let (assert, _) = run(&[
+        "tests/test1.vcd",
+        "tests/test2.vcd",
+        "--reorder",
+        "--compare",
+        "tests/expected_renumbered.vcd",
+    ])?;
+    assert
+        .success()
+        .stdout(predicate::str::contains("output is equivalent"));
+
+    Ok(())
+}
+
+#[test]
+fn compare_different() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&[
+        "tests/test1.vcd",
+        "--reorder",
+        "--compare",
+        "tests/expected.vcd",
+    ])?;
+    assert.code(1).stdout(predicate::str::contains(
+        "signal test2.clk is only declared in the reference",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn compare_values() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let header = "$timescale 1 ns $end\n$scope module top $end\n$var wire 1 ! clk $end\n\
+                  $upscope $end\n$enddefinitions $end\n";
+    let write = |name: &str, data: &str| -> std::io::Result<std::path::PathBuf> {
+        let path = dir.path().join(name);
+        std::fs::write(&path, format!("{}{}", header, data))?;
+        Ok(path)
+    };
+    let output = write("output.vcd", "#0\n0!\n#5\n#5\n1!\n#10\n0!\n")?;
+    let same = write("same.vcd", "#0\n0!\n#3\n#5\n1!\n#10\n0!\n")?;
+    let different = write("different.vcd", "#0\n0!\n#5\n1!\n#7\n0!\n")?;
+    let unsorted = write("unsorted.vcd", "#0\n0!\n#5\n1!\n#2\n0!\n")?;
+    let overflow = write("overflow.vcd", "#0\n0!\n#18446744073709551\n1!\n")?;
+
+    let before = identifiers_used();
+
+    assert_eq!(compare(&output, &same)?, None);
+    assert_eq!(
+        compare(&output, &different)?,
+        Some(Difference::Value {
+            time: 7_000_000,
+            path: "top.clk".to_string(),
+            output: vec![],
+            reference: vec!["0".to_string()],
+        })
+    );
+    assert_eq!(
+        compare(&output, &unsorted)?,
+        Some(Difference::Unsorted {
+            time: 2_000_000,
+            in_reference: true,
+        })
+    );
+
+    let error = compare(&output, &overflow).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "timestamp 18446744073709551 overflows in femtoseconds"
+    );
+
+    // the identifiers of the compared files are not reserved for the ones generated later.
+    assert_eq!(identifiers_used(), before);
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge;
+
+#[test]
+fn compressed_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let expected = merge(&["tests/test1.vcd", "tests/test2.vcd", "--reorder"])?;
+    for input in [
+        "tests/test1.vcd.gz",
+        "tests/test1.vcd.zst",
+        "tests/test1.vcd.lz4",
+    ] {
+        let merged = merge(&[input, "tests/test2.vcd", "--reorder"])?;
+        assert_eq!(merged, expected, "{}", input);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn compressed_inputs_in_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    dir.copy_from("tests", &["test1.vcd.zst", "test2.vcd"])?;
+    dir.child("test3.vcd.bak").write_str("not a vcd")?;
+
+    let expected = merge(&["tests/test1.vcd", "tests/test2.vcd", "--reorder"])?;
+    assert_eq!(
+        merge(&[dir.path().as_os_str(), "--reorder".as_ref()])?,
+        expected
+    );
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{merge_data, run};
+use predicates::prelude::*;
+
+// `a.clk` of both files is the same signal, and is set to different values at #5.
+const ARGS: [&str; 3] = ["--append", "tests/same_start1.vcd", "tests/conflict.vcd"];
+
+#[test]
+fn conflict_first() -> Result<(), Box<dyn std::error::Error>> {
+    let data = merge_data(&[&ARGS[..], &["--conflict", "first"]].concat())?;
+    assert_eq!(data, "#5\n1!\n#7\n0!\n0!\n");
+    Ok(())
+}
+
+#[test]
+fn conflict_last() -> Result<(), Box<dyn std::error::Error>> {
+    let data = merge_data(&[&ARGS[..], &["--conflict", "last"]].concat())?;
+    assert_eq!(data, "#5\n1!\n0!\n#7\n0!\n0!\n");
+    Ok(())
+}
+
+#[test]
+fn conflict_error() -> Result<(), Box<dyn std::error::Error>> {
+    // a failed merge doesn't write the output.
+    let (assert, content) = run(&[&ARGS[..], &["--conflict", "error"]].concat())?;
+    assert!(content.is_empty());
+    assert.code(6).stderr(predicate::str::contains(
+        "conflicting changes of a.clk at 5: 1 in tests/same_start1.vcd, but 0 in tests/conflict.vcd",
+    ));
+    Ok(())
+}
+
+#[test]
+fn conflict_priority() -> Result<(), Box<dyn std::error::Error>> {
+    // the change of conflict.vcd is written after the one of the appended file, so it overrides
+    // it, even though --conflict first would keep the first.
+    let priority = ["--priority", "tests/conflict.vcd,tests/same_start1.vcd"];
+    let data = merge_data(&[&ARGS[..], &["--conflict", "first"], &priority].concat())?;
+    assert_eq!(data, "#5\n1!\n0!\n#7\n0!\n0!\n");
+
+    // the appended file is the most trusted, so the change of conflict.vcd is left out.
+    let priority = ["--priority", "tests/same_start1.vcd"];
+    let data = merge_data(&[&ARGS[..], &["--conflict", "last"], &priority].concat())?;
+    assert_eq!(data, "#5\n1!\n#7\n0!\n0!\n");
+
+    // a conflict with a listed input is resolved by the priority, instead of failing.
+    let priority = ["--priority", "./tests/same_start1.vcd"];
+    merge_data(&[&ARGS[..], &["--conflict", "error"], &priority].concat())?;
+    Ok(())
+}
+
+#[test]
+fn conflict_priority_unknown() -> Result<(), Box<dyn std::error::Error>> {
+    let priority = ["--priority", "tests/test1.vcd"];
+    let (assert, _) = run(&[&ARGS[..], &["--conflict", "last"], &priority].concat())?;
+    assert.code(1).stderr(predicate::str::contains(
+        "--priority: tests/test1.vcd is not an input",
+    ));
+    Ok(())
+}
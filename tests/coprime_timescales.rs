@@ -0,0 +1,30 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge;
+
+#[test]
+fn coprime_timescales() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    let mut inputs = Vec::new();
+    for (name, scale) in [("a", "3 ns"), ("b", "7 ns")] {
+        let input = dir.child(format!("{}.vcd", name));
+        input.write_str(&format!(
+            "$timescale {} $end\n$scope module {} $end\n$var wire 1 ! clk $end\n\
+             $upscope $end\n$enddefinitions $end\n#1\n1!\n#2\n0!\n",
+            scale, name
+        ))?;
+        inputs.push(input);
+    }
+
+    let content = merge(&[inputs[0].path(), inputs[1].path()])?;
+
+    // the GCD of 3 ns and 7 ns is written as 1ns, not 1000ps, and the timestamps of the inputs
+    // are multiplied by 3 and 7.
+    let (header, data) = content.split_once("$enddefinitions $end\n").unwrap();
+    assert!(header.starts_with("$timescale 1ns $end\n"));
+    assert_eq!(data, "#3\n1!\n#6\n0!\n#7\n1\"\n#14\n0\"\n");
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::vcd_merger;
+use predicates::prelude::*;
+
+#[test]
+fn count_only() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = vcd_merger();
+
+    cmd.arg("tests/unused.vcd")
+        .arg("tests/same_start2.vcd")
+        .arg("--count-only");
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "7 value changes in 3 signals\n\
+             \x20          3 top.clk\n\
+             \x20          2 b.clk\n\
+             \x20          2 top.data\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn count_only_top() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = vcd_merger();
+
+    cmd.arg("tests/unused.vcd")
+        .arg("tests/same_start2.vcd")
+        .arg("--count-only")
+        .arg("--top")
+        .arg("1");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("3 top.clk\n"))
+        .stdout(predicate::str::contains("b.clk").not());
+
+    Ok(())
+}
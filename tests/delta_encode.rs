@@ -0,0 +1,23 @@
+mod common;
+
+use common::{data, merge_data};
+
+#[test]
+fn delta_encode() -> Result<(), Box<dyn std::error::Error>> {
+    // changes within 0.01 of the last written value are left out, but timestamps and the changes
+    // of other signals are kept.
+    let data = merge_data(&["tests/slow_real.vcd", "--delta-encode", "--epsilon", "0.01"])?;
+    assert_eq!(
+        data,
+        "#0\nr1 !\n0\"\n#1\n1\"\n#2\n0\"\n#3\nr1.5 !\n#4\n#5\n#6\nr2 !\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn delta_encode_disabled() -> Result<(), Box<dyn std::error::Error>> {
+    let output = merge_data(&["tests/slow_real.vcd"])?;
+    let input = std::fs::read_to_string("tests/slow_real.vcd")?;
+    assert_eq!(output, data(&input));
+    Ok(())
+}
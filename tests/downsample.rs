@@ -0,0 +1,61 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge_data;
+
+fn merge(downsample: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let input = dir.child("in.vcd");
+    input.write_str(
+        "$timescale 1 ns $end\n\
+         $scope module a $end\n\
+         $var wire 1 ! clk $end\n\
+         $var wire 2 \" data $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n0!\nb00 \"\n\
+         #1\n1!\nb01 \"\n\
+         #2\n0!\n\
+         #3\n1!\nb10 \"\n\
+         #4\n0!\n\
+         #5\n1!\nb11 \"\n\
+         #6\n0!\n\
+         #7\nb00 \"\n",
+    )?;
+
+    merge_data(&[input.path().to_str().unwrap(), "--downsample", downsample])
+}
+
+#[test]
+fn downsample() -> Result<(), Box<dyn std::error::Error>> {
+    // every other timestamp is kept, with the last values of the dropped ones. The change at #7
+    // is after the last kept timestamp, so it is written at #7.
+    assert_eq!(
+        merge("2")?,
+        "#0\n0!\nb00 \"\n\
+         #2\n0!\nb01 \"\n\
+         #4\n0!\nb10 \"\n\
+         #6\n0!\nb11 \"\n\
+         #7\nb00 \"\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn downsample_by_3() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge("3")?,
+        "#0\n0!\nb00 \"\n\
+         #3\n1!\nb10 \"\n\
+         #6\n0!\nb11 \"\n\
+         #7\nb00 \"\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn downsample_by_1() -> Result<(), Box<dyn std::error::Error>> {
+    let data = merge("1")?;
+    assert_eq!(data.matches('#').count(), 8);
+    Ok(())
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::merge;
+use vcd_merger::{parse_headers, plan_sections, Header, ParseOptions};
+
+#[test]
+fn dump_sections() -> Result<(), Box<dyn std::error::Error>> {
+    let dump = assert_fs::NamedTempFile::new("sections.tsv")?;
+
+    merge(&[
+        "tests/test1.vcd",
+        "tests/test2.vcd",
+        "--reorder",
+        "--dump-sections",
+        dump.path().to_str().unwrap(),
+    ])?;
+
+    let inputs = ["tests/test1.vcd", "tests/test2.vcd"];
+    let vcds = parse_headers(
+        inputs.iter(),
+        &mut Header::default(),
+        &ParseOptions::default(),
+    )?;
+    let plan = plan_sections(&vcds)?;
+
+    let content = std::fs::read_to_string(dump.path())?;
+    let mut lines = content.lines();
+    assert_eq!(lines.next(), Some("path\tstart\tend\tvalue\tfirst\tlast"));
+
+    // one row for each section, in the order they are found.
+    let rows = lines.collect::<Vec<_>>();
+    assert_eq!(rows.len(), plan.len());
+    for (row, info) in rows.iter().zip(&plan) {
+        let columns = row.split('\t').collect::<Vec<_>>();
+        assert_eq!(columns.len(), 6);
+        assert_eq!(columns[0], info.path.display().to_string());
+        assert_eq!(columns[1], info.range.start.to_string());
+        assert_eq!(columns[2], info.range.end.to_string());
+        assert_eq!(columns[3], info.time_range.0.to_string());
+    }
+
+    Ok(())
+}
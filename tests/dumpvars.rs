@@ -0,0 +1,32 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn dumpvars() -> Result<(), Box<dyn std::error::Error>> {
+    // the signals of dumpvars.vcd are remapped after the ones of nested.vcd, and every kind of
+    // value in `$dumpvars`, and after it in uppercase, is remapped.
+    let expected = "\
+#0
+0!
+b0 \"
+b0 #
+1$
+b1010 %
+r0.5 &
+sIDLE '
+#1
+1!
+b101 \"
+b1 #
+#3
+0$
+B0101 %
+R1.5 &
+SBUSY '
+";
+    let inputs = ["tests/nested.vcd", "tests/dumpvars.vcd"];
+    assert_eq!(merge_data(&inputs)?, expected);
+    assert_eq!(merge_data(&[inputs[0], inputs[1], "--reorder"])?, expected);
+    Ok(())
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{merge, vcd_merger};
+use predicates::prelude::*;
+
+#[test]
+fn duplicate_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_fs::NamedTempFile::new("out.vcd")?;
+
+    // the same file, through different paths.
+    vcd_merger()
+        .arg("tests/test1.vcd")
+        .arg("tests/../tests/test1.vcd")
+        .arg("-o")
+        .arg(output.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "warning: tests/../tests/test1.vcd is given more than once as input",
+        ));
+
+    let single = merge(&["tests/test1.vcd"])?;
+    assert_eq!(std::fs::read_to_string(output.path())?, single);
+
+    // with a different time offset, the same file is not a duplicate.
+    vcd_merger()
+        .arg("tests/test1.vcd")
+        .arg("tests/../tests/test1.vcd")
+        .arg("--time-offset")
+        .arg("1=5")
+        .arg("-o")
+        .arg(output.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("more than once").not());
+    let content = std::fs::read_to_string(output.path())?;
+    assert_eq!(content.matches("$scope module test1 $end").count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn allow_duplicate_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[
+        "tests/test1.vcd",
+        "tests/test1.vcd",
+        "--allow-duplicate-inputs",
+    ])?;
+    assert_eq!(content.matches("$scope module test1 $end").count(), 2);
+    Ok(())
+}
+
+#[test]
+fn identical_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let copy = dir.path().join("copy.vcd");
+    std::fs::copy("tests/test1.vcd", &copy)?;
+    let copy = copy.to_str().unwrap();
+
+    let output = assert_fs::NamedTempFile::new("out.vcd")?;
+
+    // a copy of the same file, which is a different file with the same content.
+    vcd_merger()
+        .arg("tests/test1.vcd")
+        .arg("tests/test2.vcd")
+        .arg(copy)
+        .arg("-o")
+        .arg(output.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "warning: {} has the same content as tests/test1.vcd, it is only merged once",
+            copy
+        )));
+
+    let without_copy = merge(&["tests/test1.vcd", "tests/test2.vcd"])?;
+    assert_eq!(std::fs::read_to_string(output.path())?, without_copy);
+
+    let content = merge(&["tests/test1.vcd", copy, "--allow-duplicate-inputs"])?;
+    assert_eq!(content.matches("$scope module test1 $end").count(), 2);
+
+    // with a different time offset, the copy is not a duplicate.
+    vcd_merger()
+        .arg("tests/test1.vcd")
+        .arg(copy)
+        .arg("--time-offset")
+        .arg("1=5")
+        .arg("-o")
+        .arg(output.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("same content").not());
+    let content = std::fs::read_to_string(output.path())?;
+    assert_eq!(content.matches("$scope module test1 $end").count(), 2);
+
+    // options given to the copy by its path apply to it and not to the original, so it is kept.
+    for (option, value) in [
+        ("--exclude", format!("{}:*", copy)),
+        ("--bit-order", format!("{}:lsb", copy)),
+        ("--priority", copy.to_string()),
+    ] {
+        vcd_merger()
+            .arg("tests/test1.vcd")
+            .arg(copy)
+            .arg(option)
+            .arg(value)
+            .arg("-o")
+            .arg(output.path())
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("same content").not());
+    }
+    let content = std::fs::read_to_string(output.path())?;
+    assert_eq!(content.matches("$scope module test1 $end").count(), 2);
+
+    Ok(())
+}
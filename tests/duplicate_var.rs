@@ -0,0 +1,25 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn duplicate_var() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&["tests/duplicate_var.vcd"])?;
+
+    let expected = "\
+$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! clk $end
+$var wire 1 \" rst $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+1\"
+#1
+1!
+";
+    assert_eq!(content, expected);
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::merge;
+
+const EXPECTED: &str = "\
+$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! a $end
+$var wire 1 \" b $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+1\"
+#5
+1!
+";
+
+/// A `$dumpvars` followed by more declarations, without a `$enddefinitions`, doesn't end the
+/// declarations, and its values are merged at time 0.
+#[test]
+fn early_dumpvars() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(merge(&["tests/early_dumpvars.vcd"])?, EXPECTED);
+    assert_eq!(merge(&["tests/early_dumpvars.vcd", "--reorder"])?, EXPECTED);
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn embed_map() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&["tests/test1.vcd", "tests/test2.vcd", "--embed-map"])?;
+    let start = content.find("$comment\n").unwrap();
+    let end = start + content[start..].find("$end\n").unwrap();
+    let comment = &content[start..end];
+
+    let mappings = comment
+        .lines()
+        .filter(|line| line.contains(" -> "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        mappings,
+        ["  ! -> !", "  \" -> \"", "  ! -> #", "  !@# -> $"]
+    );
+
+    Ok(())
+}
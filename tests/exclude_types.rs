@@ -0,0 +1,28 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn exclude_parameters() -> Result<(), Box<dyn std::error::Error>> {
+    let expected = " module p $end\n\
+                    $var wire 1 ! clk $end\n\
+                    $var real 1 \" temp $end\n\
+                    $upscope $end\n\
+                    $enddefinitions $end\n\
+                    #0\n0!\nr1.5 \"\n#5\n1!\nr2.5 \"\n";
+    let content = merge(&["tests/parameters.vcd", "--exclude-types", "parameter"])?;
+    assert_eq!(content.split_once("$scope").unwrap().1, expected);
+    Ok(())
+}
+
+#[test]
+fn exclude_multiple_types() -> Result<(), Box<dyn std::error::Error>> {
+    let expected = " module p $end\n\
+                    $var wire 1 ! clk $end\n\
+                    $upscope $end\n\
+                    $enddefinitions $end\n\
+                    #0\n0!\n#5\n1!\n";
+    let content = merge(&["tests/parameters.vcd", "--exclude-types", "parameter,real"])?;
+    assert_eq!(content.split_once("$scope").unwrap().1, expected);
+    Ok(())
+}
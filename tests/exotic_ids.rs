@@ -0,0 +1,32 @@
+mod common;
+
+use common::merge_data;
+
+const EXPECTED_DATA: &str = "\
+#0
+0!
+1\"
+b01 #
+r1.5 $
+#3
+1!
+0\"
+b10 #
+r2 $
+";
+
+#[test]
+fn exotic_ids() -> Result<(), Box<dyn std::error::Error>> {
+    // the input uses `{`, `}`, `~` and `` ` `` as identifiers.
+    assert_eq!(merge_data(&["tests/exotic_ids.vcd"])?, EXPECTED_DATA);
+    Ok(())
+}
+
+#[test]
+fn exotic_ids_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge_data(&["tests/exotic_ids.vcd", "--reorder"])?,
+        EXPECTED_DATA
+    );
+    Ok(())
+}
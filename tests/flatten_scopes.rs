@@ -0,0 +1,38 @@
+mod common;
+
+use common::{merge, run};
+use predicates::prelude::*;
+
+#[test]
+fn flatten_scopes() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&["tests/nested.vcd", "--flatten-scopes"])?;
+
+    assert!(content.contains(
+        "$timescale 1ns $end\n\
+         $var wire 1 ! top.clk $end\n\
+         $var wire 8 \" top.cpu.alu.result $end\n\
+         $var reg 16 # top.cpu.pc $end\n\
+         $enddefinitions $end\n",
+    ));
+    assert!(!content.contains("$scope"));
+
+    Ok(())
+}
+
+#[test]
+fn flatten_scopes_collision() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&[
+        "tests/same_start1.vcd",
+        "tests/same_start1.vcd",
+        "--flatten-scopes",
+        "--allow-duplicate-inputs",
+    ])?;
+
+    assert.success().stderr(predicate::str::contains(
+        "warning: a.clk is declared more than once after flattening, renamed to a.clk_1",
+    ));
+
+    assert!(content.contains("$var wire 1 ! a.clk $end\n$var wire 1 \" a.clk_1 $end\n"));
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge_data;
+
+#[test]
+fn fragmented() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // each pair of timestamps jumps backwards, so every section has only two timestamps.
+    let count = 10_000;
+    let mut inputs = Vec::new();
+    for (name, id) in [("a", '!'), ("b", '!')] {
+        let mut input = format!(
+            "$timescale 1 ns $end\n$scope module {} $end\n$var wire 1 {} clk $end\n\
+             $upscope $end\n$enddefinitions $end\n",
+            name, id
+        );
+        for k in 0..count {
+            input.push_str(&format!("#{}\n1{}\n#{}\n0{}\n", 2 * k + 1, id, 2 * k, id));
+        }
+        let file = dir.child(format!("{}.vcd", name));
+        file.write_str(&input)?;
+        inputs.push(file);
+    }
+
+    let data = merge_data(&[
+        inputs[0].path().to_str().unwrap(),
+        inputs[1].path().to_str().unwrap(),
+        "--reorder",
+        "--threads",
+        "2",
+    ])?;
+
+    let mut expected = String::new();
+    for t in 0..2 * count {
+        let value = t % 2;
+        expected.push_str(&format!("#{}\n{}!\n{}\"\n", t, value, value));
+    }
+    assert!(data == expected, "the merged timestamps are not sorted");
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{data, merge, vcd_merger};
+use predicates::prelude::*;
+
+#[test]
+fn from_config() -> Result<(), Box<dyn std::error::Error>> {
+    let from_config = merge(&["--from-config", "tests/merge.toml"])?;
+    let from_args = merge(&[
+        "tests/nested.vcd",
+        "tests/integer_parameter.vcd",
+        "--reorder",
+        "--negative-time",
+        "drop",
+        "--exclude-types",
+        "real",
+        "--time-offset",
+        "0=-1",
+        "--exclude",
+        "tests/nested.vcd:*.result",
+        "--include",
+        "tests/integer_parameter.vcd:counter.count",
+    ])?;
+    assert_eq!(from_config, from_args);
+
+    // the changes at time 0 of the first input are dropped by the offset.
+    assert_eq!(
+        data(&from_config),
+        "#0\n1!\nb1 \"\nb0 #\n#5\nb1 #\n#10\nb10 #\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn from_config_overridden() -> Result<(), Box<dyn std::error::Error>> {
+    let overridden = merge(&["--from-config=tests/merge.toml", "--negative-time", "clamp"])?;
+    let from_args = merge(&[
+        "tests/nested.vcd",
+        "tests/integer_parameter.vcd",
+        "--reorder",
+        "--exclude-types",
+        "real",
+        "--time-offset",
+        "0=-1",
+        "--exclude",
+        "tests/nested.vcd:*.result",
+        "--include",
+        "tests/integer_parameter.vcd:counter.count",
+    ])?;
+    assert_eq!(overridden, from_args);
+
+    Ok(())
+}
+
+#[test]
+fn from_config_with_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    // the inputs of the command line are merged after the ones of the config, and list options
+    // are added to the ones of the config.
+    let mixed = merge(&[
+        "tests/test2.vcd",
+        "--from-config",
+        "tests/merge.toml",
+        "--exclude-types",
+        "parameter",
+    ])?;
+    let from_args = merge(&[
+        "tests/nested.vcd",
+        "tests/integer_parameter.vcd",
+        "tests/test2.vcd",
+        "--reorder",
+        "--negative-time",
+        "drop",
+        "--exclude-types",
+        "real",
+        "--exclude-types",
+        "parameter",
+        "--time-offset",
+        "0=-1",
+        "--exclude",
+        "tests/nested.vcd:*.result",
+        "--include",
+        "tests/integer_parameter.vcd:counter.count",
+    ])?;
+    assert_eq!(mixed, from_args);
+
+    // a index of --time-offset would be shifted by the inputs of the config.
+    vcd_merger()
+        .arg("tests/test2.vcd")
+        .arg("--from-config")
+        .arg("tests/merge.toml")
+        .arg("--time-offset")
+        .arg("0=5")
+        .arg("-o")
+        .arg("out.vcd")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "--time-offset can't be used with --from-config",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_config() -> Result<(), Box<dyn std::error::Error>> {
+    let config = assert_fs::NamedTempFile::new("merge.toml")?;
+    std::fs::write(config.path(), "reorder = yes\n")?;
+
+    vcd_merger()
+        .arg("--from-config")
+        .arg(config.path())
+        .arg("-o")
+        .arg("out.vcd")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("line 1: invalid value \"yes\""));
+
+    Ok(())
+}
+
+#[test]
+fn from_config_relative_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    std::fs::create_dir(dir.path().join("inputs"))?;
+    std::fs::copy("tests/test1.vcd", dir.path().join("inputs/test1.vcd"))?;
+    std::fs::copy("tests/test2.vcd", dir.path().join("inputs/test2.vcd"))?;
+    std::fs::write(
+        dir.path().join("merge.toml"),
+        "reorder = true\n\
+         output = \"out.vcd\"\n\
+         symbol_map = \"map.txt\"\n\
+         bit_order = \"inputs/test2.vcd:msb\"\n\
+         \n\
+         [[input]]\n\
+         path = \"inputs/test1.vcd\"\n\
+         \n\
+         [[input]]\n\
+         path = \"inputs/test2.vcd\"\n",
+    )?;
+
+    // the paths are relative to the config, not to the working directory.
+    vcd_merger()
+        .arg("--from-config")
+        .arg(dir.path().join("merge.toml"))
+        .assert()
+        .success();
+
+    let output = std::fs::read_to_string(dir.path().join("out.vcd"))?;
+    let expected = std::fs::read_to_string("tests/expected.vcd")?;
+    assert_eq!(output, expected);
+    assert!(dir.path().join("map.txt").exists());
+
+    Ok(())
+}
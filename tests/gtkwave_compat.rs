@@ -0,0 +1,38 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge;
+
+#[test]
+fn gtkwave_compat() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // enough signals to need identifiers with two characters.
+    let mut input = String::from("$timescale 1 ns $end\n$scope module top $end\n");
+    for i in 0..100 {
+        input.push_str(&format!("$var wire 1 s{} sig{} $end\n", i, i));
+    }
+    input.push_str("$upscope $end\n$enddefinitions $end\n#0\n");
+    for i in 0..100 {
+        input.push_str(&format!("1s{}\n", i));
+    }
+    let input_file = dir.child("in.vcd");
+    input_file.write_str(&input)?;
+
+    let content = merge(&[input_file.path().to_str().unwrap(), "--gtkwave-compat"])?;
+    let ids = content
+        .lines()
+        .filter(|line| line.starts_with("$var"))
+        .map(|line| line.split(' ').nth(3).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids.len(), 100);
+    for id in &ids {
+        assert!(
+            id.chars().all(|c| c.is_ascii_alphanumeric()),
+            "generated identifier {}",
+            id
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn no_enddefinitions() -> Result<(), Box<dyn std::error::Error>> {
+    // the header ends at the first timestamp, which is not dropped with the header.
+    for args in [&[][..], &["--reorder"]] {
+        assert_eq!(
+            merge_data(&[&["tests/no_enddefinitions.vcd"], args].concat())?,
+            "#5\n1!\n#7\n0!\n"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn inline_data() -> Result<(), Box<dyn std::error::Error>> {
+    // the first timestamp is in the same line as `$enddefinitions $end`.
+    for args in [&[][..], &["--reorder"]] {
+        assert_eq!(
+            merge_data(&[&["tests/inline_data.vcd"], args].concat())?,
+            "#5\n1!\n#7\n0!\n"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::run;
+use flate2::{write::GzEncoder, Compression};
+use predicates::prelude::*;
+use std::io::Write;
+use vcd_merger::{parse_headers, Error, Header, HeaderError, ParseOptions};
+
+#[test]
+fn truncated_header() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/truncated_header.vcd"])?;
+    assert
+        .code(5)
+        .stderr(predicate::str::contains("unexpected end of file in $var"));
+
+    Ok(())
+}
+
+#[test]
+fn unterminated_version() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/unterminated_version.vcd"])?;
+    assert.code(5).stderr(predicate::str::contains(
+        "expected $end in $version, found \"$timescale\"",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn no_line_breaks() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("blob.vcd")?;
+
+    // declarations that go on past 1 MiB without a single line break.
+    let mut blob = String::from("$timescale 1ns $end $scope module top $end ");
+    for i in 0..100_000 {
+        blob += &format!("$var wire 1 ! s{} $end ", i);
+    }
+    std::fs::write(input.path(), blob)?;
+
+    let (assert, _) = run(&[input.path()])?;
+    assert
+        .code(5)
+        .stderr(predicate::str::contains("malformed header: no line breaks"));
+
+    // a binary blob without whitespace is a single token.
+    std::fs::write(input.path(), vec![b'x'; 2 << 20])?;
+
+    let (assert, _) = run(&[input.path()])?;
+    assert
+        .code(5)
+        .stderr(predicate::str::contains("malformed header: no line breaks"));
+
+    Ok(())
+}
+
+#[test]
+fn compressed_twice() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let input = dir.path().join("test1.vcd.gz.gz");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&std::fs::read("tests/test1.vcd.gz")?)?;
+    std::fs::write(&input, encoder.finish()?)?;
+
+    // only one layer of compression is removed.
+    let (assert, _) = run(&[input.to_str().unwrap()])?;
+    assert.code(5).stderr(predicate::str::contains(
+        "input is compressed with gzip, decompress it first",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn library_errors() {
+    let parse = |input: &str| {
+        parse_headers(
+            [input].iter(),
+            &mut Header::default(),
+            &ParseOptions::default(),
+        )
+    };
+
+    // the library returns the errors, instead of exiting the process.
+    assert!(matches!(
+        parse("tests/truncated_header.vcd"),
+        Err(Error::Header {
+            source: HeaderError::UnexpectedEof { .. },
+            ..
+        })
+    ));
+    assert!(matches!(
+        parse("tests/missing.vcd"),
+        Err(Error::Read { .. })
+    ));
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::run;
+use predicates::prelude::*;
+
+#[test]
+fn header_only() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&[
+        "tests/test1.vcd",
+        "tests/test2.vcd",
+        "--reorder",
+        "--header-only",
+    ])?;
+
+    assert
+        .success()
+        .stdout(predicate::str::contains("[2/2] writing the header"))
+        .stdout(predicate::str::contains("finding sections").not());
+
+    // the same header as the full merge, and nothing after it.
+    let expected = std::fs::read_to_string("tests/expected.vcd")?;
+    let (header, _) = expected.split_once("$enddefinitions $end\n").unwrap();
+    assert_eq!(content, format!("{}$enddefinitions $end\n", header));
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::vcd_merger;
+use predicates::prelude::*;
+
+#[test]
+fn help() -> Result<(), Box<dyn std::error::Error>> {
+    vcd_merger()
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Usage: vcd-merger [OPTIONS]"));
+    Ok(())
+}
+
+#[test]
+fn version() -> Result<(), Box<dyn std::error::Error>> {
+    vcd_merger()
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout(format!("vcd-merger {}\n", env!("CARGO_PKG_VERSION")));
+    Ok(())
+}
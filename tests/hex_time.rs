@@ -0,0 +1,23 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn hex_time_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    // the timestamps are written in hexadecimal, and #0xC8 (200) is out of order.
+    assert_eq!(
+        merge_data(&["tests/hex_time.vcd", "tests/same_start1.vcd", "--reorder"])?,
+        "#0\n0!\n#5\n1\"\n#7\n0\"\n#200\n0!\n#500\n1!\n#1000\n0!\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn hex_time() -> Result<(), Box<dyn std::error::Error>> {
+    // without reordering, the timestamps are only converted to decimal.
+    assert!(
+        merge_data(&["tests/hex_time.vcd", "tests/same_start1.vcd"])?
+            .ends_with("#500\n1!\n#200\n0!\n#1000\n0!\n")
+    );
+    Ok(())
+}
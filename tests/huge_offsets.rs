@@ -0,0 +1,37 @@
+mod common;
+
+use common::merge_data;
+use std::io::{Seek, SeekFrom, Write};
+
+/// Merge a input whose data starts after a 4 GiB comment. The comment is a hole in a sparse file,
+/// so it uses no disk space, but it still takes a few seconds to scan.
+#[test]
+#[ignore = "scans a 4 GiB file"]
+fn huge_offsets() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("huge.vcd")?;
+
+    let mut file = std::fs::File::create(input.path())?;
+    file.write_all(
+        b"$timescale 1 ns $end\n\
+          $scope module h $end\n\
+          $var wire 1 ! clk $end\n\
+          $upscope $end\n\
+          $enddefinitions $end\n\
+          $comment ",
+    )?;
+    // leave a hole of zeros, ending after 4 GiB.
+    file.seek(SeekFrom::Start(0x1_0000_0000))?;
+    file.write_all(b" $end\n#5\n1!\n#3\n0!\n#9\n0!\n")?;
+    drop(file);
+
+    let input = input.path().to_str().unwrap();
+
+    // the data doesn't start with a timestamp, so the whole file is a section at #0.
+    assert_eq!(merge_data(&[input])?, "#0\n#5\n1!\n#3\n0!\n#9\n0!\n");
+    assert_eq!(
+        merge_data(&[input, "--reorder"])?,
+        "#3\n0!\n#5\n1!\n#9\n0!\n"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{merge, run};
+use predicates::prelude::*;
+use vcd_merger::{parse_headers, Error, Header, ParseOptions};
+
+#[test]
+fn input_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    dir.copy_from("tests", &["same_start*.vcd"])?;
+    dir.child("notes.txt").write_str("not a vcd")?;
+
+    let expected = merge(&["tests/same_start1.vcd", "tests/same_start2.vcd"])?;
+    assert_eq!(merge(&[dir.path()])?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn input_directory_recursive() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    dir.child("a").copy_from("tests", &["same_start1.vcd"])?;
+    dir.child("b").copy_from("tests", &["same_start2.vcd"])?;
+
+    let expected = merge(&["tests/same_start1.vcd", "tests/same_start2.vcd"])?;
+    assert_eq!(
+        merge(&[dir.path().as_os_str(), "--recursive".as_ref()])?,
+        expected
+    );
+
+    Ok(())
+}
+
+#[test]
+fn empty_input_directory() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    dir.child("notes.txt").write_str("not a vcd")?;
+
+    let (assert, content) = run(&[dir.path()])?;
+    assert.code(1).stderr(predicate::str::contains(format!(
+        "No .vcd files found in the directory {}",
+        dir.path().display()
+    )));
+    assert!(content.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn no_inputs() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["--reorder"])?;
+    assert
+        .code(1)
+        .stderr(predicate::str::contains("No inputs given"));
+
+    // the library returns a error instead of panicking.
+    let inputs: [&str; 0] = [];
+    let result = parse_headers(
+        inputs.iter(),
+        &mut Header::default(),
+        &ParseOptions::default(),
+    );
+    assert!(matches!(result, Err(Error::NoInputs)));
+
+    Ok(())
+}
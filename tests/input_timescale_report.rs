@@ -0,0 +1,23 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::vcd_merger;
+use predicates::prelude::*;
+
+#[test]
+fn input_timescale_report() -> Result<(), Box<dyn std::error::Error>> {
+    vcd_merger()
+        .arg("tests/timescale_us.vcd")
+        .arg("tests/test1.vcd")
+        .arg("tests/invalid_timescale.vcd")
+        .arg("--input-timescale-report")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "tests/timescale_us.vcd: \"1 us\" = 1000000000fs\n\
+         tests/test1.vcd: \"1 ms\" = 1000000000000fs\n\
+         tests/invalid_timescale.vcd: \"auto\" = 1000000fs\n",
+        ));
+
+    Ok(())
+}
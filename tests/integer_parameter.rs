@@ -0,0 +1,29 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn integer_parameter() -> Result<(), Box<dyn std::error::Error>> {
+    // the integer and parameter of the second input are remapped after the signals of the first.
+    let inputs = ["tests/nested.vcd", "tests/integer_parameter.vcd"];
+    for content in [
+        merge(&inputs)?,
+        merge(&[inputs[0], inputs[1], "--reorder"])?,
+    ] {
+        let (header, data) = content.split_once("$enddefinitions $end\n").unwrap();
+
+        assert!(header.ends_with(
+            "\
+$scope module counter $end
+$var integer 32 $ count $end
+$var parameter 32 % DEPTH $end
+$upscope $end
+"
+        ));
+        assert_eq!(
+            data,
+            "#0\n0!\nb0 \"\nb0 #\nb10000 %\nb0 $\n#1\n1!\nb101 \"\nb1 #\n#5\nb1 $\n#10\nb10 $\n"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::run;
+use predicates::prelude::*;
+
+fn merge(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let inputs = ["tests/same_start1.vcd", "tests/invalid_timescale.vcd"];
+    let (assert, content) = run(&[&inputs[..], args].concat())?;
+    assert.success().stderr(predicate::str::contains(
+        "warning: invalid timescale \"auto\" in tests/invalid_timescale.vcd",
+    ));
+    Ok(content)
+}
+
+#[test]
+fn invalid_timescale_default() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[])?;
+    assert!(content.contains("$timescale 1ns $end"));
+    assert!(content.ends_with("#2\n1\"\n#4\n0\"\n#5\n1!\n#7\n0!\n"));
+    Ok(())
+}
+
+#[test]
+fn invalid_timescale_override() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&["--timescale", "100ps"])?;
+    assert!(content.contains("$timescale 100ps $end"));
+    assert!(content.ends_with("#2\n1\"\n#4\n0\"\n#50\n1!\n#70\n0!\n"));
+    Ok(())
+}
+
+#[test]
+fn fractional_timescale() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let input = dir.child("fractional.vcd");
+    input.write_str(
+        "$timescale 1.5 ns $end\n\
+         $scope module a $end\n\
+         $var wire 1 ! clk $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #2\n\
+         1!\n",
+    )?;
+
+    // the `.5` is not dropped, and the file is rejected even with a fallback timescale.
+    let (assert, content) = run(&[input.path().to_str().unwrap(), "--timescale", "100ps"])?;
+    assert.code(5).stderr(predicate::str::contains(
+        "timescale \"1.5 ns\" is not an integer",
+    ));
+    assert!(content.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn zero_timescale() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let input = dir.child("zero.vcd");
+    input.write_str(
+        "$timescale 0 ns $end\n\
+         $scope module a $end\n\
+         $var wire 1 ! clk $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #2\n\
+         1!\n",
+    )?;
+    let input = input.path().to_str().unwrap();
+
+    // a zero timescale would break finding the common timescale, so the fallback is used.
+    let (assert, content) = run(&["tests/same_start1.vcd", input])?;
+    assert.success().stderr(predicate::str::contains(
+        "warning: invalid timescale \"0 ns\" (timescale must be positive)",
+    ));
+    assert!(content.contains("$timescale 1ns $end"));
+
+    // and the fallback can't be zero either.
+    let (assert, _) = run(&[input, "--timescale", "0ns"])?;
+    assert
+        .code(2)
+        .stderr(predicate::str::contains("timescale must be positive"));
+
+    Ok(())
+}
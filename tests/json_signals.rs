@@ -0,0 +1,62 @@
+mod common;
+
+use common::merge;
+
+/// Return the maximum nesting depth of `children` arrays, checking that all brackets and strings
+/// are balanced.
+fn scope_depth(json: &str) -> usize {
+    let mut stack = Vec::new();
+    let mut max_depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut last_key = String::new();
+    let mut key = String::new();
+    for c in json.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    in_string = false;
+                    last_key = std::mem::take(&mut key);
+                }
+                _ => key.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                stack.push((c, last_key == "children"));
+                let depth = stack.iter().filter(|(c, children)| *c == '[' && *children);
+                max_depth = max_depth.max(depth.count());
+            }
+            '}' => assert_eq!(stack.pop().map(|x| x.0), Some('{')),
+            ']' => assert_eq!(stack.pop().map(|x| x.0), Some('[')),
+            _ => {}
+        }
+    }
+    assert!(stack.is_empty() && !in_string);
+    max_depth
+}
+
+#[test]
+fn json_signals() -> Result<(), Box<dyn std::error::Error>> {
+    let json = assert_fs::NamedTempFile::new("signals.json")?;
+
+    merge(&[
+        "tests/nested.vcd",
+        "--json-signals",
+        json.path().to_str().unwrap(),
+    ])?;
+
+    let json = std::fs::read_to_string(json.path())?;
+
+    // root > top > cpu > alu
+    assert_eq!(scope_depth(&json), 4);
+    assert!(json.contains(
+        r#"{"type":"module","name":"alu","children":[],"variables":[{"name":"result","type":"wire","width":8,"original_id":"\"","new_id":"\""}]}"#
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge;
+
+/// The identifiers generated for the first `count` vars of the first input.
+fn identifiers(count: usize) -> Vec<String> {
+    let mut code = [0u8; 4];
+    (0..count)
+        .map(|_| {
+            for b in code.iter_mut() {
+                if *b == 0 || *b == b'~' {
+                    let carry = *b == b'~';
+                    *b = b'!';
+                    if !carry {
+                        break;
+                    }
+                } else {
+                    *b += 1;
+                    break;
+                }
+            }
+            code.iter()
+                .take_while(|&&b| b != 0)
+                .map(|&b| b as char)
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn large_header() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // the identifiers of the input are the ones that are generated for it, so merging it alone
+    // writes the same header and changes.
+    let ids = identifiers(20_000);
+    let mut input = String::from("$date today $end\n$version gen 1.0 $end\n$timescale 1ns $end\n");
+    for (m, ids) in ids.chunks(1000).enumerate() {
+        input.push_str(&format!("$scope module m{} $end\n", m));
+        for (v, id) in ids.iter().enumerate() {
+            if v == 500 {
+                input.push_str("$scope task inner $end\n");
+            }
+            input.push_str(&format!("$var wire 8 {} sig_{} $end\n", id, v));
+        }
+        input.push_str("$upscope $end\n$upscope $end\n");
+    }
+    input.push_str("$enddefinitions $end\n#0\n");
+    for id in ids.iter().step_by(97) {
+        input.push_str(&format!("b1010 {}\n", id));
+    }
+    input.push_str(&format!("#10\nb1 {}\n", ids[ids.len() - 1]));
+
+    let input_file = dir.child("in.vcd");
+    input_file.write_str(&input)?;
+
+    assert_eq!(merge(&[input_file.path()])?, input);
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use vcd_merger::{
+    find_sections, lazy_sections, parse_headers, reset_identifiers, write_output, Header,
+    ParseOptions, Vcd, WriteOptions,
+};
+
+/// The system allocator, keeping track of the peak of memory in use.
+struct PeakAlloc;
+
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let in_use = IN_USE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(in_use, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+/// The peak of memory in use while running `f`, above the memory in use before it.
+fn peak_memory(f: impl FnOnce()) -> usize {
+    let before = IN_USE.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    f();
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+// this is the only test of this file, so no other test allocates while measuring.
+#[test]
+fn lazy_sections_of_fragmented_input() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // each group of timestamps, like `#0 #2 #1`, jumps back by one, starting a new section, for
+    // 100 thousand sections that start in ascending order.
+    let mut fragmented = String::from(
+        "$timescale 1 ns $end\n$scope module top $end\n$var wire 1 ! clk $end\n\
+         $upscope $end\n$enddefinitions $end\n",
+    );
+    for i in 0..100_000 {
+        let [a, b, c] = [3 * i, 3 * i + 2, 3 * i + 1];
+        write!(fragmented, "#{}\n1!\n#{}\n0!\n#{}\nx!\n", a, b, c)?;
+    }
+    let fragmented_path = dir.path().join("fragmented.vcd");
+    std::fs::write(&fragmented_path, fragmented)?;
+
+    // with a input in reverse order, whose sections are still found up front.
+    let inputs = [fragmented_path.as_path(), "tests/reversed.vcd".as_ref()];
+
+    let parse = || -> Result<(Header, Vec<Vcd>), vcd_merger::Error> {
+        reset_identifiers();
+        let mut header = Header::default();
+        let vcds = parse_headers(inputs.iter(), &mut header, &ParseOptions::default())?;
+        Ok((header, vcds))
+    };
+    let options = WriteOptions::default();
+
+    let (header, vcds) = parse()?;
+    let found_path = dir.path().join("found.vcd");
+    let found = peak_memory(|| {
+        let sections = find_sections(&vcds, 1, |_| {}).unwrap();
+        write_output(&found_path, header, &vcds, sections, &options, |_| {}).unwrap();
+    });
+
+    let (header, vcds) = parse()?;
+    let lazy_path = dir.path().join("lazy.vcd");
+    let lazy = peak_memory(|| {
+        let sections = lazy_sections(&vcds, 1, |_| {}).unwrap();
+        assert_eq!(sections.counts(), (4, 1));
+        write_output(&lazy_path, header, &vcds, sections, &options, |_| {}).unwrap();
+    });
+
+    assert_eq!(
+        std::fs::read_to_string(&lazy_path)?,
+        std::fs::read_to_string(&found_path)?
+    );
+
+    // the 100 thousand sections take megabytes, but only a few are streamed at once.
+    assert!(found > 4_000_000, "{} bytes", found);
+    assert!(lazy < found / 10, "{} bytes, {} bytes", lazy, found);
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn leading_changes_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    // the initial values are dumped before the first timestamp, so they are at time 0.
+    assert_eq!(
+        merge_data(&["tests/leading_changes.vcd", "--reorder"])?,
+        "#0\n0!\n1\"\n#3\n0\"\n#5\n1!\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn leading_changes() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge_data(&["tests/leading_changes.vcd"])?,
+        "#0\n0!\n1\"\n#5\n1!\n#3\n0\"\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,13 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn limit_sections() -> Result<(), Box<dyn std::error::Error>> {
+    let data = merge_data(&["tests/test1.vcd", "--reorder", "--limit-sections", "1"])?;
+
+    // The first section by start time of test1.vcd is the one starting at `#0`.
+    assert_eq!(data, "#0\n1!\n");
+
+    Ok(())
+}
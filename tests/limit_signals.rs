@@ -0,0 +1,33 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn limit_signals() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[
+        "tests/nested.vcd",
+        "tests/integer_parameter.vcd",
+        "--limit-signals",
+        "2",
+    ])?;
+    let (header, data) = content.split_once("$enddefinitions $end\n").unwrap();
+
+    // top.cpu.pc and all signals of the second input are left out, with the scope `counter`.
+    assert_eq!(
+        header,
+        "\
+$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! clk $end
+$scope module cpu $end
+$scope module alu $end
+$var wire 8 \" result $end
+$upscope $end
+$upscope $end
+$upscope $end
+"
+    );
+    assert_eq!(data, "#0\n0!\nb0 \"\n#1\n1!\nb101 \"\n#5\n#10\n");
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn max_time() -> Result<(), Box<dyn std::error::Error>> {
+    // 5.2ms is between the timestamps 10 and 11, in units of 500us.
+    let inputs = ["tests/test1.vcd", "tests/test2.vcd", "--reorder"];
+    let data = merge_data(&[&inputs[..], &["--max-time", "5200us"]].concat())?;
+    let expected = merge_data(&inputs)?;
+
+    let end = expected.find("#11\n").unwrap();
+    assert_eq!(data, expected[..end]);
+    assert!(data.ends_with("#10\n1!\nb01 $\n1#\n"));
+
+    Ok(())
+}
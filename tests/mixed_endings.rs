@@ -0,0 +1,32 @@
+mod common;
+
+use common::merge_data;
+
+const EXPECTED_DATA: &str = "\
+#5
+1!
+b101 \"
+r1.5 #
+#7
+0!
+b0 \"
+r0 #
+#9
+1!
+";
+
+#[test]
+fn mixed_endings() -> Result<(), Box<dyn std::error::Error>> {
+    // some lines of the file end in CRLF, and others only in LF.
+    assert_eq!(merge_data(&["tests/mixed_endings.vcd"])?, EXPECTED_DATA);
+    Ok(())
+}
+
+#[test]
+fn mixed_endings_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge_data(&["tests/mixed_endings.vcd", "--reorder"])?,
+        EXPECTED_DATA
+    );
+    Ok(())
+}
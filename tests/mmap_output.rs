@@ -0,0 +1,15 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn mmap_output() -> Result<(), Box<dyn std::error::Error>> {
+    let inputs = ["tests/test1.vcd", "tests/test2.vcd", "--reorder"];
+    let buffered = merge(&inputs)?;
+    let mmap = merge(&[&inputs[..], &["--mmap-output"]].concat())?;
+
+    assert_eq!(buffered, std::fs::read_to_string("tests/expected.vcd")?);
+    assert_eq!(mmap, buffered);
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn no_changes() -> Result<(), Box<dyn std::error::Error>> {
+    // there is nothing to merge after the header, so the progress bar is empty.
+    assert_eq!(merge_data(&["tests/no_changes.vcd"])?, "#0\n");
+    Ok(())
+}
+
+#[test]
+fn no_changes_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(merge_data(&["tests/no_changes.vcd", "--reorder"])?, "");
+    Ok(())
+}
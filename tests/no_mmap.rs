@@ -0,0 +1,15 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn no_mmap() -> Result<(), Box<dyn std::error::Error>> {
+    let inputs = ["tests/test1.vcd", "tests/test2.vcd", "--reorder"];
+    let mapped = merge(&inputs)?;
+    let read = merge(&[&inputs[..], &["--no-mmap"]].concat())?;
+
+    assert_eq!(read, std::fs::read_to_string("tests/expected.vcd")?);
+    assert_eq!(read, mapped);
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+mod common;
+
+use common::run;
+use predicates::prelude::*;
+
+#[test]
+fn no_progress() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&[
+        "tests/test1.vcd",
+        "tests/test2.vcd",
+        "--reorder",
+        "--no-progress",
+    ])?;
+    assert
+        .success()
+        .stdout(predicate::str::contains("[3/3] merging"))
+        .stdout(predicate::str::contains("\r").not())
+        .stderr(predicate::str::contains("\r").not());
+
+    Ok(())
+}
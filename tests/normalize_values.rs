@@ -0,0 +1,21 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn normalize_values() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge_data(&["tests/mixed_case.vcd", "--normalize-values"])?,
+        "#0\nx!\nbzx10 \"\nr1.5 #\n#1\nz!\nbxz01 \"\n#2\n1!\nbx0z1 \"\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn normalize_values_upper() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge_data(&["tests/mixed_case.vcd", "--normalize-values=upper"])?,
+        "#0\nX!\nbZX10 \"\nr1.5 #\n#1\nZ!\nbXZ01 \"\n#2\n1!\nbX0Z1 \"\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge;
+
+#[test]
+fn padded_width() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let input = dir.child("in.vcd");
+    input.write_str(
+        "$timescale 1 ns $end\n\
+         $scope module a $end\n\
+         $var wire 08 ! x $end\n\
+         $var\twire  \t0001 \" y $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n\
+         b00000000 !\n\
+         0\"\n",
+    )?;
+
+    // the widths are written without the leading zeros or the extra whitespace.
+    let content = merge(&[input.path()])?;
+    assert!(content.contains("$var wire 8 ! x $end\n$var wire 1 \" y $end\n"));
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use vcd_merger::{parse_headers, plan_sections, Header, ParseOptions};
+
+#[test]
+fn plan_sections_reversed() -> Result<(), Box<dyn std::error::Error>> {
+    let mut headers = Header::default();
+    let vcds = parse_headers(
+        ["tests/test1.vcd"].iter(),
+        &mut headers,
+        &ParseOptions::default(),
+    )?;
+
+    let plan = plan_sections(&vcds)?;
+
+    // `#10` to `#14` are sorted, followed by each timestamp down to `#0`, with `#8` repeated.
+    let time_ranges = plan.iter().map(|s| s.time_range).collect::<Vec<_>>();
+    assert_eq!(
+        time_ranges,
+        [
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (4, 4),
+            (5, 5),
+            (6, 6),
+            (7, 7),
+            (8, 8),
+            (9, 9),
+            (10, 14)
+        ]
+    );
+
+    let file = std::fs::read("tests/test1.vcd")?;
+    for section in &plan {
+        assert_eq!(section.input, 0);
+        assert_eq!(section.path.to_str(), Some("tests/test1.vcd"));
+        assert_eq!(file[section.range.start], b'#');
+    }
+    assert_eq!(plan[10].range.end, plan[9].range.start);
+    assert_eq!(plan[0].range.end, file.len());
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::vcd_merger;
+
+#[test]
+fn preview() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    let assert = vcd_merger()
+        .current_dir(dir.path())
+        .arg(std::fs::canonicalize("tests/test1.vcd")?)
+        .arg(std::fs::canonicalize("tests/test2.vcd")?)
+        .arg("--reorder")
+        .arg("--preview")
+        .arg("10")
+        .assert()
+        .success();
+
+    // the progress messages go to stderr, leaving only the preview in stdout.
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    assert!(stderr.contains("timescale set to"));
+    assert!(stderr.contains("previewing 10 lines\n"));
+    let preview = String::from_utf8(assert.get_output().stdout.clone())?;
+    let expected = std::fs::read_to_string("tests/expected.vcd")?;
+    let expected = expected.lines().take(10).collect::<Vec<_>>();
+    assert_eq!(preview.lines().collect::<Vec<_>>(), expected);
+    assert!(preview.starts_with("$timescale"));
+
+    // no output is written.
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use common::run;
+use predicates::prelude::*;
+
+#[test]
+fn profile_sections() -> Result<(), Box<dyn std::error::Error>> {
+    // test1.vcd jumps back in time often, so most of its sections have a single timestamp.
+    let (assert, _) = run(&["tests/test1.vcd", "--reorder", "--profile-sections"])?;
+    assert.success().stdout(predicate::str::contains(
+        "\
+tests/test1.vcd: 11 sections
+           8 up to 8 bytes
+           2 up to 16 bytes
+           1 up to 64 bytes
+",
+    ));
+
+    Ok(())
+}
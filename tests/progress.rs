@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use vcd_merger::{merge, ParseOptions, Phase, Progress, WriteOptions};
+
+#[test]
+fn phases_in_order() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_fs::NamedTempFile::new("output.vcd")?;
+    let inputs = [
+        PathBuf::from("tests/test1.vcd"),
+        PathBuf::from("tests/test2.vcd"),
+    ];
+
+    let mut reports: Vec<Progress> = Vec::new();
+    merge(
+        &inputs,
+        output.path(),
+        &ParseOptions::default(),
+        &WriteOptions::default(),
+        2,
+        |progress| reports.push(progress),
+    )?;
+
+    // phases are reported in order, and each one ends complete.
+    assert!(reports.windows(2).all(|w| w[0].phase <= w[1].phase));
+    for phase in [Phase::Gathering, Phase::Finding, Phase::Merging] {
+        let last = reports.iter().rev().find(|p| p.phase == phase).unwrap();
+        assert_eq!(last.bytes, last.total);
+        assert!(last.total > 0);
+        assert_eq!(last.fraction(), 1.0);
+    }
+    assert!(reports.iter().all(|p| p.bytes <= p.total));
+
+    let content = std::fs::read_to_string(output.path())?;
+    assert!(content.contains("$enddefinitions $end\n"));
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::run;
+
+/// The phase, bytes and total of a progress line like
+/// `{"phase":"merging","bytes":1024,"total":4096}`.
+fn parse_progress(line: &str) -> (String, u64, u64) {
+    let fields = line
+        .strip_prefix("{\"phase\":\"")
+        .and_then(|x| x.strip_suffix('}'))
+        .unwrap_or_else(|| panic!("invalid progress line {:?}", line));
+    let (phase, rest) = fields.split_once("\",\"bytes\":").unwrap();
+    let (bytes, total) = rest.split_once(",\"total\":").unwrap();
+    (
+        phase.to_string(),
+        bytes.parse().unwrap(),
+        total.parse().unwrap(),
+    )
+}
+
+#[test]
+fn progress_json() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&[
+        "tests/test1.vcd",
+        "tests/test2.vcd",
+        "--reorder",
+        "--progress-json",
+    ])?;
+    let assert = assert.success();
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    let progress = stderr.lines().map(parse_progress).collect::<Vec<_>>();
+
+    let phases = progress.iter().map(|(phase, ..)| phase.as_str());
+    let mut phases = phases.collect::<Vec<_>>();
+    phases.dedup();
+    assert_eq!(phases, ["finding", "merging"]);
+
+    assert!(progress.iter().all(|&(_, bytes, total)| bytes <= total));
+    for phase in ["finding", "merging"] {
+        let last = progress.iter().rev().find(|(p, ..)| p == phase).unwrap();
+        assert_eq!(last.1, last.2);
+        assert!(last.2 > 0);
+    }
+
+    Ok(())
+}
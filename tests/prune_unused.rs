@@ -0,0 +1,24 @@
+mod common;
+
+use common::run;
+use predicates::prelude::*;
+
+#[test]
+fn prune_unused() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&["tests/unused.vcd", "--prune-unused"])?;
+    assert
+        .success()
+        .stdout(predicate::str::contains("2 unused signals pruned"));
+
+    // `idle` and `sub.unused` never change, and `sub` becomes empty.
+    assert!(content.contains(
+        "$scope module top $end\n\
+         $var wire 1 ! clk $end\n\
+         $var wire 1 $ data $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n",
+    ));
+    assert!(content.contains("#10\n0!\nb1 $\n"));
+
+    Ok(())
+}
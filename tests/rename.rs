@@ -0,0 +1,61 @@
+mod common;
+
+use common::{merge, run};
+use predicates::prelude::*;
+
+#[test]
+fn rename() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[
+        "tests/same_start1.vcd",
+        "tests/same_start2.vcd",
+        "--rename",
+        "b.clk=b.clock",
+    ])?;
+
+    assert!(content.contains(
+        "$scope module a $end\n\
+         $var wire 1 ! clk $end\n\
+         $upscope $end\n\
+         $scope module b $end\n\
+         $var wire 1 \" clock $end\n\
+         $upscope $end\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn rename_to_unify() -> Result<(), Box<dyn std::error::Error>> {
+    // `a.clock` is renamed to the `a.clk` of the existing file, so they are the same signal.
+    let content = merge(&[
+        "--append",
+        "tests/same_start1.vcd",
+        "tests/renamed.vcd",
+        "--rename",
+        "a.clock=a.clk",
+    ])?;
+
+    assert_eq!(
+        content,
+        "$timescale 1ns $end\n\
+         $scope module a $end\n\
+         $var wire 1 ! clk $end\n\
+         $upscope $end\n\
+         $scope module a $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #5\n1!\n#7\n0!\n#9\n1!\n#11\n0!\n",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rename_other_scope() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/same_start1.vcd", "--rename", "a.clk=b.clk"])?;
+    assert.failure().stderr(predicate::str::contains(
+        "a.clk and b.clk are not in the same scope",
+    ));
+
+    Ok(())
+}
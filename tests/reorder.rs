@@ -1,24 +1,12 @@
-use assert_cmd::prelude::*;
-use assert_fs::prelude::*;
-use predicates::prelude::*;
-use std::process::Command;
+mod common;
+
+use common::merge;
 
 #[test]
 fn reorder() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("vcd-merger")?;
-
-    let output = assert_fs::NamedTempFile::new("out.vcd")?;
-
-    cmd.arg("tests/test1.vcd")
-        .arg("tests/test2.vcd")
-        .arg("-o")
-        .arg(output.path())
-        .arg("--reorder");
-
-    cmd.assert().success();
+    let content = merge(&["tests/test1.vcd", "tests/test2.vcd", "--reorder"])?;
 
-    output.assert(predicate::path::exists());
-    output.assert(predicate::path::eq_file("tests/expected.vcd"));
+    assert_eq!(content, std::fs::read_to_string("tests/expected.vcd")?);
 
     Ok(())
 }
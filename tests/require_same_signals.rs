@@ -0,0 +1,32 @@
+mod common;
+
+use common::{merge, run};
+use predicates::prelude::*;
+
+#[test]
+fn require_same_signals_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&[
+        "tests/same_start1.vcd",
+        "tests/same_start2.vcd",
+        "--require-same-signals",
+    ])?;
+    assert.code(8).stderr(predicate::str::contains(
+        "The inputs don't declare the same signals:\n  \
+             a.clk is missing in tests/same_start2.vcd\n  \
+             b.clk is missing in tests/same_start1.vcd\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn require_same_signals_match() -> Result<(), Box<dyn std::error::Error>> {
+    // both declare only `a.clk`.
+    merge(&[
+        "tests/same_start1.vcd",
+        "tests/conflict.vcd",
+        "--require-same-signals",
+    ])?;
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn rescan_declarations() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[
+        "tests/test1.vcd",
+        "tests/rescan.vcd",
+        "--reorder",
+        "--rescan-declarations",
+    ])?;
+
+    // `late.data` is declared mid-stream, after `top.clk` was mapped to `#`.
+    assert!(content.contains(
+        "$scope module late $end\n$var wire 4 $ data $end\n$upscope $end\n$enddefinitions $end\n",
+    ));
+    assert!(content.contains("#10\n0#\nb1010 $\n"));
+    assert!(content.contains("#15\n1#\nb0101 $\n"));
+
+    Ok(())
+}
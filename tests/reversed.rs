@@ -0,0 +1,29 @@
+mod common;
+
+use common::{data, run};
+use predicates::prelude::*;
+
+fn merge(args: &[&str]) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let inputs = ["tests/same_start2.vcd", "tests/reversed.vcd"];
+    let (assert, content) = run(&[&inputs[..], args].concat())?;
+    let stderr = String::from_utf8(assert.success().get_output().stderr.clone())?;
+    Ok((data(&content).to_string(), stderr))
+}
+
+#[test]
+fn reversed_input() -> Result<(), Box<dyn std::error::Error>> {
+    let (data, stderr) = merge(&["--reorder"])?;
+    assert_eq!(data, "#1\n0\"\n#5\n0!\n1\"\n#6\n0\"\n#7\n1!\n#9\n1\"\n");
+    assert!(stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn reversed_input_without_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/reversed.vcd"])?;
+    assert.success().stderr(predicate::str::contains(
+        "warning: the timestamps of tests/reversed.vcd are not sorted",
+    ));
+
+    Ok(())
+}
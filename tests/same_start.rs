@@ -0,0 +1,34 @@
+mod common;
+
+use common::merge_data;
+
+const EXPECTED_DATA: &str = "\
+#5
+1!
+0\"
+#7
+0!
+1\"
+";
+
+#[test]
+fn same_start() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge_data(&["tests/same_start1.vcd", "tests/same_start2.vcd"])?,
+        EXPECTED_DATA
+    );
+    Ok(())
+}
+
+#[test]
+fn same_start_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(
+        merge_data(&[
+            "tests/same_start1.vcd",
+            "tests/same_start2.vcd",
+            "--reorder"
+        ])?,
+        EXPECTED_DATA
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use vcd_merger::{find_sections, parse_headers, BitOrder, Header, ParseOptions, Samples};
+
+#[test]
+fn sample() {
+    let mut headers = Header::default();
+    let vcds = parse_headers(
+        ["tests/test1.vcd", "tests/test2.vcd"].iter(),
+        &mut headers,
+        &ParseOptions::default(),
+    )
+    .unwrap();
+    let sections = find_sections(&vcds, 1, |_| {}).unwrap();
+    let samples = Samples::new(&vcds, &sections).unwrap();
+
+    // test1.vcd is in ms, and the output in 500us.
+    assert_eq!(samples.sample("test1.bar", 17), None);
+    assert_eq!(samples.sample("test1.bar", 18), Some("x"));
+    assert_eq!(samples.sample("test1.bar", 27), Some("x"));
+    assert_eq!(samples.sample("test1.bar", 28), Some("0"));
+    assert_eq!(samples.sample("test1.bar", 1000), Some("0"));
+
+    // the inputs are not sorted, but the samples are.
+    assert_eq!(samples.sample("test2.foo", 3), None);
+    assert_eq!(samples.sample("test2.foo", 5), Some("bxx"));
+    assert_eq!(samples.sample("test2.foo", 9), Some("b10"));
+    assert_eq!(samples.sample("test2.same_foo", 9), Some("b10"));
+    assert_eq!(samples.sample("test2.foo", 100), Some("b01"));
+
+    assert_eq!(samples.sample("test1.clk", 0), Some("1"));
+    assert_eq!(samples.sample("test1.missing", 10), None);
+}
+
+#[test]
+fn sample_bit_order() {
+    let mut headers = Header::default();
+    let options = ParseOptions {
+        bit_orders: vec![BitOrder::Lsb],
+        ..ParseOptions::default()
+    };
+    let vcds = parse_headers(["tests/lsb_bus.vcd"].iter(), &mut headers, &options).unwrap();
+    let sections = find_sections(&vcds, 1, |_| {}).unwrap();
+    let samples = Samples::new(&vcds, &sections).unwrap();
+
+    // the vectors are reversed, like in the output.
+    assert_eq!(samples.sample("top.bus", 4), Some("b0011"));
+    assert_eq!(samples.sample("top.bus", 6), Some("b1000"));
+    assert_eq!(samples.sample("top.clk", 6), Some("1"));
+}
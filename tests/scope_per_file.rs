@@ -0,0 +1,43 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn scope_per_file() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[
+        "tests/same_start1.vcd",
+        "tests/same_start2.vcd",
+        "--scope-per-file",
+    ])?;
+
+    assert!(content.contains(
+        "$scope module same_start1 $end\n\
+         $scope module a $end\n\
+         $var wire 1 ! clk $end\n\
+         $upscope $end\n\
+         $upscope $end\n\
+         $scope module same_start2 $end\n\
+         $scope module b $end\n\
+         $var wire 1 \" clk $end\n\
+         $upscope $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn scope_per_file_same_name() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[
+        "tests/same_start1.vcd",
+        "tests/same_start1.vcd",
+        "--scope-per-file",
+        "--allow-duplicate-inputs",
+    ])?;
+
+    assert!(content.contains("$scope module same_start1 $end\n"));
+    assert!(content.contains("$scope module same_start1_1 $end\n"));
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{merge, run};
+use predicates::prelude::*;
+
+#[test]
+fn per_input_filters() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&[
+        "tests/nested.vcd",
+        "tests/integer_parameter.vcd",
+        "--include",
+        "tests/nested.vcd:top.cpu.*",
+        "--include",
+        "./tests/integer_parameter.vcd:counter.c?unt",
+        "--exclude",
+        "*.result",
+    ])?;
+    let (header, data) = content.split_once("$enddefinitions $end\n").unwrap();
+
+    // top.clk is not included, and top.cpu.alu.result is excluded, leaving its scope empty.
+    assert_eq!(
+        header,
+        "\
+$timescale 1ns $end
+$scope module top $end
+$scope module cpu $end
+$var reg 16 ! pc $end
+$upscope $end
+$upscope $end
+$scope module counter $end
+$var integer 32 \" count $end
+$upscope $end
+"
+    );
+    assert_eq!(data, "#0\nb0 !\nb0 \"\n#1\nb1 !\n#5\nb1 \"\n#10\nb10 \"\n");
+
+    Ok(())
+}
+
+#[test]
+fn filter_unknown_input() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/nested.vcd", "--exclude", "tests/test1.vcd:*"])?;
+    assert.code(1).stderr(predicate::str::contains(
+        "--exclude: tests/test1.vcd is not an input",
+    ));
+
+    Ok(())
+}
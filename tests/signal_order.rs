@@ -0,0 +1,51 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{merge, run};
+
+#[test]
+fn signal_order() -> Result<(), Box<dyn std::error::Error>> {
+    let order = assert_fs::NamedTempFile::new("order.txt")?;
+    // top.cpu.alu.result is not listed, so it is written after the listed signals.
+    order.write_str("a.clk\ntop.cpu.pc\n\ntop.clk\n")?;
+
+    let content = merge(&[
+        "tests/nested.vcd",
+        "tests/same_start1.vcd",
+        "--signal-order",
+        order.path().to_str().unwrap(),
+    ])?;
+    let (header, _) = content.split_once("$enddefinitions $end\n").unwrap();
+    assert_eq!(
+        header,
+        "\
+$timescale 1ns $end
+$scope module a $end
+$var wire 1 $ clk $end
+$upscope $end
+$scope module top $end
+$scope module cpu $end
+$var reg 16 # pc $end
+$scope module alu $end
+$var wire 8 \" result $end
+$upscope $end
+$upscope $end
+$var wire 1 ! clk $end
+$upscope $end
+"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn missing_signal_order() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&[
+        "tests/nested.vcd",
+        "--signal-order",
+        "tests/missing_order.txt",
+    ])?;
+    assert.failure().code(2);
+
+    Ok(())
+}
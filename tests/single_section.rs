@@ -0,0 +1,57 @@
+use assert_fs::prelude::*;
+use vcd_merger::{file_sections, parse_headers, write_output, Header, ParseOptions, WriteOptions};
+
+#[test]
+fn single_section() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // a single timestamp with many changes is a single section.
+    let changes = 600_000;
+    let mut input = String::from(
+        "$timescale 1 ns $end\n$scope module a $end\n$var wire 1 ! clk $end\n\
+         $upscope $end\n$enddefinitions $end\n#0\n",
+    );
+    for i in 0..changes {
+        input.push_str(if i % 2 == 0 { "0!\n" } else { "1!\n" });
+    }
+    let input_file = dir.child("in.vcd");
+    input_file.write_str(&input)?;
+    let output = dir.child("out.vcd");
+
+    let mut headers = Header::default();
+    let vcds = parse_headers(
+        [input_file.path()].iter(),
+        &mut headers,
+        &ParseOptions::default(),
+    )?;
+    let sections = file_sections(&vcds)?;
+    assert_eq!(sections.len(), 1);
+    let total = sections[0].section.len() as u64;
+
+    // progress is reported while writing the section, not only after it.
+    let mut reports = Vec::new();
+    write_output(
+        output.path(),
+        headers,
+        &vcds,
+        sections,
+        &WriteOptions::default(),
+        |progress| reports.push(progress),
+    )?;
+    assert!(
+        reports.len() >= 2,
+        "progress reported {} times",
+        reports.len()
+    );
+    assert!(reports.windows(2).all(|w| w[0] < w[1]));
+    assert!(reports.iter().all(|&progress| progress <= total));
+
+    let content = std::fs::read_to_string(output.path())?;
+    let data = content.split("$enddefinitions $end\n").nth(1).unwrap();
+    assert_eq!(
+        data.len(),
+        input.split("$enddefinitions $end\n").nth(1).unwrap().len()
+    );
+
+    Ok(())
+}
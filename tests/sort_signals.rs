@@ -0,0 +1,35 @@
+mod common;
+
+use common::{data, merge};
+
+#[test]
+fn sort_signals() -> Result<(), Box<dyn std::error::Error>> {
+    let inputs = ["tests/nested.vcd", "tests/same_start1.vcd"];
+    let sorted = merge(&[inputs[0], inputs[1], "--sort-signals"])?;
+    let unsorted = merge(&inputs)?;
+
+    let (header, _) = sorted.split_once("$enddefinitions $end\n").unwrap();
+    assert_eq!(
+        header,
+        "\
+$timescale 1ns $end
+$scope module a $end
+$var wire 1 $ clk $end
+$upscope $end
+$scope module top $end
+$var wire 1 ! clk $end
+$scope module cpu $end
+$scope module alu $end
+$var wire 8 \" result $end
+$upscope $end
+$var reg 16 # pc $end
+$upscope $end
+$upscope $end
+"
+    );
+
+    // only the declarations are reordered, the signals keep their identifiers.
+    assert_eq!(data(&sorted), data(&unsorted));
+
+    Ok(())
+}
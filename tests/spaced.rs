@@ -0,0 +1,29 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use common::{data, merge, vcd_merger};
+
+#[test]
+fn spaced() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let spaced = dir.child("spaced.vcd");
+
+    vcd_merger()
+        .arg("tests/same_start1.vcd")
+        .arg("tests/same_start2.vcd")
+        .arg("-o")
+        .arg(spaced.path())
+        .arg("--spaced")
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(spaced.path())?;
+    assert_eq!(data(&content), "\n#5\n1!\n0\"\n\n#7\n0!\n1\"\n");
+
+    // the spaced output is still a valid input, equivalent to the output without spaces.
+    let content = merge(&[spaced.path().to_str().unwrap(), "--reorder"])?;
+    assert_eq!(data(&content), "#5\n1!\n0\"\n#7\n0!\n1\"\n");
+
+    Ok(())
+}
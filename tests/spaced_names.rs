@@ -0,0 +1,18 @@
+mod common;
+
+use common::merge;
+
+#[test]
+fn spaced_names() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&["tests/test1.vcd", "tests/spaced_names.vcd"])?;
+
+    // only the identifiers are changed, the names keep their whitespace.
+    assert!(content.contains(
+        "$scope module s $end\n\
+         $var reg 4 # mem  [0:3] $end\n\
+         $var wire 1 $ \tdata\t$end\n\
+         $upscope $end\n",
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use common::vcd_merger;
+use predicates::prelude::*;
+
+#[test]
+fn split_by_scope() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = vcd_merger();
+
+    let dir = assert_fs::TempDir::new()?;
+
+    cmd.arg("tests/two_scopes.vcd")
+        .arg("--split-by-scope")
+        .arg("-o")
+        .arg(dir.path());
+
+    cmd.assert().success();
+
+    // each output only declares the signals of its scope, with their value changes.
+    let cpu = std::fs::read_to_string(dir.path().join("cpu.vcd"))?;
+    assert!(cpu.contains("$scope module cpu $end"));
+    assert!(cpu.contains("$scope module alu $end"));
+    assert!(!cpu.contains("mem"));
+    assert!(cpu.ends_with("#10\n0!\nb101 \"\n"));
+
+    let mem = std::fs::read_to_string(dir.path().join("mem.vcd"))?;
+    // the identifiers of each file start from `!`.
+    assert!(mem.contains("$scope module mem $end\n$var wire 1 ! we $end\n$upscope $end\n"));
+    assert!(!mem.contains("cpu"));
+    assert!(mem.contains("#0\n0!\n#5\n1!\n"));
+
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn split_by_scope_single_input() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = vcd_merger();
+
+    let dir = assert_fs::TempDir::new()?;
+
+    cmd.arg("tests/two_scopes.vcd")
+        .arg("tests/test1.vcd")
+        .arg("--split-by-scope")
+        .arg("-o")
+        .arg(dir.path());
+
+    cmd.assert().code(1);
+
+    Ok(())
+}
+
+#[test]
+fn split_by_scope_names() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // scopes named like paths, a var outside of any scope, and unsorted timestamps.
+    let input = dir.child("input.vcd");
+    input.write_str(
+        "$timescale 1ns $end\n$var wire 1 ! top_clk $end\n\
+         $scope module .. $end\n$var wire 1 \" a $end\n$upscope $end\n\
+         $scope module a/b $end\n$var wire 1 # b $end\n$upscope $end\n\
+         $scope module a_b $end\n$var wire 1 $ c $end\n$upscope $end\n\
+         $enddefinitions $end\n#5\n1!\n1\"\n1#\n1$\n#0\n0!\n0\"\n0#\n0$\n",
+    )?;
+    let output = dir.child("out");
+
+    vcd_merger()
+        .arg(input.path())
+        .arg("--split-by-scope")
+        .arg("-o")
+        .arg(output.path())
+        .assert()
+        .success()
+        .stderr(
+            predicate::str::contains(
+                "warning: 1 vars declared outside of any scope are not written to any file",
+            )
+            .and(predicate::str::contains("are not sorted").count(1)),
+        );
+
+    let mut files = std::fs::read_dir(output.path())?
+        .map(|entry| entry.map(|entry| entry.file_name().into_string().unwrap()))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.sort();
+    assert_eq!(files, ["_...vcd", "a_b.vcd", "a_b_2.vcd"]);
+    assert!(dir.child("a").read_dir().is_err());
+
+    let a_b = std::fs::read_to_string(output.child("a_b.vcd").path())?;
+    assert!(a_b.contains("$scope module a/b $end\n$var wire 1 ! b $end\n$upscope $end\n"));
+    assert!(!a_b.contains("top_clk"));
+
+    Ok(())
+}
+
+#[test]
+fn split_by_scope_stdin() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    vcd_merger()
+        .arg("-")
+        .arg("--split-by-scope")
+        .arg("-o")
+        .arg(dir.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "--split-by-scope can't read the input from the standard input",
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::vcd_merger;
+use std::fmt::Write;
+
+#[test]
+fn split_size() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // ten timestamps of 6 bytes each, like `#0\n0!\n`, so a chunk ends every two timestamps.
+    let header = "$timescale 1 ns $end\n$scope module top $end\n$var wire 1 ! clk $end\n\
+                  $upscope $end\n$enddefinitions $end\n";
+    let mut input = header.to_string();
+    for i in 0..10 {
+        write!(input, "#{}\n{}!\n", i, i % 2)?;
+    }
+    let input_path = dir.path().join("input.vcd");
+    std::fs::write(&input_path, input)?;
+
+    let output = dir.path().join("out.vcd");
+    vcd_merger()
+        .arg(&input_path)
+        .args(["--split-size", "12", "-o"])
+        .arg(&output)
+        .assert()
+        .success();
+
+    // the merged file is replaced by the chunks.
+    assert!(!output.exists());
+
+    let manifest = std::fs::read_to_string(dir.path().join("out.manifest.json"))?;
+    assert_eq!(
+        manifest,
+        "{\"chunks\":[\
+         {\"file\":\"out.0.vcd\",\"time_range\":[0,1]},\
+         {\"file\":\"out.1.vcd\",\"time_range\":[2,3]},\
+         {\"file\":\"out.2.vcd\",\"time_range\":[4,5]},\
+         {\"file\":\"out.3.vcd\",\"time_range\":[6,7]},\
+         {\"file\":\"out.4.vcd\",\"time_range\":[8,9]}]}\n"
+    );
+
+    // each chunk has the timestamps of its range, and starts with the value before it.
+    for i in 0..5 {
+        let chunk = std::fs::read_to_string(dir.path().join(format!("out.{}.vcd", i)))?;
+        let (chunk_header, data) = chunk.split_once("$enddefinitions $end\n").unwrap();
+        assert!(chunk_header.contains("$var wire 1 ! clk $end"));
+        let timestamps = data
+            .lines()
+            .filter_map(|line| line.strip_prefix('#'))
+            .map(|time| time.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(timestamps, [2 * i, 2 * i + 1]);
+        if i == 0 {
+            assert_eq!(data, "#0\n0!\n#1\n1!\n");
+        } else {
+            assert!(data.starts_with(&format!("#{}\n$dumpvars\n1!\n$end\n0!\n", 2 * i)));
+        }
+    }
+
+    Ok(())
+}
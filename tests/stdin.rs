@@ -0,0 +1,26 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::vcd_merger;
+
+#[test]
+fn stdin_input() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_fs::NamedTempFile::new("out.vcd")?;
+
+    vcd_merger()
+        .arg("-")
+        .arg("tests/test2.vcd")
+        .arg("-o")
+        .arg(output.path())
+        .arg("--reorder")
+        .stdin(std::fs::File::open("tests/test1.vcd")?)
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read_to_string(output.path())?,
+        std::fs::read_to_string("tests/expected.vcd")?
+    );
+
+    Ok(())
+}
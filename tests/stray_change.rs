@@ -0,0 +1,71 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{run, vcd_merger};
+use predicates::prelude::*;
+
+#[test]
+fn stray_change() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&["tests/stray_change.vcd"])?;
+    assert.success().stderr(predicate::str::contains(
+        "warning: skipped stray value changes in 2 places among the declarations of \
+         tests/stray_change.vcd",
+    ));
+
+    // the declarations after the stray value changes are still parsed, and these are skipped.
+    assert_eq!(
+        content,
+        "\
+$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! a $end
+$var wire 1 \" b $end
+$var wire 2 # c $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+1\"
+b10 #
+#5
+1!
+"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stray_change_validate() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = vcd_merger();
+
+    cmd.arg("tests/stray_change.vcd").arg("--validate-only");
+
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "tests/stray_change.vcd: line 4: value change among the declarations",
+        ))
+        .stdout(predicate::str::contains(
+            "tests/stray_change.vcd: line 6: value change among the declarations",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn stray_change_non_ascii() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("in.vcd")?;
+    std::fs::write(
+        input.path(),
+        b"$timescale 1ns $end\n$var wire 1 ! a $end\n\xC3\xA9\n\xFF\n$var wire 1 \" b $end\n\
+          $enddefinitions $end\n#0x0\n1!\n",
+    )?;
+
+    let (assert, _) = run(&[input.path()])?;
+    assert.success().stderr(predicate::str::contains(
+        "warning: skipped stray value changes in 1 places",
+    ));
+
+    Ok(())
+}
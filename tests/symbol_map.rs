@@ -0,0 +1,64 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{run, vcd_merger};
+
+#[test]
+fn symbol_map() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let map = dir.path().join("map.tsv");
+    let first = dir.path().join("first.vcd");
+    let second = dir.path().join("second.vcd");
+
+    vcd_merger()
+        .arg("tests/nested.vcd")
+        .arg("tests/integer_parameter.vcd")
+        .arg("-o")
+        .arg(&first)
+        .arg("--symbol-map")
+        .arg(&map)
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read_to_string(&map)?,
+        "name\tid\ntop.clk\t!\ntop.cpu.alu.result\t\"\ntop.cpu.pc\t#\ncounter.count\t$\n\
+         counter.DEPTH\t%\n"
+    );
+
+    // with the inputs in a different order, and a new input, the mapped signals keep their
+    // identifiers, and the new signal receives one after them.
+    vcd_merger()
+        .arg("tests/same_start1.vcd")
+        .arg("tests/integer_parameter.vcd")
+        .arg("tests/nested.vcd")
+        .arg("-o")
+        .arg(&second)
+        .arg("--symbol-map-in")
+        .arg(&map)
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(&second)?;
+    for var in [
+        "$var wire 1 & clk $end",
+        "$var integer 32 $ count $end",
+        "$var parameter 32 % DEPTH $end",
+        "$var wire 1 ! clk $end",
+        "$var wire 8 \" result $end",
+        "$var reg 16 # pc $end",
+    ] {
+        assert!(content.contains(var), "{} not found in:\n{}", var, content);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_symbol_map() -> Result<(), Box<dyn std::error::Error>> {
+    // a VCD is not a symbol map.
+    let (assert, _) = run(&["tests/nested.vcd", "--symbol-map-in", "tests/nested.vcd"])?;
+    assert.failure().code(2);
+
+    Ok(())
+}
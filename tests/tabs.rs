@@ -0,0 +1,27 @@
+mod common;
+
+use common::merge_data;
+
+const EXPECTED_DATA: &str = "\
+#0
+0!
+b0000 \"
+r0 #
+#5
+1!
+b1010 \"
+r2.5 #
+";
+
+#[test]
+fn tabs() -> Result<(), Box<dyn std::error::Error>> {
+    // the vector and real changes separate the value and identifier with a tab.
+    assert_eq!(merge_data(&["tests/tabs.vcd"])?, EXPECTED_DATA);
+    Ok(())
+}
+
+#[test]
+fn tabs_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    assert_eq!(merge_data(&["tests/tabs.vcd", "--reorder"])?, EXPECTED_DATA);
+    Ok(())
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use common::{merge, vcd_merger};
+
+#[test]
+fn threads() -> Result<(), Box<dyn std::error::Error>> {
+    let inputs = ["tests/test1.vcd", "tests/test2.vcd", "--reorder"];
+    let single = merge(&[&inputs[..], &["--threads", "1"]].concat())?;
+    let default = merge(&inputs)?;
+
+    assert_eq!(single, std::fs::read_to_string("tests/expected.vcd")?);
+    assert_eq!(default, single);
+
+    Ok(())
+}
+
+#[test]
+fn threads_keep_section_order() -> Result<(), Box<dyn std::error::Error>> {
+    let dump = |threads: &str| -> Result<String, Box<dyn std::error::Error>> {
+        let dir = assert_fs::TempDir::new()?;
+        let sections = dir.child("sections.tsv");
+        vcd_merger()
+            .arg("tests/reversed.vcd")
+            .arg("tests/test1.vcd")
+            .arg("tests/early_dumpvars.vcd")
+            .arg("tests/test2.vcd")
+            .arg("-o")
+            .arg(dir.child("out.vcd").path())
+            .arg("--reorder")
+            .arg("--threads")
+            .arg(threads)
+            .arg("--dump-sections")
+            .arg(sections.path())
+            .assert()
+            .success();
+        Ok(std::fs::read_to_string(sections.path())?)
+    };
+
+    let single = dump("1")?;
+    for threads in ["2", "3", "4"] {
+        assert_eq!(dump(threads)?, single);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::{data, run};
+use predicates::prelude::*;
+
+fn merge(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let inputs = ["tests/same_start1.vcd", "tests/same_start2.vcd"];
+    let (assert, content) = run(&[&inputs[..], args].concat())?;
+    assert.success().stderr(predicate::str::contains("warning"));
+    Ok(data(&content).to_string())
+}
+
+#[test]
+fn negative_time_offset_clamp() -> Result<(), Box<dyn std::error::Error>> {
+    let expected = "#0\n1!\n#1\n0!\n#5\n0\"\n#7\n1\"\n";
+    assert_eq!(merge(&["--time-offset", "0=-6"])?, expected);
+    assert_eq!(merge(&["--time-offset", "0=-6", "--reorder"])?, expected);
+    Ok(())
+}
+
+#[test]
+fn negative_time_offset_drop() -> Result<(), Box<dyn std::error::Error>> {
+    let expected = "#1\n0!\n#5\n0\"\n#7\n1\"\n";
+    let args = ["--time-offset", "0=-6", "--negative-time", "drop"];
+    assert_eq!(merge(&args)?, expected);
+    assert_eq!(merge(&[&args[..], &["--reorder"]].concat())?, expected);
+    Ok(())
+}
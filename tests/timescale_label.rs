@@ -0,0 +1,55 @@
+mod common;
+
+use common::{merge, run};
+use predicates::prelude::*;
+
+#[test]
+fn timescale_label() -> Result<(), Box<dyn std::error::Error>> {
+    let default = std::fs::read_to_string("tests/expected.vcd")?;
+    assert!(default.contains("$timescale 500us $end\n"));
+
+    // only the spelling of the timescale changes.
+    for (label, timescale) in [
+        ("ns", "500000ns"),
+        ("auto-spaced", "500 us"),
+        ("ps-spaced", "500000000 ps"),
+    ] {
+        let content = merge(&[
+            "tests/test1.vcd",
+            "tests/test2.vcd",
+            "--reorder",
+            "--output-timescale-label",
+            label,
+        ])?;
+        let expected = default.replace("500us", timescale);
+        assert_eq!(content, expected, "--output-timescale-label {}", label);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn timescale_label_coarser_unit() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&[
+        "tests/test1.vcd",
+        "tests/test2.vcd",
+        "--output-timescale-label",
+        "ms",
+    ])?;
+
+    assert.success().stderr(predicate::str::contains(
+        "warning: the output timescale is not a whole number of ms",
+    ));
+
+    assert!(content.contains("$timescale 500us $end\n"));
+
+    Ok(())
+}
+
+#[test]
+fn timescale_label_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/test1.vcd", "--output-timescale-label", "minutes"])?;
+    assert.code(2);
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{data, run};
+use predicates::prelude::*;
+
+#[test]
+fn timescale_ratio() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&["tests/seconds.vcd", "tests/femtoseconds.vcd"])?;
+    assert.success().stderr(predicate::str::contains(
+        "warning: the output timescale of 1fs is 1000000000000000 times finer than the timescale \
+         of tests/seconds.vcd, whose timestamps above 18446 overflow and fail the merge; pass \
+         `--timescale 1ns` for a coarser output timescale",
+    ));
+
+    assert_eq!(
+        data(&content),
+        "#0\n0!\n0\"\n#5\n1\"\n#2000000000000000\n1!\n"
+    );
+
+    // following the suggestion, the finer input is rounded down instead.
+    let (assert, content) = run(&[
+        "tests/seconds.vcd",
+        "tests/femtoseconds.vcd",
+        "--timescale",
+        "1ns",
+    ])?;
+    assert
+        .success()
+        .stderr(predicate::str::contains(
+            "warning: the timescale of tests/femtoseconds.vcd is finer than the output timescale \
+             of 1ns, its timestamps are rounded down",
+        ))
+        .stderr(predicate::str::contains("times finer").not());
+
+    assert!(content.contains("$timescale 1ns $end"));
+    assert_eq!(data(&content), "#0\n0!\n0\"\n1\"\n#2000000000\n1!\n");
+
+    Ok(())
+}
+
+#[test]
+fn timescale_overflow() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // 20000 s is more than u64::MAX femtoseconds, so it can't be written in the output.
+    let input = dir.child("long.vcd");
+    input.write_str(
+        "$timescale 1 s $end\n$scope module slow $end\n$var wire 1 ! tick $end\n\
+         $upscope $end\n$enddefinitions $end\n#0\n0!\n#20000\n1!\n",
+    )?;
+
+    let (assert, _) = run(&[input.path().to_str().unwrap(), "tests/femtoseconds.vcd"])?;
+    assert
+        .code(9)
+        .stderr(
+            predicate::str::contains("timestamp #20000 of ").and(predicate::str::contains(
+                "overflows in the output timescale",
+            )),
+        );
+
+    Ok(())
+}
+
+#[test]
+fn timestamp_too_large() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // doesn't fit in 64 bits, even in the timescale of the input.
+    let input = dir.child("huge.vcd");
+    input.write_str(
+        "$timescale 1 ns $end\n$scope module top $end\n$var wire 1 ! clk $end\n\
+         $upscope $end\n$enddefinitions $end\n#0\n0!\n#99999999999999999999999\n1!\n",
+    )?;
+
+    let (assert, _) = run(&[input.path()])?;
+    assert.code(9).stderr(predicate::str::contains(
+        "timestamp #99999999999999999999999 of ",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_timestamp() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    let input = dir.child("invalid.vcd");
+    input.write_str(
+        "$timescale 1 ns $end\n$scope module top $end\n$var wire 1 ! clk $end\n\
+         $upscope $end\n$enddefinitions $end\n#0\n0!\n#12a\n1!\n",
+    )?;
+
+    let (assert, _) = run(&[input.path()])?;
+    assert
+        .code(9)
+        .stderr(predicate::str::contains("invalid timestamp #12a in "));
+
+    Ok(())
+}
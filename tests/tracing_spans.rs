@@ -0,0 +1,80 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use tracing::{
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+use vcd_merger::{find_sections, parse_headers, write_output, Header, ParseOptions, WriteOptions};
+
+/// A subscriber that records the name of each span created, in order.
+#[derive(Clone, Default)]
+struct Spans {
+    names: Arc<Mutex<Vec<&'static str>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.names.lock().unwrap().push(span.metadata().name());
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn tracing_spans() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_fs::NamedTempFile::new("out.vcd")?;
+    let spans = Spans::default();
+
+    tracing::subscriber::with_default(spans.clone(), || -> Result<(), vcd_merger::Error> {
+        let mut header = Header::default();
+        let inputs = ["tests/test1.vcd", "tests/test2.vcd"];
+        let vcds = parse_headers(inputs.iter(), &mut header, &ParseOptions::default())?;
+        let sections = find_sections(&vcds, 1, |_| {})?;
+        write_output(
+            output.path(),
+            header,
+            &vcds,
+            sections,
+            &WriteOptions::default(),
+            |_| {},
+        )
+    })?;
+
+    let names = spans.names.lock().unwrap();
+    let count = |name| names.iter().filter(|&&x| x == name).count();
+    assert_eq!(
+        names[..3],
+        ["gathering", "parse_file", "parse_file"],
+        "{:?}",
+        names
+    );
+    assert_eq!(count("gathering"), 1);
+    assert_eq!(count("parse_file"), 2);
+    assert_eq!(count("finding"), 1);
+    assert_eq!(count("find_file"), 2);
+    assert_eq!(count("merging"), 1);
+    assert!(count("write_section") > 0);
+
+    // the merge happens in this order.
+    let position = |name| names.iter().position(|&x| x == name).unwrap();
+    assert!(position("parse_file") < position("finding"));
+    assert!(position("find_file") < position("merging"));
+    assert!(position("merging") < position("write_section"));
+    Ok(())
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{run, vcd_merger};
+use predicates::prelude::*;
+
+#[test]
+fn trailing_tokens() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&["tests/trailing_tokens.vcd"])?;
+    assert.success().stderr(predicate::str::contains(
+        "warning: skipped tokens after $end in 2 places in the header of \
+         tests/trailing_tokens.vcd",
+    ));
+
+    // the declarations after `$upscope $end extra_token` are still parsed.
+    assert_eq!(
+        content,
+        "\
+$timescale 1ns $end
+$scope module top $end
+$var wire 1 ! a $end
+$scope module sub $end
+$var wire 1 \" b $end
+$upscope $end
+$var wire 1 # c $end
+$upscope $end
+$enddefinitions $end
+#0
+0!
+1\"
+0#
+#5
+1!
+"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn trailing_tokens_validate() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = vcd_merger();
+
+    cmd.arg("tests/trailing_tokens.vcd").arg("--validate-only");
+
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "tests/trailing_tokens.vcd: line 6: tokens after $end",
+        ))
+        .stdout(predicate::str::contains(
+            "tests/trailing_tokens.vcd: line 7: tokens after $end",
+        ));
+
+    Ok(())
+}
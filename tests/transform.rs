@@ -0,0 +1,38 @@
+use std::borrow::Cow;
+use vcd_merger::{
+    file_sections, parse_headers, write_output, Header, ParseOptions, SignalInfo, WriteOptions,
+};
+
+fn invert_clk<'a>(signal: &SignalInfo, value: &'a [u8]) -> Cow<'a, [u8]> {
+    match (signal.name.as_str(), value) {
+        ("a.clk", b"0") => Cow::Borrowed(b"1"),
+        ("a.clk", b"1") => Cow::Borrowed(b"0"),
+        _ => Cow::Borrowed(value),
+    }
+}
+
+#[test]
+fn transform() -> Result<(), Box<dyn std::error::Error>> {
+    let output = assert_fs::NamedTempFile::new("out.vcd")?;
+
+    let mut headers = Header::default();
+    let vcds = parse_headers(
+        ["tests/same_start1.vcd", "tests/same_start2.vcd"].iter(),
+        &mut headers,
+        &ParseOptions::default(),
+    )?;
+    let sections = file_sections(&vcds)?;
+
+    let options = WriteOptions {
+        transform: Some(&invert_clk),
+        ..Default::default()
+    };
+    write_output(output.path(), headers, &vcds, sections, &options, |_| {})?;
+
+    // `a.clk` is inverted, but `b.clk` is kept as is.
+    let content = std::fs::read_to_string(output.path())?;
+    let data = content.split("$enddefinitions $end\n").nth(1).unwrap();
+    assert_eq!(data, "#5\n0!\n0\"\n#7\n1!\n1\"\n");
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::run;
+use predicates::prelude::*;
+
+#[test]
+fn truncated_change() -> Result<(), Box<dyn std::error::Error>> {
+    // the last change has a value, but no identifier.
+    for args in [
+        &["tests/truncated_change.vcd"][..],
+        &["tests/truncated_change.vcd", "--reorder"],
+    ] {
+        let (assert, content) = run(args)?;
+        assert.failure().code(6).stderr(predicate::str::contains(
+            "malformed value change \"1\" in tests/truncated_change.vcd",
+        ));
+        assert!(content.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn undeclared_identifier() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // `"` is not declared, `abcdef` is longer than any identifier, and `! ` has a trailing space.
+    for (change, symbol) in [("1\"", "\""), ("b1 abcdef", "abcdef"), ("1! ", "! ")] {
+        let input = dir.child("in.vcd");
+        input.write_str(&format!(
+            "$timescale 1 ns $end\n$scope module a $end\n$var wire 1 ! clk $end\n\
+             $upscope $end\n$enddefinitions $end\n#0\n0!\n#5\n{}\n",
+            change
+        ))?;
+
+        for reorder in [&[][..], &["--reorder"]] {
+            let (assert, content) = run(&[&[input.path().to_str().unwrap()], reorder].concat())?;
+            assert
+                .failure()
+                .code(6)
+                .stderr(predicate::str::contains(format!(
+                    "undeclared identifier {:?} in the value change {:?} in {}",
+                    symbol,
+                    change,
+                    input.path().display()
+                )));
+            assert!(content.is_empty());
+        }
+    }
+
+    Ok(())
+}
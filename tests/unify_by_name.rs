@@ -0,0 +1,55 @@
+mod common;
+
+use common::{data, merge, merge_data, run};
+use predicates::prelude::*;
+
+// `tests/recoded.vcd` uses `%` for `a.clk` and `!` for `a.rst`, while `tests/same_start1.vcd`
+// uses `!` for `a.clk`.
+const ARGS: [&str; 3] = [
+    "--unify-by-name",
+    "tests/same_start1.vcd",
+    "tests/recoded.vcd",
+];
+
+#[test]
+fn unify_by_name() -> Result<(), Box<dyn std::error::Error>> {
+    let content = merge(&ARGS)?;
+    let (header, _) = content.split_once("$enddefinitions $end\n").unwrap();
+    assert_eq!(header.matches("$var").count(), 2);
+    assert!(header.contains("$var wire 1 ! clk $end\n"));
+    assert!(header.contains("$var wire 1 \" rst $end\n"));
+    assert_eq!(data(&content), "#5\n1!\n#7\n0!\n#10\n0\"\n0!\n");
+    Ok(())
+}
+
+#[test]
+fn without_unify_by_name() -> Result<(), Box<dyn std::error::Error>> {
+    // each input declares its own `a.clk`.
+    let content = merge(&ARGS[1..])?;
+    let (header, _) = content.split_once("$enddefinitions $end\n").unwrap();
+    assert_eq!(header.matches("clk $end").count(), 2);
+    Ok(())
+}
+
+// `a.clk` is set to 1 at #5 by `tests/same_start1.vcd` and to 0 by `tests/conflict.vcd`.
+const CONFLICT: [&str; 3] = [
+    "--unify-by-name",
+    "tests/same_start1.vcd",
+    "tests/conflict.vcd",
+];
+
+#[test]
+fn unify_by_name_conflict() -> Result<(), Box<dyn std::error::Error>> {
+    let data = merge_data(&[&CONFLICT[..], &["--conflict", "first"]].concat())?;
+    assert_eq!(data, "#5\n1!\n#7\n0!\n0!\n");
+
+    let data = merge_data(&[&CONFLICT[..], &["--conflict", "last"]].concat())?;
+    assert_eq!(data, "#5\n1!\n0!\n#7\n0!\n0!\n");
+
+    let (assert, content) = run(&[&CONFLICT[..], &["--conflict", "error"]].concat())?;
+    assert!(content.is_empty());
+    assert.code(6).stderr(predicate::str::contains(
+        "conflicting changes of a.clk at 5: 1 in tests/same_start1.vcd, but 0 in tests/conflict.vcd",
+    ));
+    Ok(())
+}
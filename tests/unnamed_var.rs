@@ -0,0 +1,33 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::run;
+use predicates::prelude::*;
+
+#[test]
+fn unnamed_var() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let input = dir.child("in.vcd");
+    input.write_str(
+        "$timescale 1 ns $end\n\
+         $scope module a $end\n\
+         $var wire 1 ! clk $end\n\
+         $var wire 1 # $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n\
+         0!\n\
+         1#\n",
+    )?;
+
+    let (assert, content) = run(&[input.path()])?;
+    assert
+        .success()
+        .stderr(predicate::str::contains("$var without a name in"))
+        .stderr(predicate::str::contains("named unnamed_#"));
+
+    assert!(content.contains("$var wire 1 ! clk $end\n$var wire 1 \" unnamed_# $end\n"));
+    assert!(content.ends_with("#0\n0!\n1\"\n"));
+
+    Ok(())
+}
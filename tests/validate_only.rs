@@ -0,0 +1,51 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{run, vcd_merger};
+use predicates::prelude::*;
+use vcd_merger::{identifiers_used, validate};
+
+#[test]
+fn validate_clean() -> Result<(), Box<dyn std::error::Error>> {
+    vcd_merger()
+        .arg("--validate-only")
+        .arg("tests/nested.vcd")
+        .assert()
+        .success()
+        .stdout("tests/nested.vcd: ok\n");
+
+    Ok(())
+}
+
+#[test]
+fn validate_broken() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, content) = run(&["--validate-only", "tests/nested.vcd", "tests/broken.vcd"])?;
+    assert.failure().code(1).stdout(predicate::str::diff(
+        "\
+tests/nested.vcd: ok
+tests/broken.vcd: invalid timescale \"1 parsec\"
+tests/broken.vcd: 1 $scope without a matching $upscope
+tests/broken.vcd: line 12: identifier \"?\" is not declared
+tests/broken.vcd: line 14: malformed value change \"b0101\"
+tests/broken.vcd: line 15: timestamp 3 is before 5
+tests/broken.vcd: line 17: invalid timestamp \"#x\"
+",
+    ));
+
+    // the output is never written.
+    assert!(content.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn validate_keeps_identifiers() -> Result<(), Box<dyn std::error::Error>> {
+    let before = identifiers_used();
+
+    assert!(validate("tests/expected.vcd")?.is_empty());
+
+    // the identifiers of the validated file are not reserved for the ones generated later.
+    assert_eq!(identifiers_used(), before);
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::run;
+use predicates::prelude::*;
+
+#[test]
+fn warn_wide_ids() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // the identifiers of the existing file are kept, so new signals get identifiers after `~~~}`,
+    // which is past 90% of all 4 character identifiers.
+    let existing = dir.child("existing.vcd");
+    existing.write_str(
+        "$timescale 1 ns $end\n\
+         $scope module a $end\n\
+         $var wire 1 ~~~} clk $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n\
+         0~~~}\n",
+    )?;
+
+    let (assert, _) = run(&[
+        "--append",
+        existing.path().to_str().unwrap(),
+        "tests/same_start2.vcd",
+        "--warn-wide-ids",
+    ])?;
+    assert.success().stderr(predicate::str::contains(
+        "identifiers are used, merging more signals may run out of identifiers",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn warn_wide_ids_few_signals() -> Result<(), Box<dyn std::error::Error>> {
+    let (assert, _) = run(&["tests/test1.vcd", "tests/test2.vcd", "--warn-wide-ids"])?;
+    assert
+        .success()
+        .stderr(predicate::str::contains("identifiers are used").not());
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::merge_data;
+
+#[test]
+fn wide_bus() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("wide.vcd")?;
+
+    let values = ["01xz", "1100", "zx10"].map(|x| x.repeat(256));
+
+    let mut vcd = String::from(
+        "$timescale 1 ns $end\n\
+         $scope module top $end\n\
+         $var wire 1024 !@ bus $end\n\
+         $var real 64 \" temp $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n",
+    );
+    let mut expected = String::new();
+    for (i, value) in values.iter().enumerate() {
+        vcd += &format!("#{}\nb{} !@\nr{}.5 \"\n", i * 10, value, i);
+        expected += &format!("#{}\nb{} \"\nr{}.5 #\n", i * 10, value, i);
+        if i == 0 {
+            expected += "#5\n1!\n#7\n0!\n";
+        }
+    }
+    input.write_str(&vcd)?;
+
+    let data = merge_data(&[
+        "tests/same_start1.vcd",
+        input.path().to_str().unwrap(),
+        "--reorder",
+    ])?;
+    assert_eq!(data, expected);
+
+    Ok(())
+}
+
+#[test]
+fn wide_buses_with_same_identifiers() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    // both inputs use the same identifiers, which are remapped differently for each one.
+    let values = ["01xz", "1100", "zx10", "0000"].map(|x| x.repeat(256));
+    let header = "$timescale 1 ns $end\n\
+                  $scope module top $end\n\
+                  $var wire 1024 ! a $end\n\
+                  $var wire 1024 \" b $end\n\
+                  $upscope $end\n\
+                  $enddefinitions $end\n";
+    let mut first = String::from(header);
+    let mut second = String::from(header);
+    let mut expected = String::new();
+    for time in 0..8 {
+        let [a, b, c, d] = [0, 1, 2, 3].map(|i| &values[(time + i) % values.len()]);
+        first += &format!("#{}\nb{} !\nb{} \"\n", time * 2, a, b);
+        second += &format!("#{}\nb{} \"\nb{} !\n", time * 2, c, d);
+        expected += &format!("#{}\nb{} !\nb{} \"\nb{} $\nb{} #\n", time * 2, a, b, c, d);
+    }
+    let first_path = dir.child("first.vcd");
+    let second_path = dir.child("second.vcd");
+    first_path.write_str(&first)?;
+    second_path.write_str(&second)?;
+
+    let data = merge_data(&[
+        first_path.path().to_str().unwrap(),
+        second_path.path().to_str().unwrap(),
+        "--reorder",
+    ])?;
+    assert_eq!(data, expected);
+
+    Ok(())
+}
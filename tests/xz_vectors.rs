@@ -0,0 +1,26 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn xz_vectors() -> Result<(), Box<dyn std::error::Error>> {
+    let data = merge_data(&["tests/same_start1.vcd", "tests/xz_vectors.vcd", "--reorder"])?;
+    let expected = "\
+#0
+bxxxx \"
+bzzzz #
+#1
+bxz01 \"
+bzzz0 #
+#2
+b0101 \"
+bZX10 #
+#5
+1!
+#7
+0!
+";
+    assert_eq!(data, expected);
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+mod common;
+
+use common::merge_data;
+
+#[test]
+fn zero_timestamp() -> Result<(), Box<dyn std::error::Error>> {
+    let data = merge_data(&["tests/test1.vcd", "--reorder"])?;
+    assert!(data.starts_with("#0\n1!\n"));
+    assert!(!data.lines().any(|line| line == "#"));
+
+    Ok(())
+}